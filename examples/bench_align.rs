@@ -0,0 +1,35 @@
+//! Compare the throughput with and without RingFileBuilder::align() on large records.
+//!
+//! cargo run --release --example bench_align
+
+use ring_file::RingFileBuilder;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+const BUF_SIZE: i32 = 64 * 1024 * 1024;
+const RECORD: usize = 4096 + 100;
+const COUNT: usize = 200_000;
+
+fn bench(align: usize) {
+    let ring = RingFileBuilder::new(BUF_SIZE, Path::new("/tmp/ringfile_bench_align.store").into())
+        .inline(true)
+        .prefault(true)
+        .align(align)
+        .build()
+        .expect("build ok");
+    let record = vec![b'x'; RECORD];
+    let start = Instant::now();
+    for _ in 0..COUNT {
+        (&ring).write_all(&record).expect("write ok");
+    }
+    let elapsed = start.elapsed();
+    let mb = (RECORD * COUNT) as f64 / 1024.0 / 1024.0;
+    println!("align {:>4}: {:?}, {:.0} MB/s", align, elapsed, mb / elapsed.as_secs_f64());
+}
+
+fn main() {
+    for align in [0, 16, 64, 4096] {
+        bench(align);
+    }
+}
@@ -0,0 +1,34 @@
+//! Compare the cost of the binary uptime stamp with the text uptime prefix on the write path.
+//!
+//! cargo run --release --example bench_binary_uptime
+
+use ring_file::RingFileBuilder;
+use std::path::Path;
+use std::time::Instant;
+
+const COUNT: usize = 2_000_000;
+
+fn bench(name: &str, uptime: bool, binary_uptime: bool) {
+    let ring = RingFileBuilder::new(
+        64 * 1024 * 1024,
+        Path::new("/tmp/ringfile_bench_uptime.store").into(),
+    )
+    .inline(true)
+    .framed(true)
+    .uptime(uptime)
+    .binary_uptime(binary_uptime)
+    .build()
+    .expect("build ok");
+    let start = Instant::now();
+    for _ in 0..COUNT {
+        ring.write("message of a typical length\n".to_string());
+    }
+    let elapsed = start.elapsed();
+    println!("{:>8}: {:?}, {:.0} ns/msg", name, elapsed, elapsed.as_nanos() as f64 / COUNT as f64);
+}
+
+fn main() {
+    bench("none", false, false);
+    bench("text", true, false);
+    bench("binary", false, true);
+}
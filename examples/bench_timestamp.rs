@@ -0,0 +1,60 @@
+//! Compare the cost of the compact timestamp prefix with formatting a calendar date per message.
+//!
+//! cargo run --release --example bench_timestamp
+
+use ring_file::RingFileBuilder;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const COUNT: usize = 2_000_000;
+
+/// What a caller would do without the compact prefix.
+fn rfc3339(time: SystemTime) -> String {
+    let d = time.duration_since(UNIX_EPOCH).unwrap();
+    let secs = d.as_secs();
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60,
+        d.subsec_micros()
+    )
+}
+
+fn bench(name: &str, timestamps: bool, caller_format: bool) {
+    let ring =
+        RingFileBuilder::new(64 * 1024 * 1024, Path::new("/tmp/ringfile_bench_ts.store").into())
+            .inline(true)
+            .timestamps(timestamps)
+            .build()
+            .expect("build ok");
+    let start = Instant::now();
+    for i in 0..COUNT {
+        if caller_format {
+            ring.write(format!("[{}] message {}\n", rfc3339(SystemTime::now()), i));
+        } else {
+            ring.write(format!("message {}\n", i));
+        }
+    }
+    let elapsed = start.elapsed();
+    println!("{:>8}: {:?}, {:.0} ns/msg", name, elapsed, elapsed.as_nanos() as f64 / COUNT as f64);
+}
+
+fn main() {
+    bench("none", false, false);
+    bench("compact", true, false);
+    bench("rfc3339", false, true);
+}
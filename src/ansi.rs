@@ -0,0 +1,76 @@
+use std::io::{Result, Write};
+
+const ESC: u8 = 0x1b;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Text,
+    /// After ESC
+    Escape,
+    /// Inside `ESC [`, until the final byte
+    Csi,
+}
+
+/// Remove the CSI escape sequences (including the SGR colors like `ESC[1;31m`) from the content
+/// written through, see [RingFileBuilder::strip_ansi()](crate::RingFileBuilder::strip_ansi()).
+///
+/// The state is kept between write() calls, so a sequence split across the wrap seam
+/// is removed as well. Other escapes are passed through.
+pub(crate) struct StripAnsi<W: Write> {
+    inner: W,
+    state: State,
+}
+
+impl<W: Write> StripAnsi<W> {
+    #[inline]
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, state: State::Text }
+    }
+}
+
+impl<W: Write> Write for StripAnsi<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        // Start of the text run to pass through, valid in State::Text
+        let mut start = 0;
+        for (i, b) in buf.iter().enumerate() {
+            match (self.state, *b) {
+                (State::Text, ESC) => {
+                    self.inner.write_all(&buf[start..i])?;
+                    self.state = State::Escape;
+                }
+                (State::Text, _) => {}
+                (State::Escape, b'[') => self.state = State::Csi,
+                (State::Escape, b) => {
+                    // Not CSI, keep the ESC
+                    self.inner.write_all(&[ESC])?;
+                    if b != ESC {
+                        self.state = State::Text;
+                        start = i;
+                    }
+                }
+                // Parameter and intermediate bytes
+                (State::Csi, 0x20..=0x3f) => {}
+                // Final byte
+                (State::Csi, 0x40..=0x7e) => {
+                    self.state = State::Text;
+                    start = i + 1;
+                }
+                (State::Csi, ESC) => self.state = State::Escape,
+                // Malformed, keep the byte, e.g. a newline
+                (State::Csi, _) => {
+                    self.state = State::Text;
+                    start = i;
+                }
+            }
+        }
+        if self.state == State::Text {
+            self.inner.write_all(&buf[start..])?;
+        }
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
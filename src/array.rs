@@ -0,0 +1,208 @@
+use crate::buffer::{copy_segments, dump_file, Cursor, DEFAULT_MODE};
+use crate::PartialDump;
+use std::io::{Result, Write};
+use std::path::Path;
+
+/// Same as [RingBuffer](crate::RingBuffer), but over a storage `S` given by the caller instead
+/// of an allocated buffer, e.g. [ArrayRingBuffer] or [RingBufferRef].
+///
+/// The ordering and the rewinding are the same code as [RingBuffer](crate::RingBuffer),
+/// the capacity is the length of the storage. The bytes of the storage before the first write
+/// are ignored. Only the plain byte stream is supported, without no_split or framed mode.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::{FixedRingBuffer, RingBuffer};
+/// use std::io::Write;
+/// let mut boxed = FixedRingBuffer::from_storage(vec![0u8; 64].into_boxed_slice());
+/// let mut heap = RingBuffer::new(64);
+/// for i in 0..100 {
+///     let record = format!("{}\n", i);
+///     boxed.write_all(record.as_bytes()).expect("write ok");
+///     heap.write_all(record.as_bytes()).expect("write ok");
+/// }
+/// assert_eq!(boxed.capacity(), 64);
+/// assert_eq!(boxed.segments(), heap.segments());
+/// ```
+pub struct FixedRingBuffer<S: AsRef<[u8]> + AsMut<[u8]>> {
+    cur: Cursor,
+    /// Logical offset of the end, the total bytes ever written
+    written: u64,
+    inner: S,
+}
+
+/// Same as [RingBuffer](crate::RingBuffer), but the capacity `N` is known at compile time and
+/// the content is an inline array, so it can live on the stack or in a static without the
+/// allocator, e.g. on a tiny embedded target. A [FixedRingBuffer] over an inline array.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::{ArrayRingBuffer, RingBuffer};
+/// use std::io::Write;
+/// let mut array = ArrayRingBuffer::<64>::new();
+/// let mut heap = RingBuffer::new(64);
+/// let mut seed = 7usize;
+/// for i in 0..200 {
+///     seed = seed * 31 % 97;
+///     let record = format!("{}:{}\n", i, "x".repeat(seed % 40));
+///     array.write_all(record.as_bytes()).expect("write ok");
+///     heap.write_all(record.as_bytes()).expect("write ok");
+///     assert_eq!(array.segments(), heap.segments());
+/// }
+/// assert_eq!(array.len(), heap.len());
+/// assert_eq!(array.total_written(), heap.total_written());
+/// let mut dst = [0u8; 10];
+/// assert_eq!(array.copy_into(&mut dst), heap.copy_into(&mut [0u8; 10]));
+/// assert_eq!(&dst[..], &heap.read_to_vec()[54..]);
+/// array.clear();
+/// assert!(array.is_empty());
+/// ```
+pub type ArrayRingBuffer<const N: usize> = FixedRingBuffer<[u8; N]>;
+
+/// Same as [RingBuffer](crate::RingBuffer), but the content lives in a slice borrowed from the
+/// caller, e.g. a region of an arena, a pool or shared memory, so the ring does no allocation.
+/// Created by [RingBuffer::from_slice()](crate::RingBuffer::from_slice()), a [FixedRingBuffer]
+/// over the slice.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::RingBuffer;
+/// use std::io::Write;
+/// let mut arena = vec![0xffu8; 256];
+/// let (_, region) = arena.split_at_mut(192);
+/// let mut borrowed = RingBuffer::from_slice(region);
+/// let mut owned = RingBuffer::new(64);
+/// let mut seed = 7usize;
+/// for i in 0..200 {
+///     seed = seed * 31 % 97;
+///     let record = format!("{}:{}\n", i, "x".repeat(seed % 40));
+///     assert_eq!(borrowed.write(record.as_bytes()).unwrap(), owned.write(record.as_bytes()).unwrap());
+///     assert_eq!(borrowed.segments(), owned.segments());
+/// }
+/// assert_eq!(borrowed.capacity(), 64);
+/// assert_eq!(borrowed.total_written(), owned.total_written());
+/// assert_eq!(borrowed.read_to_vec(), owned.read_to_vec());
+/// borrowed.dump("/tmp/ringfile_borrowed.store").expect("dump ok");
+/// owned.dump("/tmp/ringfile_owned.store").expect("dump ok");
+/// assert_eq!(
+///     std::fs::read("/tmp/ringfile_borrowed.store").unwrap(),
+///     std::fs::read("/tmp/ringfile_owned.store").unwrap()
+/// );
+/// borrowed.clear();
+/// assert!(borrowed.is_empty());
+/// drop(borrowed);
+/// // The memory outside of the region is untouched
+/// assert!(arena[..192].iter().all(|b| *b == 0xff));
+/// ```
+pub type RingBufferRef<'a> = FixedRingBuffer<&'a mut [u8]>;
+
+impl<const N: usize> ArrayRingBuffer<N> {
+    /// Panics when `N` is 0.
+    pub const fn new() -> Self {
+        assert!(N > 0, "capacity should not be 0");
+        Self { cur: Cursor::new(N), written: 0, inner: [0; N] }
+    }
+}
+
+impl<const N: usize> Default for ArrayRingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> FixedRingBuffer<S> {
+    /// Panics when `storage` is empty.
+    pub fn from_storage(storage: S) -> Self {
+        let capacity = storage.as_ref().len();
+        assert!(capacity > 0, "storage should not be empty");
+        Self { cur: Cursor::new(capacity), written: 0, inner: storage }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.inner.as_ref().len()
+    }
+
+    /// Return the content in order as two slices, see [RingBuffer::segments()](crate::RingBuffer::segments()).
+    #[inline]
+    pub fn segments(&self) -> (&[u8], &[u8]) {
+        self.cur.split(self.inner.as_ref())
+    }
+
+    /// Return the bytes of content currently kept.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let (head, tail) = self.segments();
+        head.len() + tail.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total bytes ever written, see
+    /// [RingBuffer::total_written()](crate::RingBuffer::total_written()).
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Copy the content into `dst` in order without allocation, return the bytes copied.
+    ///
+    /// When `dst` is smaller than the content, only the newest bytes are copied.
+    pub fn copy_into(&self, dst: &mut [u8]) -> usize {
+        let (head, tail) = self.segments();
+        copy_segments(head, tail, dst)
+    }
+
+    /// Copy the content in order into a new Vec.
+    pub fn read_to_vec(&self) -> Vec<u8> {
+        let (head, tail) = self.segments();
+        let mut v = Vec::with_capacity(head.len() + tail.len());
+        v.extend_from_slice(head);
+        v.extend_from_slice(tail);
+        v
+    }
+
+    /// Discard the content, [FixedRingBuffer::total_written()] is kept.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.cur = Cursor::new(self.capacity());
+    }
+
+    /// Write the content in order to `writer`.
+    pub fn dump_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let (head, tail) = self.segments();
+        writer.write_all(head)?;
+        writer.write_all(tail)
+    }
+
+    /// Will create a truncated file and write all data from mem to disk.
+    pub fn dump<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
+        dump_file(file_path.as_ref(), PartialDump::Keep, DEFAULT_MODE, |file| self.dump_to(file))
+    }
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Write for FixedRingBuffer<S> {
+    /// Same as [RingBuffer](crate::RingBuffer), write will abort when reaching the boundary of
+    /// buffer, rewind the offset to 0 and return the bytes written.
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let (offset, l) = self.cur.advance_raw(buf.len(), self.capacity());
+        self.inner.as_mut()[offset..offset + l].copy_from_slice(&buf[0..l]);
+        self.written += l as u64;
+        Ok(l)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Shared by the writers and the backend to account the bytes and messages queued in the
+/// channel, see [RingFile::queued_bytes()](crate::RingFile::queued_bytes()) and
+/// [RingFile::queue_len()](crate::RingFile::queue_len()).
+pub(crate) struct Backlog {
+    bytes: AtomicUsize,
+    messages: AtomicUsize,
+    /// The most messages queued since the start or the last reset
+    peak: AtomicUsize,
+    /// 0 for unlimited
+    max: usize,
+    dropped: AtomicU64,
+}
+
+impl Backlog {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            bytes: AtomicUsize::new(0),
+            messages: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            max,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Called by the writer before sending `len` bytes, return false when the message should be dropped.
+    #[inline]
+    pub(crate) fn admit(&self, len: usize) -> bool {
+        let queued = self.bytes.fetch_add(len, Ordering::Relaxed) + len;
+        if self.max > 0 && queued > self.max {
+            self.bytes.fetch_sub(len, Ordering::Relaxed);
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        let messages = self.messages.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak.fetch_max(messages, Ordering::Relaxed);
+        true
+    }
+
+    /// Called by the backend after a message of `len` bytes is taken out of the channel,
+    /// or by the writer when the send fails.
+    #[inline]
+    pub(crate) fn done(&self, len: usize) {
+        self.bytes.fetch_sub(len, Ordering::Relaxed);
+        self.messages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn messages(&self) -> usize {
+        self.messages.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Restart the peak from the messages queued now.
+    #[inline]
+    pub(crate) fn reset_peak(&self) {
+        self.peak.store(self.messages(), Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -1,7 +1,20 @@
+use crate::frame;
+use crate::record::{Record, Records};
+use crate::sink::{DumpSink, RecordMeta};
+use crate::timestamp::{parse_timestamp, render_stamp};
+use crate::{FixedRingBuffer, RingBufferRef};
 use io_buffer::{safe_copy, Buffer};
+use std::borrow::Cow;
 use std::fs::*;
-use std::io::{Result, Write};
-use std::path::Path;
+use std::io::{BufWriter, Error, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const PAGE_SIZE: usize = 4096;
+
+/// Bytes per write of [RingBuffer::dump_with_budget()], between the checks of the budget
+const BUDGET_CHUNK: usize = 64 * 1024;
 
 /// The content is kept in memory when written, when offset rewinds, new content will overwrite old content,
 /// So that memory consumption is limited to buf_size.
@@ -18,55 +31,2306 @@ use std::path::Path;
 /// file.dump("/tmp/ringfile.store").expect("dump ok");
 /// ```
 pub struct RingBuffer {
+    cur: Cursor,
+    no_split: usize,
+    /// Logical offset of the end, the total bytes ever written
+    written: u64,
+    /// Content before this logical offset is discarded
+    floor: u64,
+    partial_dump: PartialDump,
+    /// Permissions of the dump file on unix
+    dump_mode: u32,
+    /// Store each write() as a length-prefixed record
+    framed: bool,
+    /// Logical offset of the oldest whole record, in framed mode
+    first_record: u64,
+    /// Bytes at the start of inner protected from the wrapping, see set_reserved_prefix()
+    reserved: usize,
+    /// Length of the preamble in the reserved bytes, None until written
+    preamble: Option<usize>,
+    /// Each record starts with the stamp of RingFileBuilder::binary_uptime(), rendered on read
+    binary_uptime: bool,
+    /// Byte of the region not yet written, see set_fill()
+    fill: Option<u8>,
+    /// Discard the writes up to the next newline, see clear_to_record_boundary()
+    resync: bool,
+    inner: Buffer,
+}
+
+/// What to do with the target file when a dump fails after it is opened, e.g. on disk full,
+/// see [RingBuffer::set_partial_dump()].
+///
+/// The error returned states how many bytes were written before the failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartialDump {
+    /// Leave the truncated file as is (default)
+    Keep,
+    /// Delete the partial file
+    Remove,
+    /// Write into `{path}.tmp` and rename it over the target on success,
+    /// so that the previous good dump stays intact on failure.
+    KeepPrevious,
+}
+
+// RingBuffer is Send + Sync, because io_buffer::Buffer owns its memory exclusively
+// (alloc with malloc, without interior mutability). Shared reads through &RingBuffer are safe.
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    #[allow(dead_code)]
+    fn check() {
+        assert_send_sync::<Buffer>();
+        assert_send_sync::<RingBuffer>();
+    }
+};
+
+/// Position of the write cursor.
+#[derive(Clone, Copy)]
+pub(crate) struct Cursor {
     end: usize,
+    /// Whether the offset has rewinded
     full: bool,
-    inner: Buffer,
+    /// The content of the previous round ends at `limit` when full.
+    /// Normally equals to capacity, unless rewinded earlier by the no_split option.
+    limit: usize,
+    /// Times the offset rewinded to 0, kept by clear()
+    wraps: u64,
+    /// Times the cursor was found out of the buffer and reset, kept by clear()
+    repairs: u64,
+}
+
+/// The ordering logic on a buffer of `bound` bytes, shared by [RingBuffer],
+/// and [FixedRingBuffer](crate::FixedRingBuffer).
+impl Cursor {
+    #[inline(always)]
+    pub(crate) const fn new(capacity: usize) -> Self {
+        Self { end: 0, full: false, limit: capacity, wraps: 0, repairs: 0 }
+    }
+
+    /// Split `data` into the content in order, as two slices.
+    ///
+    /// A cursor out of `data`, which is a bug, is clamped rather than panic.
+    #[inline(always)]
+    pub(crate) fn split<'a>(&self, data: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        let Cursor { end, full, limit, .. } = *self;
+        debug_assert!(
+            cfg!(feature = "test-util") || (end <= limit && limit <= data.len()),
+            "cursor end {} limit {} out of the buffer of {}",
+            end,
+            limit,
+            data.len()
+        );
+        let limit = limit.min(data.len());
+        let end = end.min(limit);
+        if !full {
+            (&data[0..end], &[][..])
+        } else if end < limit {
+            (&data[end..limit], &data[0..end])
+        } else {
+            (&data[0..end], &[][..])
+        }
+    }
+
+    /// Reset a cursor out of `bound` before moving it, see [Stats::repairs].
+    #[inline(always)]
+    fn check(&mut self, bound: usize) {
+        if self.end >= bound || self.limit > bound {
+            self.repair(bound);
+        }
+    }
+
+    /// Rewind to 0 as if the content of the previous round ended at `bound`.
+    #[cold]
+    fn repair(&mut self, bound: usize) {
+        #[cfg(not(feature = "test-util"))]
+        debug_assert!(
+            self.end < bound && self.limit <= bound,
+            "cursor end {} limit {} out of the buffer of {}",
+            self.end,
+            self.limit,
+            bound
+        );
+        self.end = 0;
+        self.full = true;
+        self.limit = bound;
+        self.wraps += 1;
+        self.repairs += 1;
+    }
+
+    /// Rewind early for the no_split option, before writing a record of `l` bytes.
+    #[inline(always)]
+    pub(crate) fn keep_contiguous(&mut self, l: usize, no_split: usize, bound: usize) {
+        self.check(bound);
+        if l <= no_split && l < bound && self.end + l > bound {
+            self.limit = self.end;
+            self.end = 0;
+            self.full = true;
+            self.wraps += 1;
+        }
+    }
+
+    /// Move for writing `l` bytes up to the end of buffer, return the offset and bytes to copy.
+    #[inline(always)]
+    pub(crate) fn advance_raw(&mut self, l: usize, bound: usize) -> (usize, usize) {
+        self.check(bound);
+        let offset = self.end;
+        if offset + l >= bound {
+            self.full = true;
+            self.end = 0;
+            self.limit = bound;
+            self.wraps += 1;
+            (offset, bound - offset)
+        } else {
+            self.end += l;
+            (offset, l)
+        }
+    }
+}
+
+/// The counters of a [RingBuffer] taken at once, see [RingBuffer::stats()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    /// Same as [RingBuffer::len()]
+    pub len: usize,
+    /// Same as [RingBuffer::capacity()]
+    pub capacity: usize,
+    /// Same as [RingBuffer::total_written()]
+    pub total_written: u64,
+    /// Same as [RingBuffer::earliest_retained_offset()]
+    pub earliest_retained_offset: u64,
+    /// Times the write offset rewinded to the start of the buffer
+    pub wraps: u64,
+    /// Times the write cursor was found out of the buffer and reset to the start, instead of
+    /// a panic on the slicing. Always 0 unless there is a bug, kept by clear().
+    pub repairs: u64,
 }
 
 impl RingBuffer {
     /// Allocate a whole buffer specified by `buf_size`, size can not exceed 2GB.
     pub fn new(buf_size: i32) -> Self {
+        Self::alloc(buf_size).expect("alloc")
+    }
+
+    /// A ring over `backing` borrowed from the caller, without allocation, see [RingBufferRef].
+    ///
+    /// Panics when `backing` is empty.
+    #[inline]
+    pub fn from_slice(backing: &mut [u8]) -> RingBufferRef<'_> {
+        FixedRingBuffer::from_storage(backing)
+    }
+
+    /// Allocate a buffer of `buf_size` holding `snapshot`, the content as given by
+    /// [RingBuffer::read_to_vec()] or [RingBufferView::snapshot()](crate::RingBufferView::snapshot()).
+    ///
+    /// Only the newest `buf_size` bytes are kept when the snapshot is larger.
+    /// Other settings are the defaults of [RingBuffer::new()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"first\nsecond\n").expect("write ok");
+    /// let mut replay = RingBuffer::from_snapshot(16, &buf.read_to_vec()).expect("alloc ok");
+    /// replay.write_all(b"third\n").expect("write ok");
+    /// buf.write_all(b"third\n").expect("write ok");
+    /// assert_eq!(replay.read_to_vec(), buf.read_to_vec());
+    /// ```
+    pub fn from_snapshot(buf_size: i32, snapshot: &[u8]) -> Result<Self> {
+        let mut buffer = Self::alloc(buf_size)?;
+        buffer.write_all(snapshot)?;
+        Ok(buffer)
+    }
+
+    pub(crate) fn alloc(buf_size: i32) -> Result<Self> {
         assert!(buf_size > 0);
-        let inner = Buffer::alloc(buf_size).expect("alloc");
-        Self { end: 0, inner, full: false }
+        let inner = Buffer::alloc(buf_size).map_err(Error::from)?;
+        Ok(Self {
+            cur: Cursor::new(inner.capacity()),
+            no_split: 0,
+            written: 0,
+            floor: 0,
+            partial_dump: PartialDump::Keep,
+            dump_mode: DEFAULT_MODE,
+            framed: false,
+            first_record: 0,
+            reserved: 0,
+            preamble: None,
+            binary_uptime: false,
+            fill: None,
+            resync: false,
+            inner,
+        })
+    }
+
+    /// Keep the record (a single write() call) no longer than `max_record` contiguous.
+    /// (default 0, disabled)
+    ///
+    /// When such a record does not fit in the remaining tail, the offset rewinds to 0 before writing,
+    /// so it will not straddle the end of buffer. The cost is a gap in the tail unused for this round,
+    /// which is not included in the dump. A record exactly filling the tail is not affected.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(32);
+    /// buf.set_no_split(16);
+    /// for i in 0..5 {
+    ///     buf.write_all(format!("record {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let (head, tail) = buf.segments();
+    /// assert_eq!(head, b"record 2\n");
+    /// assert_eq!(tail, b"record 3\nrecord 4\n");
+    /// ```
+    pub fn set_no_split(&mut self, max_record: usize) {
+        self.no_split = max_record;
+    }
+
+    #[inline]
+    pub(crate) fn no_split(&self) -> usize {
+        self.no_split
+    }
+
+    #[inline]
+    pub(crate) fn partial_dump(&self) -> PartialDump {
+        self.partial_dump
+    }
+
+    #[inline]
+    pub(crate) fn dump_mode(&self) -> u32 {
+        self.dump_mode
+    }
+
+    /// Store each write() call as a record prefixed with its length, instead of a plain byte stream.
+    /// (default false) The content already written is discarded.
+    ///
+    /// The prefix gives the exact record boundary, so a payload with embedded newlines stays one item
+    /// of [RingBuffer::lines()], and a record partially overwritten is skipped as a whole.
+    /// [RingBuffer::dump()], [RingBuffer::dump_to()] and [RingBuffer::dump_filtered()] write the
+    /// payloads without prefix, reproducing the bytes written, while [RingBuffer::segments()] and
+    /// [RingBuffer::read_to_vec()] still give the raw storage with the prefixes.
+    ///
+    /// The overhead is the LEB128 length: 1 byte per record below 128 bytes, 2 bytes below 16KB,
+    /// 3 bytes below 2MB. A record larger than the capacity only keeps its newest bytes.
+    /// Use write_all() to write a record, every write() call takes the whole slice.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(32);
+    /// buf.set_framed(true);
+    /// for i in 0..4 {
+    ///     buf.write_all(format!("record {}\nline 2", i).as_bytes()).expect("write ok");
+    /// }
+    /// // Two records of 1 + 15 bytes are kept
+    /// assert_eq!(buf.len(), 32);
+    /// let records: Vec<Vec<u8>> = buf.lines().map(|r| r.into_owned()).collect();
+    /// assert_eq!(records, vec![b"record 2\nline 2".to_vec(), b"record 3\nline 2".to_vec()]);
+    /// let mut out = Vec::new();
+    /// buf.dump_to(&mut out).expect("dump ok");
+    /// assert_eq!(out, b"record 2\nline 2record 3\nline 2");
+    /// assert_eq!(buf.dump_size(), out.len());
+    /// // Partially overwrite the oldest record, which is skipped
+    /// buf.write_all(b"tiny").expect("write ok");
+    /// assert_eq!(buf.lines().count(), 2);
+    /// assert_eq!(&*buf.lines().next().unwrap(), b"record 3\nline 2");
+    /// ```
+    pub fn set_framed(&mut self, framed: bool) {
+        self.clear();
+        self.framed = framed;
+    }
+
+    #[inline]
+    pub(crate) fn framed(&self) -> bool {
+        self.framed
+    }
+
+    /// Render the leading stamp of every framed record as the uptime prefix on read,
+    /// see [RingFileBuilder::binary_uptime()](crate::RingFileBuilder::binary_uptime()).
+    #[inline]
+    pub(crate) fn set_binary_uptime(&mut self, binary_uptime: bool) {
+        self.binary_uptime = binary_uptime;
+    }
+
+    #[inline]
+    pub(crate) fn binary_uptime(&self) -> bool {
+        self.binary_uptime
+    }
+
+    /// Touch every page not yet written, so that the memory is committed up front,
+    /// instead of taking page faults during the first round of writes.
+    ///
+    /// After this call, RSS grows to the whole buf_size immediately.
+    /// Content already written is not affected.
+    pub fn prefault(&mut self) {
+        if self.cur.full {
+            return;
+        }
+        let mut offset = self.cur.end;
+        let bound = self.capacity();
+        while offset < bound {
+            self.inner[self.reserved + offset] = self.fill.unwrap_or(0);
+            offset = (offset / PAGE_SIZE + 1) * PAGE_SIZE;
+        }
+    }
+
+    /// Fill the region not yet written with the byte `fill`, now and after every
+    /// [RingBuffer::clear()], so that the end of the content is easy to spot in
+    /// [RingBuffer::dump_raw()]. (default None, the memory is left as allocated)
+    ///
+    /// The dumps only write the content up to the end, the fill never appears in them.
+    /// Like [RingBuffer::prefault()], this touches the whole buf_size.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let (path, raw) = ("/tmp/ringfile_fill.store", "/tmp/ringfile_fill.raw");
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.set_fill(Some(b'.'));
+    /// buf.write_all(b"hello\n").expect("write ok");
+    /// buf.dump(path).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"hello\n");
+    /// buf.dump_raw(raw).expect("dump ok");
+    /// assert_eq!(std::fs::read(raw).unwrap(), b"hello\n..........");
+    /// buf.write_all(b"0123456789abcdef").expect("write ok");
+    /// buf.dump_raw(raw).expect("dump ok");
+    /// assert_eq!(std::fs::read(raw).unwrap(), b"abcdef0123456789");
+    /// buf.clear();
+    /// buf.write_all(b"again\n").expect("write ok");
+    /// buf.dump_raw(raw).expect("dump ok");
+    /// assert_eq!(std::fs::read(raw).unwrap(), b"again\n..........");
+    /// ```
+    pub fn set_fill(&mut self, fill: Option<u8>) {
+        self.fill = fill;
+        self.fill_unused();
+    }
+
+    /// Write the fill into the region not yet written.
+    fn fill_unused(&mut self) {
+        let Some(fill) = self.fill else {
+            return;
+        };
+        if !self.cur.full {
+            let start = (self.reserved + self.cur.end).min(self.inner.len());
+            self.inner[start..].fill(fill);
+        }
+    }
+
+    /// Set what [RingBuffer::dump()] does with the file when the write fails midway.
+    /// (default [PartialDump::Keep])
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::{PartialDump, RingBuffer};
+    /// use std::io::Write;
+    /// let path = "/tmp/ringfile_partial.store";
+    /// let mut buf = RingBuffer::new(1024);
+    /// buf.set_partial_dump(PartialDump::KeepPrevious);
+    /// buf.write_all(b"log message").expect("write ok");
+    /// buf.dump(path).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"log message");
+    /// assert!(!std::path::Path::new("/tmp/ringfile_partial.store.tmp").exists());
+    /// ```
+    pub fn set_partial_dump(&mut self, partial_dump: PartialDump) {
+        self.partial_dump = partial_dump;
+    }
+
+    /// Set the permissions of the file created by [RingBuffer::dump()] and the other dumps,
+    /// as the dump may contain sensitive data. (default 0o600)
+    ///
+    /// The mode is applied with `OpenOptionsExt::mode()` (then masked by the umask), so only
+    /// when the file is created: an existing file keeps its permissions.
+    /// Accepted and ignored on non-unix platforms.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let path = "/tmp/ringfile_mode.store";
+    /// let mut buf = RingBuffer::new(1024);
+    /// buf.write_all(b"secret").expect("write ok");
+    /// for mode in [0o600, 0o640].iter() {
+    ///     let _ = std::fs::remove_file(path);
+    ///     buf.set_dump_mode(*mode);
+    ///     buf.dump(path).expect("dump ok");
+    ///     #[cfg(unix)]
+    ///     {
+    ///         use std::os::unix::fs::PermissionsExt;
+    ///         let perm = std::fs::metadata(path).unwrap().permissions();
+    ///         assert_eq!(perm.mode() & 0o777, *mode);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_dump_mode(&mut self, mode: u32) {
+        self.dump_mode = mode;
     }
 
     /// Will create a truncated file and write all data from mem to disk.
+    ///
+    /// On failure after the file is opened, the file is handled by [RingBuffer::set_partial_dump()].
     pub fn dump<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
-        let mut file =
-            OpenOptions::new().write(true).create(true).truncate(true).open(file_path.as_ref())?;
-        if self.full {
-            file.write_all(&self.inner[self.end..])?;
-            return file.write_all(&self.inner[0..self.end]);
+        dump_file(file_path.as_ref(), self.partial_dump, self.dump_mode, |file| {
+            self.for_each_chunk(|chunk| file.write_all(chunk))
+        })
+    }
+
+    /// Write the whole physical buffer into a truncated file, for low-level debugging.
+    ///
+    /// Unlike [RingBuffer::dump()], which writes the content from the oldest to the newest up to
+    /// the end, this is the memory as is: buf_size bytes from offset 0, so the content after the
+    /// rewinding comes first, followed by the older one. It includes the region not yet written
+    /// (see [RingBuffer::set_fill()]), the bytes cleared or skipped by [RingBuffer::set_no_split()],
+    /// the length prefixes of [RingBuffer::set_framed()] and the reserved prefix of
+    /// [RingBuffer::set_reserved_prefix()].
+    pub fn dump_raw<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
+        let mut file = create_file(file_path.as_ref(), self.dump_mode)?;
+        file.write_all(&self.inner[..])
+    }
+
+    /// Same as [RingBuffer::dump()], but across the files `{prefix}.part000`, `{prefix}.part001`,
+    /// ... of at most `part_size` bytes each, e.g. for the part limit of an object storage upload.
+    /// Return the files written in order, at least one.
+    ///
+    /// The split is byte-accurate, concatenating the parts reproduces the dump exactly.
+    /// Returns error of `ErrorKind::InvalidInput` when `part_size` is 0.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(100);
+    /// for i in 0..30 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let parts = buf.dump_split("/tmp/ringfile_split.store", 32).expect("dump ok");
+    /// assert_eq!(parts.len(), 4);
+    /// assert_eq!(parts[0].to_str().unwrap(), "/tmp/ringfile_split.store.part000");
+    /// let mut joined = Vec::new();
+    /// for part in parts.iter() {
+    ///     let content = std::fs::read(part).unwrap();
+    ///     assert!(content.len() <= 32);
+    ///     joined.extend_from_slice(&content);
+    /// }
+    /// buf.dump("/tmp/ringfile_split.store").expect("dump ok");
+    /// assert_eq!(joined, std::fs::read("/tmp/ringfile_split.store").unwrap());
+    /// ```
+    pub fn dump_split<P: AsRef<Path>>(&self, prefix: P, part_size: usize) -> Result<Vec<PathBuf>> {
+        let mut parts = SplitWriter::new(prefix.as_ref(), part_size, self.dump_mode)?;
+        self.for_each_chunk(|chunk| parts.write_all(chunk))?;
+        parts.finish()
+    }
+
+    /// Call `f` with the content to dump in order, the payloads in framed mode.
+    #[inline]
+    fn for_each_chunk<F: FnMut(&[u8]) -> Result<()>>(&self, mut f: F) -> Result<()> {
+        if self.preamble.is_some() {
+            f(self.preamble())?;
+        }
+        if self.framed {
+            for record in self.lines() {
+                f(&record)?;
+            }
+            Ok(())
+        } else {
+            let (head, tail) = self.segments();
+            f(head)?;
+            f(tail)
+        }
+    }
+
+    /// Same as [RingBuffer::dump()], but do not create the file when the buffer is empty.
+    ///
+    /// Return Ok(false) when nothing is dumped.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_nonempty.store");
+    /// let _ = std::fs::remove_file(path);
+    /// let mut buf = RingBuffer::new(1024);
+    /// assert!(!buf.dump_if_nonempty(path).unwrap());
+    /// assert!(!path.exists());
+    /// buf.write_all(b"log message").expect("write ok");
+    /// assert!(buf.dump_if_nonempty(path).unwrap());
+    /// assert_eq!(std::fs::read(path).unwrap(), b"log message");
+    /// ```
+    pub fn dump_if_nonempty<P: AsRef<Path>>(&self, file_path: P) -> Result<bool> {
+        if self.is_empty() {
+            return Ok(false);
+        }
+        self.dump(file_path)?;
+        Ok(true)
+    }
+
+    /// Write all data from mem to `writer`, from the oldest to the newest.
+    ///
+    /// On failure, the error states how many bytes were written before.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// // Fails once 4 bytes are written
+    /// struct Failing(usize);
+    /// impl Write for Failing {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         if self.0 == 0 {
+    ///             return Err(std::io::Error::new(std::io::ErrorKind::Other, "disk full"));
+    ///         }
+    ///         let l = buf.len().min(self.0);
+    ///         self.0 -= l;
+    ///         Ok(l)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    /// let mut buf = RingBuffer::new(1024);
+    /// buf.write_all(b"log message").expect("write ok");
+    /// let e = buf.dump_to(&mut Failing(4)).unwrap_err();
+    /// assert_eq!(e.kind(), std::io::ErrorKind::Other);
+    /// assert_eq!(e.to_string(), "dump failed after 4 bytes: disk full");
+    /// ```
+    pub fn dump_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = Counter::new(writer);
+        self.for_each_chunk(|chunk| writer.write_all(chunk)).map_err(|e| writer.failed(e))
+    }
+
+    /// Same as [RingBuffer::dump()], but stop writing once `max` has elapsed, e.g. when the disk
+    /// is about to hang on the very failure being investigated.
+    /// Return the bytes written, and true if the budget ran out before the whole content.
+    ///
+    /// The content is written backward by chunks of 64KB at their final offsets, so the newest
+    /// bytes land first. On timeout the file keeps its full size, and the front not written
+    /// reads as zero bytes. The budget is checked between the chunks, so a single blocked
+    /// write is not interrupted, and the newest chunk is always written.
+    /// The file is not handled by [RingBuffer::set_partial_dump()].
+    pub fn dump_with_budget<P: AsRef<Path>>(
+        &self, file_path: P, max: Duration,
+    ) -> Result<(usize, bool)> {
+        let mut file = create_file(file_path.as_ref(), self.dump_mode)?;
+        self.dump_to_with_budget(&mut file, max)
+    }
+
+    /// Same as [RingBuffer::dump_with_budget()], into `writer` which is seekable.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::{Cursor, Seek, SeekFrom, Write};
+    /// use std::time::Duration;
+    /// // Each write takes 50ms
+    /// struct Slow(Cursor<Vec<u8>>);
+    /// impl Write for Slow {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         std::thread::sleep(Duration::from_millis(50));
+    ///         self.0.write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    /// impl Seek for Slow {
+    ///     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    ///         self.0.seek(pos)
+    ///     }
+    /// }
+    /// let mut buf = RingBuffer::new(256 * 1024);
+    /// let mut i = 0;
+    /// while buf.len() < 250 * 1024 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    ///     i += 1;
+    /// }
+    /// let content = buf.read_to_vec();
+    /// let mut out = Slow(Cursor::new(Vec::new()));
+    /// let (written, timed_out) = buf.dump_to_with_budget(&mut out, Duration::from_millis(60)).unwrap();
+    /// assert!(timed_out);
+    /// assert!(written >= 64 * 1024 && written < content.len());
+    /// // The newest bytes made it, at their offsets
+    /// let out = out.0.into_inner();
+    /// assert_eq!(out.len(), content.len());
+    /// assert_eq!(&out[out.len() - written..], &content[content.len() - written..]);
+    /// assert!(out.ends_with(format!("line {}\n", i - 1).as_bytes()));
+    /// // Without a budget issue, same as dump_to()
+    /// let mut out = Cursor::new(Vec::new());
+    /// assert_eq!(buf.dump_to_with_budget(&mut out, Duration::from_secs(10)).unwrap(), (content.len(), false));
+    /// assert_eq!(out.into_inner(), content);
+    /// ```
+    pub fn dump_to_with_budget<W: Write + Seek>(
+        &self, writer: &mut W, max: Duration,
+    ) -> Result<(usize, bool)> {
+        let deadline = Instant::now() + max;
+        let mut pieces: Vec<Cow<'_, [u8]>> = Vec::new();
+        if self.preamble.is_some() {
+            pieces.push(Cow::Borrowed(self.preamble()));
+        }
+        if self.framed {
+            pieces.extend(self.lines());
+        } else {
+            let (head, tail) = self.segments();
+            pieces.push(Cow::Borrowed(head));
+            pieces.push(Cow::Borrowed(tail));
+        }
+        let mut end: usize = pieces.iter().map(|piece| piece.len()).sum();
+        let mut written = 0;
+        for piece in pieces.iter().rev() {
+            let mut rest: &[u8] = piece;
+            while !rest.is_empty() {
+                if written > 0 && Instant::now() >= deadline {
+                    return Ok((written, true));
+                }
+                let (front, chunk) = rest.split_at(rest.len() - rest.len().min(BUDGET_CHUNK));
+                end -= chunk.len();
+                writer.seek(SeekFrom::Start(end as u64))?;
+                writer.write_all(chunk).map_err(|e| {
+                    Error::new(e.kind(), format!("dump failed after {} bytes: {}", written, e))
+                })?;
+                written += chunk.len();
+                rest = front;
+            }
+        }
+        writer.flush()?;
+        Ok((written, false))
+    }
+
+    /// Spawn `cmd` with `args` and write the content to its stdin with [RingBuffer::dump_to()],
+    /// then wait for it to exit, e.g. to pipe the dump through `grep` or `gzip` without
+    /// a temporary file. The stdout and stderr of the command are inherited.
+    ///
+    /// The command may exit before reading the whole content, e.g. `head`, the broken pipe
+    /// is not an error and the exit status is returned. Other errors of writing are returned
+    /// after the command exits.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"0123456789abcdef0123\n").expect("write ok");
+    /// let path = "/tmp/ringfile_command.out";
+    /// let status = buf.dump_to_command("sh", &["-c", &format!("cat > {}", path)]).unwrap();
+    /// assert!(status.success());
+    /// assert_eq!(std::fs::read(path).unwrap(), buf.read_to_vec());
+    /// // Exits without reading
+    /// let mut big = RingBuffer::new(1 << 20);
+    /// big.write_all(&vec![b'x'; 1 << 20]).expect("write ok");
+    /// assert!(big.dump_to_command("true", &[]).unwrap().success());
+    /// ```
+    pub fn dump_to_command(&self, cmd: &str, args: &[&str]) -> Result<ExitStatus> {
+        let mut child = Command::new(cmd).args(args).stdin(Stdio::piped()).spawn()?;
+        let mut stdin = child.stdin.take().expect("piped stdin");
+        let res = self.dump_to(&mut stdin);
+        // Close the pipe so that the command sees the end of input
+        drop(stdin);
+        let status = child.wait()?;
+        match res {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e),
+            _ => Ok(status),
+        }
+    }
+
+    /// Write the content as a single regular file entry `name` of a tar archive, in the same order
+    /// as [RingBuffer::dump_to()], so a crash report can be assembled without a temporary file.
+    ///
+    /// Only the entry is written (header, content and padding), other entries may follow.
+    /// The caller ends the archive with two zero blocks of 512 bytes.
+    /// Returns error of `ErrorKind::InvalidInput` when `name` is empty or longer than 100 bytes.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(64);
+    /// for i in 0..20 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let mut archive = std::fs::File::create("/tmp/ringfile_report.tar").unwrap();
+    /// buf.dump_into_tar(&mut archive, "ring.log").expect("dump ok");
+    /// archive.write_all(&[0u8; 1024]).unwrap();
+    /// drop(archive);
+    /// let _ = std::fs::remove_dir_all("/tmp/ringfile_report");
+    /// std::fs::create_dir("/tmp/ringfile_report").unwrap();
+    /// let status = std::process::Command::new("tar")
+    ///     .args(&["-xf", "/tmp/ringfile_report.tar", "-C", "/tmp/ringfile_report"])
+    ///     .status()
+    ///     .expect("run tar");
+    /// assert!(status.success());
+    /// buf.dump("/tmp/ringfile_report.store").expect("dump ok");
+    /// assert_eq!(
+    ///     std::fs::read("/tmp/ringfile_report/ring.log").unwrap(),
+    ///     std::fs::read("/tmp/ringfile_report.store").unwrap()
+    /// );
+    /// ```
+    #[cfg(feature = "tar")]
+    pub fn dump_into_tar<W: Write>(&self, writer: &mut W, name: &str) -> Result<()> {
+        let size = self.dump_size() as u64;
+        crate::tar::write_header(writer, name, size)?;
+        self.dump_to(writer)?;
+        crate::tar::write_padding(writer, size)
+    }
+
+    /// Same as [RingBuffer::dump_to()], but write the content as the entry `name` of `zip`,
+    /// so that the ring goes into a crash report without a temporary file.
+    ///
+    /// The content is walked twice, first for the CRC32 as the local header comes first.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::{RingBuffer, ZipWriter};
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(64);
+    /// for i in 0..100 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let archive = std::fs::File::create("/tmp/ringfile_report.zip").unwrap();
+    /// let mut zip = ZipWriter::new(archive);
+    /// zip.add_file("reason.txt", b"deadlock\n").expect("add ok");
+    /// buf.dump_into_zip(&mut zip, "ring.log").expect("dump ok");
+    /// zip.finish().expect("finish ok");
+    /// let _ = std::fs::remove_dir_all("/tmp/ringfile_report_zip");
+    /// let status = std::process::Command::new("unzip")
+    ///     .args(&["-q", "/tmp/ringfile_report.zip", "-d", "/tmp/ringfile_report_zip"])
+    ///     .status()
+    ///     .expect("run unzip");
+    /// assert!(status.success());
+    /// buf.dump("/tmp/ringfile_report_zip.store").expect("dump ok");
+    /// assert_eq!(
+    ///     std::fs::read("/tmp/ringfile_report_zip/ring.log").unwrap(),
+    ///     std::fs::read("/tmp/ringfile_report_zip.store").unwrap()
+    /// );
+    /// assert_eq!(std::fs::read("/tmp/ringfile_report_zip/reason.txt").unwrap(), b"deadlock\n");
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn dump_into_zip<W: Write>(&self, zip: &mut crate::ZipWriter<W>, name: &str) -> Result<()> {
+        let mut sum = crate::zip::Checksum::default();
+        self.dump_to(&mut sum)?;
+        zip.write_entry(name, sum, |writer| self.dump_to(writer))
+    }
+
+    /// Return the number of bytes [RingBuffer::dump()] and [RingBuffer::dump_to()] would write,
+    /// same as [RingBuffer::len()] unless framed or with a preamble.
+    #[inline]
+    pub fn dump_size(&self) -> usize {
+        let content =
+            if self.framed { self.lines().map(|record| record.len()).sum() } else { self.len() };
+        self.preamble().len() + content
+    }
+
+    /// Protect the first `reserved` bytes of the buffer from the wrapping, so that the
+    /// preamble written there by [RingBuffer::write_preamble()] is never overwritten, e.g.
+    /// the service metadata or a config snapshot. (default 0)
+    ///
+    /// The writes wrap within the rest, so [RingBuffer::capacity()] becomes
+    /// `buf_size - reserved`. The dumps ([RingBuffer::dump()], [RingBuffer::dump_to()] and
+    /// the ones built on them) write the preamble first, while [RingBuffer::segments()],
+    /// [RingBuffer::lines()] and the other views of the content leave it out, see
+    /// [RingBuffer::preamble()]. clear() keeps the preamble. Unlike the sticky banner of
+    /// [RingFileBuilder::sticky_banner()](crate::RingFileBuilder::sticky_banner()), which is
+    /// formatted on each dump, the preamble lives in the buffer itself.
+    ///
+    /// Returns error of `ErrorKind::InvalidInput` once something is written,
+    /// or when `reserved` is not less than buf_size.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(64);
+    /// buf.set_reserved_prefix(16).expect("reserve ok");
+    /// buf.write_preamble(b"svc v1 cfg=abc\n").expect("preamble ok");
+    /// assert!(buf.write_preamble(b"again\n").is_err());
+    /// assert_eq!(buf.capacity(), 48);
+    /// for i in 0..1000 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// assert_eq!(buf.preamble(), b"svc v1 cfg=abc\n");
+    /// let content = buf.read_to_vec();
+    /// assert_eq!(content.len(), 48);
+    /// assert!(content.ends_with(b"line 999\n"));
+    /// let path = "/tmp/ringfile_preamble.store";
+    /// buf.dump(path).expect("dump ok");
+    /// let dumped = std::fs::read(path).unwrap();
+    /// assert_eq!(dumped, [&b"svc v1 cfg=abc\n"[..], &content].concat());
+    /// assert_eq!(buf.dump_size(), dumped.len());
+    /// buf.clear();
+    /// buf.dump(path).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"svc v1 cfg=abc\n");
+    /// assert!(buf.set_reserved_prefix(8).is_err());
+    /// ```
+    pub fn set_reserved_prefix(&mut self, reserved: usize) -> Result<()> {
+        if self.written > 0 || reserved >= self.inner.capacity() {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "reserved_prefix should be set before writing, and less than buf_size",
+            ));
+        }
+        self.reserved = reserved;
+        self.cur = Cursor::new(self.capacity());
+        Ok(())
+    }
+
+    /// Write the preamble into the bytes reserved by [RingBuffer::set_reserved_prefix()],
+    /// only once.
+    ///
+    /// Returns error of `ErrorKind::InvalidInput` when already written,
+    /// or when `data` is longer than the reserved bytes.
+    pub fn write_preamble(&mut self, data: &[u8]) -> Result<()> {
+        if self.preamble.is_some() || data.len() > self.reserved {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "preamble is already written, or longer than reserved_prefix",
+            ));
+        }
+        safe_copy(&mut self.inner[0..data.len()], data);
+        self.preamble = Some(data.len());
+        Ok(())
+    }
+
+    /// Return the preamble written by [RingBuffer::write_preamble()], empty if none.
+    #[inline]
+    pub fn preamble(&self) -> &[u8] {
+        &self.inner[0..self.preamble.unwrap_or(0)]
+    }
+
+    /// Write all data to each of the `writers`, walking the content only once.
+    ///
+    /// Returns the result of each writer in the same order,
+    /// a failing writer does not stop the others.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(1024);
+    /// buf.write_all(b"log message").expect("write ok");
+    /// let mut good = Vec::new();
+    /// let mut bad = &mut [0u8; 3][..];
+    /// let res = buf.dump_to_all(&mut [&mut good, &mut bad]);
+    /// assert!(res[0].is_ok());
+    /// assert!(res[1].is_err());
+    /// assert_eq!(good, b"log message");
+    /// ```
+    pub fn dump_to_all(&self, writers: &mut [&mut dyn Write]) -> Vec<Result<()>> {
+        let mut results: Vec<Result<()>> = writers.iter().map(|_| Ok(())).collect();
+        let _ = self.for_each_chunk(|chunk| {
+            for (writer, res) in writers.iter_mut().zip(results.iter_mut()) {
+                if res.is_ok() {
+                    *res = writer.write_all(chunk);
+                }
+            }
+            Ok(())
+        });
+        results
+    }
+
+    /// Dump only the lines matching `pred` into a truncated file, in order.
+    ///
+    /// `pred` receives each line including the trailing newline, see [RingBuffer::lines()].
+    /// The line across the end of buffer is passed stitched.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(40);
+    /// for i in 0..10 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let path = "/tmp/ringfile_filtered.store";
+    /// // The first line is partially overwritten
+    /// buf.dump_filtered(path, |line| line.len() == 7 && line[5] % 2 == 0).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 6\nline 8\n");
+    /// ```
+    pub fn dump_filtered<P: AsRef<Path>, F: Fn(&[u8]) -> bool>(
+        &self, file_path: P, pred: F,
+    ) -> Result<()> {
+        let mut file = create_file(file_path.as_ref(), self.dump_mode)?;
+        for line in self.lines() {
+            if pred(&line) {
+                file.write_all(&line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Dump the lines transformed by `f` into a truncated file, in order, e.g. to add a prefix
+    /// or redact a field.
+    ///
+    /// `f` receives each line like [RingBuffer::dump_filtered()], and returns the bytes to write,
+    /// which may drop or add the trailing newline. Return `Cow::Borrowed` for the lines unchanged,
+    /// to write them without copy.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::borrow::Cow;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(40);
+    /// for i in 0..10 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let path = "/tmp/ringfile_map.store";
+    /// buf.dump_map(path, |line| Cow::Borrowed(line)).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), buf.read_to_vec());
+    /// buf.dump_map(path, |line| {
+    ///     if line.starts_with(b"line 7") {
+    ///         Cow::Borrowed(b"[redacted]\n")
+    ///     } else {
+    ///         Cow::Owned([b"> ", line].concat())
+    ///     }
+    /// })
+    /// .expect("dump ok");
+    /// // The first line is partially overwritten
+    /// let content = "> ne 4\n> line 5\n> line 6\n[redacted]\n> line 8\n> line 9\n";
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), content);
+    /// ```
+    pub fn dump_map<P, F>(&self, file_path: P, mut f: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&[u8]) -> Cow<'_, [u8]>,
+    {
+        let mut file = create_file(file_path.as_ref(), self.dump_mode)?;
+        for line in self.lines() {
+            file.write_all(&f(&line))?;
+        }
+        Ok(())
+    }
+
+    /// Dump the lines to `data_path` like [RingBuffer::dump()], and an index of the lines to
+    /// `index_path`, for a viewer to seek in a large dump, or binary search it by time.
+    ///
+    /// The index is text, the header `# offset\tlen\ttime_ns` followed by one line per record
+    /// of the data file: the byte offset, the length with the newline, and the nanoseconds
+    /// since UNIX epoch of the prefix of [RingFileBuilder::timestamps()](crate::RingFileBuilder::timestamps()),
+    /// `-` without it. In framed mode, a record is a whole record, without the length prefix.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let (data, index) = ("/tmp/ringfile_indexed.store", "/tmp/ringfile_indexed.index");
+    /// let mut buf = RingBuffer::new(64);
+    /// buf.write_all(b"cut by the rewinding\n").expect("write ok");
+    /// buf.write_all(b"[1700000000000000us] first\n").expect("write ok");
+    /// buf.write_all(b"plain\n").expect("write ok");
+    /// buf.write_all(b"[1700000001500000us] second\n").expect("write ok");
+    /// buf.dump_with_index(data, index).expect("dump ok");
+    /// let data = std::fs::read(data).unwrap();
+    /// assert_eq!(data, buf.read_to_vec());
+    /// let index = std::fs::read_to_string(index).unwrap();
+    /// let mut lines = index.lines();
+    /// assert_eq!(lines.next(), Some("# offset\tlen\ttime_ns"));
+    /// let entries: Vec<Vec<&str>> = lines.map(|line| line.split('\t').collect()).collect();
+    /// assert_eq!(entries.len(), 4);
+    /// assert_eq!(entries[1][2], "1700000000000000000");
+    /// assert_eq!(entries[2][2], "-");
+    /// assert_eq!(entries[3][2], "1700000001500000000");
+    /// let mut end = 0;
+    /// for (entry, line) in entries.iter().zip(buf.lines()) {
+    ///     let (offset, len): (usize, usize) = (entry[0].parse().unwrap(), entry[1].parse().unwrap());
+    ///     assert_eq!(offset, end);
+    ///     assert_eq!(&data[offset..offset + len], &*line);
+    ///     end += len;
+    /// }
+    /// assert_eq!(end, data.len());
+    /// ```
+    pub fn dump_with_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self, data_path: P, index_path: Q,
+    ) -> Result<()> {
+        dump_indexed(self.lines(), data_path.as_ref(), index_path.as_ref(), self.dump_mode)
+    }
+
+    /// Iterate the content line by line, from the oldest to the newest.
+    ///
+    /// Each line includes the trailing newline, except the last one if not terminated.
+    /// The line across the end of buffer is stitched into an owned copy, others are borrowed.
+    /// When the buffer has rewinded, the first line is likely partially overwritten.
+    ///
+    /// In framed mode, iterate the whole records instead, see [RingBuffer::set_framed()].
+    #[inline]
+    pub fn lines(&self) -> Lines<'_> {
+        self.lines_since(0)
+    }
+
+    /// Iterate the lines like [RingBuffer::lines()], with the prefixes of timestamp, uptime,
+    /// thread name, sequence and level parsed into a [Record](crate::Record).
+    ///
+    /// The line stitched across the end of buffer is parsed as any other. The first line
+    /// after rewinding is returned as is, see [Record::is_partial()](crate::Record::is_partial()).
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{Level, RingFileBuilder};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_records.store");
+    /// for flags in 0..16 {
+    ///     let on = |bit: u32| flags & (1 << bit) != 0;
+    ///     let ring = RingFileBuilder::new(1024, path.into())
+    ///         .timestamps(on(0))
+    ///         .uptime(on(1))
+    ///         .thread_names(on(2))
+    ///         .per_thread_seq(on(3))
+    ///         .build()
+    ///         .expect("build ok");
+    ///     ring.write_leveled(Level::Warn, b"disk almost full\n".to_vec());
+    ///     ring.write("plain\n".to_string());
+    ///     let buffer = ring.take_buffer().expect("take ok");
+    ///     let records: Vec<_> = buffer.records().collect();
+    ///     assert_eq!(records.len(), 2);
+    ///     for (i, record) in records.iter().enumerate() {
+    ///         assert!(!record.is_partial());
+    ///         assert_eq!(record.timestamp().is_some(), on(0));
+    ///         assert_eq!(record.uptime().is_some(), on(1));
+    ///         assert_eq!(record.thread().is_some(), on(2));
+    ///         assert_eq!(record.sequence(), Some(i as u64 + 1).filter(|_| on(3)));
+    ///     }
+    ///     assert_eq!(records[0].level(), Some(Level::Warn));
+    ///     assert_eq!(records[0].body(), b"disk almost full");
+    ///     assert_eq!(records[1].level(), None);
+    ///     assert_eq!(records[1].body(), b"plain");
+    /// }
+    ///
+    /// // The first line is cut after rewinding
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"E first\nI second\nW third\n").expect("write ok");
+    /// let records: Vec<_> = buf.records().collect();
+    /// assert!(records[0].is_partial());
+    /// assert_eq!((records[0].level(), records[0].body()), (None, &b" second"[..]));
+    /// assert_eq!((records[1].level(), records[1].body()), (Some(Level::Warn), &b"third"[..]));
+    /// ```
+    #[inline]
+    pub fn records(&self) -> Records<'_> {
+        let cut = !self.framed && self.earliest_retained_offset() > self.floor;
+        Records::new(self.lines(), cut)
+    }
+
+    /// Drive `sink` over the records of [RingBuffer::records()], to write the content in a
+    /// custom format. With [PlainSink](crate::PlainSink) the output is the same as
+    /// [RingBuffer::dump_to()]. The preamble of [RingBuffer::write_preamble()], if any,
+    /// is the first record.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{DumpSink, Level, PlainSink, RecordMeta, RingFileBuilder, Stats};
+    /// use std::path::Path;
+    /// /// Write CSV, and count the records
+    /// struct Csv {
+    ///     out: Vec<u8>,
+    ///     count: usize,
+    ///     total_written: u64,
+    /// }
+    /// impl DumpSink for Csv {
+    ///     fn begin(&mut self, stats: &Stats) -> std::io::Result<()> {
+    ///         self.total_written = stats.total_written;
+    ///         self.out.extend_from_slice(b"index,level,thread,seq,body\n");
+    ///         Ok(())
+    ///     }
+    ///     fn record(&mut self, body: &[u8], meta: &RecordMeta<'_>) -> std::io::Result<()> {
+    ///         let level = meta.level.map(|level| format!("{:?}", level)).unwrap_or_default();
+    ///         let line = format!(
+    ///             "{},{},{},{},{}\n",
+    ///             meta.index,
+    ///             level,
+    ///             meta.thread.unwrap_or(""),
+    ///             meta.sequence.unwrap_or(0),
+    ///             String::from_utf8_lossy(body)
+    ///         );
+    ///         self.out.extend_from_slice(line.as_bytes());
+    ///         self.count += 1;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_sink.store").into())
+    ///     .thread_names(true)
+    ///     .per_thread_seq(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// std::thread::Builder::new()
+    ///     .name("worker".to_string())
+    ///     .spawn(move || {
+    ///         ring.write_leveled(Level::Warn, b"disk almost full\n".to_vec());
+    ///         ring.write("plain\n".to_string());
+    ///         let buffer = ring.take_buffer().expect("take ok");
+    ///         let mut csv = Csv { out: Vec::new(), count: 0, total_written: 0 };
+    ///         buffer.dump_with_sink(&mut csv).expect("dump ok");
+    ///         assert_eq!(csv.count, 2);
+    ///         assert_eq!(csv.total_written, buffer.total_written());
+    ///         assert_eq!(
+    ///             String::from_utf8(csv.out).unwrap(),
+    ///             "index,level,thread,seq,body\n0,Warn,worker,1,disk almost full\n1,,worker,2,plain\n"
+    ///         );
+    ///         let mut plain = PlainSink(Vec::new());
+    ///         buffer.dump_with_sink(&mut plain).expect("dump ok");
+    ///         let mut expected = Vec::new();
+    ///         buffer.dump_to(&mut expected).expect("dump ok");
+    ///         assert_eq!(plain.0, expected);
+    ///     })
+    ///     .unwrap()
+    ///     .join()
+    ///     .unwrap();
+    /// ```
+    pub fn dump_with_sink<S: DumpSink>(&self, sink: &mut S) -> Result<()> {
+        sink.begin(&self.stats())?;
+        let preamble = self.preamble.map(|_| Record::parse(Cow::Borrowed(self.preamble()), false));
+        for (index, record) in preamble.into_iter().chain(self.records()).enumerate() {
+            sink.record(record.body(), &RecordMeta::new(index, &record))?;
+        }
+        sink.end()
+    }
+
+    /// Iterate the content as owned chunks of up to `chunk` lines each, from the oldest to the
+    /// newest, e.g. to stream a large dump over a channel with backpressure, without copying
+    /// the whole content at once.
+    ///
+    /// The chunks start at [RingBuffer::first_complete_record_offset()], so the line cut by
+    /// the rewinding is left out. The line across the end of buffer is stitched whole into
+    /// its chunk. The last line is included even if not terminated.
+    ///
+    /// Panics if `chunk` is 0.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(32);
+    /// for i in 0..10 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// // The content is "e 5\nline 6\n..." with "line 9\n" across the end of buffer
+    /// let (tx, rx) = std::sync::mpsc::sync_channel(1);
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         for chunk in buf.into_line_chunks(2) {
+    ///             tx.send(chunk).unwrap();
+    ///         }
+    ///         drop(tx);
+    ///     });
+    ///     let chunks: Vec<Vec<u8>> = rx.iter().collect();
+    ///     assert_eq!(chunks, vec![b"line 6\nline 7\n".to_vec(), b"line 8\nline 9\n".to_vec()]);
+    ///     let content = buf.read_to_vec();
+    ///     assert_eq!(chunks.concat(), &content[buf.first_complete_record_offset()..]);
+    /// });
+    /// ```
+    pub fn into_line_chunks(&self, chunk: usize) -> LineChunks<'_> {
+        assert!(chunk > 0, "chunk should not be 0");
+        let start = self.earliest_retained_offset() + self.first_complete_record_offset() as u64;
+        LineChunks { lines: self.lines_since(start), chunk }
+    }
+
+    /// Same as [RingBuffer::lines()], but only the content after logical `offset`,
+    /// which must be a record boundary in framed mode.
+    #[inline]
+    pub(crate) fn lines_since(&self, offset: u64) -> Lines<'_> {
+        if self.framed {
+            let (head, tail) = self.segments_since(offset.max(self.first_record));
+            Lines { head, tail, framed: true, binary: self.binary_uptime }
+        } else {
+            let (head, tail) = self.segments_since(offset);
+            Lines { head, tail, framed: false, binary: false }
+        }
+    }
+
+    /// Copy the content into a Vec, from the oldest to the newest.
+    pub fn read_to_vec(&self) -> Vec<u8> {
+        let (head, tail) = self.segments();
+        let mut v = Vec::with_capacity(head.len() + tail.len());
+        v.extend_from_slice(head);
+        v.extend_from_slice(tail);
+        v
+    }
+
+    /// Return the 64-bit FNV-1a hash of the content in logical order, independent of where the
+    /// content sits in the buffer.
+    ///
+    /// [RingBuffer::total_written()] alone tells whether anything was written between two
+    /// observations. The hash adds value when comparing the content itself, e.g. whether the
+    /// same content was written again after a clear(), or two buffers hold the same log.
+    /// It reads the whole content, and is not cryptographic.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut a = RingBuffer::new(8);
+    /// let mut b = RingBuffer::new(8);
+    /// a.write_all(b"xx01234567").expect("write ok");
+    /// b.write_all(b"01234567").expect("write ok");
+    /// // Same content at different physical offsets
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    /// let before = b.content_hash();
+    /// b.clear();
+    /// b.write_all(b"01234567").expect("write ok");
+    /// assert_eq!(b.content_hash(), before);
+    /// b.write_all(b"8").expect("write ok");
+    /// assert_ne!(b.content_hash(), before);
+    /// assert_ne!(RingBuffer::new(8).content_hash(), before);
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let (head, tail) = self.segments();
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for b in head.iter().chain(tail.iter()) {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    /// Render the lines from the oldest to the newest with 1-based line numbers, like `1: message`,
+    /// for a quick look from a debugger or a REPL. The invalid UTF-8 is replaced with U+FFFD.
+    ///
+    /// **NOTE**: It allocates the whole String, not intended for hot paths.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(20);
+    /// for i in 0..5 {
+    ///     buf.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// // The partial first line is across the end of buffer
+    /// assert_eq!(buf.to_numbered_string(), "1: ine 2\n2: line 3\n3: line 4\n");
+    /// buf.write_all(b"no newline").expect("write ok");
+    /// assert_eq!(buf.to_numbered_string(), "1:  3\n2: line 4\n3: no newline\n");
+    /// ```
+    pub fn to_numbered_string(&self) -> String {
+        let mut s = String::with_capacity(self.len() + self.len() / 8);
+        for (i, line) in self.lines().enumerate() {
+            s.push_str(&format!("{}: {}", i + 1, String::from_utf8_lossy(&line)));
+            if !s.ends_with('\n') {
+                s.push('\n');
+            }
+        }
+        s
+    }
+
+    /// Copy the content into `dst` in order without allocation, return the bytes copied.
+    ///
+    /// When `dst` is smaller than the content, only the newest bytes are copied.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(8);
+    /// buf.write_all(b"0123456789").expect("write ok");
+    /// let mut large = [0u8; 12];
+    /// assert_eq!(buf.copy_into(&mut large), 8);
+    /// assert_eq!(&large[0..8], b"23456789");
+    /// let mut equal = [0u8; 8];
+    /// assert_eq!(buf.copy_into(&mut equal), 8);
+    /// assert_eq!(&equal, b"23456789");
+    /// let mut small = [0u8; 5];
+    /// assert_eq!(buf.copy_into(&mut small), 5);
+    /// assert_eq!(&small, b"56789");
+    /// ```
+    pub fn copy_into(&self, dst: &mut [u8]) -> usize {
+        let (head, tail) = self.segments();
+        copy_segments(head, tail, dst)
+    }
+
+    /// Same as [RingBuffer::copy_into()], but zero the rest of `region`, so that a region
+    /// updated again and again, e.g. a memory snapshot by a crash reporter, holds only the
+    /// current content. Return the bytes of content copied.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(8);
+    /// let mut region = [0xffu8; 6];
+    /// buf.write_all(b"abc").expect("write ok");
+    /// assert_eq!(buf.mirror_into(&mut region), 3);
+    /// assert_eq!(&region, b"abc\0\0\0");
+    /// buf.write_all(b"0123456789").expect("write ok");
+    /// assert_eq!(buf.mirror_into(&mut region), 6);
+    /// assert_eq!(&region, b"456789");
+    /// buf.clear();
+    /// buf.write_all(b"z").expect("write ok");
+    /// assert_eq!(buf.mirror_into(&mut region), 1);
+    /// assert_eq!(&region, b"z\0\0\0\0\0");
+    /// ```
+    pub fn mirror_into(&self, region: &mut [u8]) -> usize {
+        let n = self.copy_into(region);
+        region[n..].fill(0);
+        n
+    }
+
+    /// Reallocate the buffer to `buf_size`, keeping the settings, the preamble and the newest
+    /// content that fits.
+    ///
+    /// The old and the new buffer are both allocated during the copy, the memory peaks at the
+    /// sum of the two sizes. The logical offsets of [RingBuffer::total_written()] carry on,
+    /// except that a framed record longer than the new buffer is cut.
+    /// Returns error of `ErrorKind::InvalidInput` when `buf_size` is not larger than the
+    /// reserved prefix, the buffer is left as is on error.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"first\nsecond\n").expect("write ok");
+    /// buf.resize(64).expect("resize ok");
+    /// assert_eq!(buf.capacity(), 64);
+    /// buf.write_all(b"third\nfourth\n").expect("write ok");
+    /// assert_eq!(buf.read_to_vec(), b"first\nsecond\nthird\nfourth\n");
+    /// // Shrink, only the newest bytes are kept
+    /// buf.resize(10).expect("resize ok");
+    /// assert_eq!(buf.read_to_vec(), b"rd\nfourth\n");
+    /// assert_eq!(buf.total_written(), 26);
+    /// assert_eq!(buf.earliest_retained_offset(), 16);
+    /// ```
+    pub fn resize(&mut self, buf_size: i32) -> Result<()> {
+        if buf_size <= 0 || buf_size as usize <= self.reserved {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "buf_size should be larger than reserved_prefix",
+            ));
+        }
+        let mut resized = Self::alloc(buf_size)?;
+        resized.no_split = self.no_split;
+        resized.partial_dump = self.partial_dump;
+        resized.dump_mode = self.dump_mode;
+        resized.framed = self.framed;
+        resized.binary_uptime = self.binary_uptime;
+        resized.reserved = self.reserved;
+        resized.preamble = self.preamble;
+        resized.cur = Cursor::new(resized.capacity());
+        resized.cur.wraps = self.cur.wraps;
+        resized.cur.repairs = self.cur.repairs;
+        safe_copy(&mut resized.inner[0..self.reserved], &self.inner[0..self.reserved]);
+        // The copy written from the logical offset of the content, to carry the offsets on
+        let start = if self.framed {
+            self.first_record.max(self.earliest_retained_offset())
+        } else {
+            self.earliest_retained_offset()
+        };
+        resized.written = start;
+        resized.floor = start;
+        resized.first_record = start;
+        resized.append_from(self)?;
+        resized.resync = self.resync;
+        resized.fill = self.fill;
+        resized.fill_unused();
+        *self = resized;
+        Ok(())
+    }
+
+    /// Write the content of `other` into this buffer in order, through the normal write path,
+    /// so the capacity and the rewinding of this buffer apply.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut small = RingBuffer::new(16);
+    /// for i in 0..5 {
+    ///     small.write_all(format!("line {}\n", i).as_bytes()).expect("write ok");
+    /// }
+    /// let mut combined = RingBuffer::new(1024);
+    /// combined.write_all(b"other\n").expect("write ok");
+    /// combined.append_from(&small).expect("append ok");
+    /// assert_eq!(combined.read_to_vec(), b"other\n2\nline 3\nline 4\n");
+    /// ```
+    pub fn append_from(&mut self, other: &RingBuffer) -> Result<()> {
+        if other.framed {
+            let mut records = other.lines();
+            // Keep the stamps as is between two buffers storing them
+            records.binary &= !self.binary_uptime;
+            for record in records {
+                self.write_all(&record)?;
+            }
+            return Ok(());
+        }
+        let (head, tail) = other.segments();
+        self.write_all(head)?;
+        self.write_all(tail)
+    }
+
+    /// Return the content as two slices, from the oldest to the newest.
+    ///
+    /// The second slice is empty when the buffer has not rewinded yet.
+    #[inline]
+    pub fn segments(&self) -> (&[u8], &[u8]) {
+        let (head, tail) = self.cur.split(&self.inner[self.reserved..]);
+        let len = head.len() + tail.len();
+        let skip = self.floor.saturating_sub(self.written.saturating_sub(len as u64)) as usize;
+        skip_front(head, tail, skip)
+    }
+
+    /// Same as [RingBuffer::segments()], but only the content after logical `offset`.
+    #[inline]
+    pub(crate) fn segments_since(&self, offset: u64) -> (&[u8], &[u8]) {
+        let (head, tail) = self.segments();
+        let skip = offset.saturating_sub(self.earliest_retained_offset()) as usize;
+        skip_front(head, tail, skip)
+    }
+
+    /// Overwrite the raw state of the cursor, even out of the buffer, to test that a corrupted
+    /// state does not panic. Requires the `test-util` feature.
+    ///
+    /// The reads clamp the cursor into the buffer, the next write resets it to the start,
+    /// counted by [Stats::repairs].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"0123456789").expect("write ok");
+    /// buf.set_raw_offsets(100, true, 200);
+    /// assert_eq!(buf.read_to_vec().len(), 16);
+    /// buf.dump("/tmp/ringfile_corrupted.store").expect("dump ok");
+    /// let _ = buf.lines().count();
+    /// buf.write_all(b"abc\n").expect("write ok");
+    /// assert_eq!(buf.stats().repairs, 1);
+    /// assert_eq!(buf.offsets(), (4, true, 16));
+    /// assert!(buf.read_to_vec().ends_with(b"abc\n"));
+    /// buf.clear();
+    /// buf.write_all(b"clean\n").expect("write ok");
+    /// assert_eq!(buf.read_to_vec(), b"clean\n");
+    /// assert_eq!(buf.stats().repairs, 1);
+    /// ```
+    #[cfg(feature = "test-util")]
+    pub fn set_raw_offsets(&mut self, end: usize, full: bool, limit: usize) {
+        self.cur.end = end;
+        self.cur.full = full;
+        self.cur.limit = limit;
+    }
+
+    /// Return the raw state `(end, full, capacity)` for custom dump logic.
+    ///
+    /// These are physical offsets inside the buffer, not logical ones:
+    /// when `full` is true, the oldest byte is at `end`, otherwise the content is `0..end`.
+    /// (The gap left by [RingBuffer::set_no_split()] is not reflected.)
+    /// [RingBuffer::segments()] already gives the logical view.
+    /// With [RingBuffer::set_reserved_prefix()], the offsets start after the reserved bytes.
+    #[inline]
+    pub fn offsets(&self) -> (usize, bool, usize) {
+        (self.cur.end, self.cur.full, self.capacity())
+    }
+
+    /// Return the whole physical buffer as is, the bytes [RingBuffer::dump_raw()] writes,
+    /// for memory forensics tooling reconstructing what was in RAM.
+    ///
+    /// Nothing is reordered: the caller applies the wrap logic with [RingBuffer::offsets()],
+    /// after skipping the bytes of [RingBuffer::set_reserved_prefix()]. The slice also holds
+    /// what the logical view hides, the region not yet written, the content discarded by
+    /// [RingBuffer::clear()], the tail gap of [RingBuffer::set_no_split()] and the length
+    /// prefixes of [RingBuffer::set_framed()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// let rebuild = |buf: &RingBuffer| {
+    ///     let (end, full, capacity) = buf.offsets();
+    ///     let raw = &buf.physical_bytes()[buf.physical_bytes().len() - capacity..];
+    ///     if full { [&raw[end..], &raw[..end]].concat() } else { raw[..end].to_vec() }
+    /// };
+    /// buf.write_all(b"first\n").expect("write ok");
+    /// assert_eq!(rebuild(&buf), buf.read_to_vec());
+    /// buf.write_all(b"second\nthird\n").expect("write ok");
+    /// assert_eq!(buf.offsets(), (3, true, 16));
+    /// assert_eq!(&buf.physical_bytes()[..3], b"rd\n");
+    /// assert_eq!(rebuild(&buf), buf.read_to_vec());
+    /// ```
+    #[inline]
+    pub fn physical_bytes(&self) -> &[u8] {
+        &self.inner[..]
+    }
+
+    /// Return the capacity specified by `buf_size`,
+    /// less the bytes of [RingBuffer::set_reserved_prefix()].
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity() - self.reserved
+    }
+
+    /// Return the bytes of content currently kept.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let (head, tail) = self.segments();
+        head.len() + tail.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the whole `buf` like write_all(), return the bytes written and whether it overwrote
+    /// older content, e.g. to alert at once when the history starts being lost.
+    ///
+    /// Filling the buffer exactly up to the end is not an overwrite, the first byte written
+    /// after is. The content discarded by [RingBuffer::clear()] does not count.
+    /// In framed mode, a record skipped as a whole is an overwrite too.
+    ///
+    /// [RingFile](crate::RingFile) writes on the backend, so the caller can not know it at the
+    /// time of the write, watch [Stats::wraps] of
+    /// [RingFile::snapshot_with_stats()](crate::RingFile::snapshot_with_stats()) instead.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// let mut buf = RingBuffer::new(16);
+    /// assert_eq!(buf.write_tracked(b"0123456789"), (10, false));
+    /// // Exactly at the boundary
+    /// assert_eq!(buf.write_tracked(b"abcdef"), (6, false));
+    /// assert_eq!(buf.write_tracked(b"X"), (1, true));
+    /// buf.clear();
+    /// assert_eq!(buf.write_tracked(b"0123456789"), (10, false));
+    /// // Across the boundary
+    /// assert_eq!(buf.write_tracked(b"abcdefgh"), (8, true));
+    /// assert_eq!(buf.read_to_vec(), b"23456789abcdefgh");
+    /// ```
+    pub fn write_tracked(&mut self, buf: &[u8]) -> (usize, bool) {
+        let (written, len) = (self.written, self.len() as u64);
+        let _ = self.write_all(buf);
+        let stored = self.written - written;
+        (buf.len(), len + stored > self.len() as u64)
+    }
+
+    /// Return the oldest content that a write of `incoming` bytes would overwrite now,
+    /// as two slices in order, or None when it fits without overwriting anything.
+    ///
+    /// This is a read-only inspection: nothing is moved or copied, so a watchdog can
+    /// salvage the bytes about to be lost before the next write. The gap skipped by
+    /// [RingBuffer::set_no_split()] is taken into account, and in framed mode the length
+    /// prefix and the truncation of the record are too.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"0123456789ab").expect("write ok");
+    /// // Up to the end of buffer, nothing is lost
+    /// assert_eq!(buf.peek_overwrite_region(0), None);
+    /// assert_eq!(buf.peek_overwrite_region(4), None);
+    /// assert_eq!(buf.peek_overwrite_region(5), Some((&b"0"[..], &b""[..])));
+    /// assert_eq!(buf.peek_overwrite_region(16), Some((&b"0123456789ab"[..], &b""[..])));
+    /// buf.write_all(b"cdefXYZ").expect("write ok");
+    /// // The content is "3456789abcdefXYZ", with "XYZ" at the start of the buffer
+    /// assert_eq!(buf.peek_overwrite_region(1), Some((&b"3"[..], &b""[..])));
+    /// assert_eq!(buf.peek_overwrite_region(14), Some((&b"3456789abcdef"[..], &b"X"[..])));
+    /// assert_eq!(buf.peek_overwrite_region(100), Some((&b"3456789abcdef"[..], &b"XYZ"[..])));
+    /// // Matches what the write loses
+    /// let (head, tail) = buf.peek_overwrite_region(5).unwrap();
+    /// let lost = [head, tail].concat();
+    /// let before = buf.read_to_vec();
+    /// buf.write_all(b"12345").expect("write ok");
+    /// assert_eq!(&before[lost.len()..], &buf.read_to_vec()[0..before.len() - lost.len()]);
+    /// ```
+    pub fn peek_overwrite_region(&self, incoming_len: usize) -> Option<(&[u8], &[u8])> {
+        let incoming = if self.framed {
+            let payload = incoming_len.min(frame::max_payload(self.capacity()));
+            frame::prefix_len(payload) + payload
+        } else {
+            incoming_len
+        };
+        let (head, tail) = self.evicted_by(incoming);
+        if head.is_empty() && tail.is_empty() {
+            None
+        } else {
+            Some((head, tail))
+        }
+    }
+
+    /// Return the oldest content that will be overwritten by writing `incoming` bytes,
+    /// as two slices in order.
+    pub(crate) fn evicted_by(&self, incoming: usize) -> (&[u8], &[u8]) {
+        let n = self.evicted_len(incoming);
+        let (head, tail) = self.segments();
+        if n <= head.len() {
+            (&head[0..n], &[])
         } else {
-            return file.write_all(&self.inner[0..self.end]);
+            (head, &tail[0..n - head.len()])
+        }
+    }
+
+    /// Return the number of bytes overwritten by a write_all() of `incoming` bytes.
+    fn evicted_len(&self, incoming: usize) -> usize {
+        // Simulate write_all() on the cursor
+        let mut cur = self.cur;
+        let mut left = incoming;
+        while left > 0 {
+            left -= self.advance(&mut cur, left).1;
+        }
+        let len_after = match cur {
+            Cursor { end, full: true, limit, .. } if end < limit => limit,
+            Cursor { end, .. } => end,
+        };
+        let start_after = self.written + incoming as u64 - len_after as u64;
+        start_after.min(self.written).saturating_sub(self.earliest_retained_offset()) as usize
+    }
+
+    /// Move `first_record` over the records starting before logical `offset`.
+    fn skip_records(&mut self, offset: u64) {
+        while self.first_record < offset && self.first_record < self.written {
+            let (mut head, mut tail) = self.segments_since(self.first_record);
+            let before = head.len() + tail.len();
+            match frame::decode_len(&mut head, &mut tail) {
+                Some(len) => {
+                    let prefix = before - head.len() - tail.len();
+                    self.first_record += (prefix + len) as u64;
+                }
+                None => self.first_record = self.written,
+            }
+        }
+    }
+
+    /// Write `buf` as one record of the framed mode.
+    fn write_record(&mut self, buf: &[u8]) -> Result<usize> {
+        let payload = &buf[buf.len() - buf.len().min(frame::max_payload(self.capacity()))..];
+        let mut prefix = [0u8; frame::MAX_PREFIX_LEN];
+        let l = frame::encode_len(payload.len(), &mut prefix);
+        // The prefix and the payload are kept contiguous together by no_split,
+        // equivalent to a single write_all()
+        let total = l + payload.len();
+        let earliest = self.earliest_retained_offset();
+        self.skip_records(earliest + self.evicted_len(total) as u64);
+        let mut cur = self.cur;
+        self.keep_contiguous(&mut cur, total);
+        self.cur = cur;
+        for mut part in [&prefix[0..l], payload].iter().copied() {
+            while !part.is_empty() {
+                let mut cur = self.cur;
+                let (offset, l) = self.advance_raw(&mut cur, part.len());
+                self.copy_at(cur, offset, &part[0..l]);
+                part = &part[l..];
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Copy `buf` to `offset` and move the cursor to `cur`.
+    #[inline(always)]
+    fn copy_at(&mut self, cur: Cursor, offset: usize, buf: &[u8]) {
+        self.cur = cur;
+        let offset = self.reserved + offset;
+        safe_copy(&mut self.inner[offset..offset + buf.len()], buf);
+        self.written += buf.len() as u64;
+    }
+
+    /// The total bytes ever written, which is also the logical offset of the end.
+    ///
+    /// Every byte written gets a logical offset, counting from 0 and growing monotonically,
+    /// unaffected by the rewinding or [RingBuffer::clear()]. The content kept is always the
+    /// range from [RingBuffer::earliest_retained_offset()] to this offset.
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Logical offset of the oldest byte kept, equals to [RingBuffer::total_written()] when empty.
+    ///
+    /// Normally `total_written - capacity` once rewinded, but later when the tail gap of
+    /// [RingBuffer::set_no_split()] is skipped, or the content is cleared.
+    #[inline]
+    pub fn earliest_retained_offset(&self) -> u64 {
+        self.written.saturating_sub(self.len() as u64)
+    }
+
+    /// Whether the byte at logical `offset` is still kept, i.e. not yet overwritten or cleared.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(8);
+    /// buf.write_all(b"012345").expect("write ok");
+    /// assert!(buf.contains_offset(0) && buf.contains_offset(5));
+    /// assert!(!buf.contains_offset(6));
+    /// // Wrap around, the first 4 bytes are overwritten
+    /// buf.write_all(b"6789ab").expect("write ok");
+    /// assert_eq!(buf.total_written(), 12);
+    /// assert_eq!(buf.earliest_retained_offset(), 4);
+    /// assert!(!buf.contains_offset(3));
+    /// assert!(buf.contains_offset(4) && buf.contains_offset(11));
+    /// // Wrap again
+    /// buf.write_all(b"cdefghij").expect("write ok");
+    /// assert_eq!(buf.earliest_retained_offset(), 12);
+    /// assert!(!buf.contains_offset(11) && buf.contains_offset(12));
+    /// buf.clear();
+    /// assert_eq!(buf.earliest_retained_offset(), 20);
+    /// assert!(!buf.contains_offset(19));
+    /// ```
+    #[inline]
+    pub fn contains_offset(&self, offset: u64) -> bool {
+        offset >= self.earliest_retained_offset() && offset < self.written
+    }
+
+    /// Return the position in the content (as dumped) where the first complete line begins,
+    /// so a parser of the dump can seek past the leading line cut by the rewinding.
+    ///
+    /// Once some content is overwritten, this is the position after the first newline
+    /// (the length when there is no newline), 0 otherwise. The cut may fall exactly on a line
+    /// boundary, which can not be told apart, so a whole line may be skipped.
+    /// In framed mode, the boundary is exact and the dump never starts mid-record, always 0.
+    /// Costs the length of the first line.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"first\nsecond\n").expect("write ok");
+    /// assert_eq!(buf.first_complete_record_offset(), 0);
+    /// buf.write_all(b"third\n").expect("write ok");
+    /// let content = buf.read_to_vec();
+    /// assert_eq!(content, b"st\nsecond\nthird\n");
+    /// let offset = buf.first_complete_record_offset();
+    /// assert_eq!(offset, 3);
+    /// assert_eq!(&content[offset..], b"second\nthird\n");
+    /// buf.clear();
+    /// buf.write_all(b"again\n").expect("write ok");
+    /// assert_eq!(buf.first_complete_record_offset(), 0);
+    /// ```
+    pub fn first_complete_record_offset(&self) -> usize {
+        let earliest = self.earliest_retained_offset();
+        if self.framed || earliest <= self.floor {
+            return 0;
+        }
+        let (head, tail) = self.segments();
+        match head.iter().position(|b| *b == b'\n') {
+            Some(i) => i + 1,
+            None => match tail.iter().position(|b| *b == b'\n') {
+                Some(i) => head.len() + i + 1,
+                None => head.len() + tail.len(),
+            },
+        }
+    }
+
+    /// Return how much wall-clock time the content covers, from the earliest to the latest
+    /// timestamp of [RingFileBuilder::timestamps()](crate::RingFileBuilder::timestamps()),
+    /// None with fewer than two records stamped.
+    ///
+    /// The leading record cut by the rewinding is not counted. Costs a scan of the content.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// use std::time::Duration;
+    /// let mut buf = RingBuffer::new(96);
+    /// assert_eq!(buf.time_span(), None);
+    /// buf.write_all(b"[1700000000000000us] first\n").expect("write ok");
+    /// buf.write_all(b"no timestamp\n").expect("write ok");
+    /// assert_eq!(buf.time_span(), None);
+    /// buf.write_all(b"[1700000002500000us] second\n").expect("write ok");
+    /// assert_eq!(buf.time_span(), Some(Duration::from_millis(2500)));
+    /// buf.write_all(b"[1700000004000000us] third\n").expect("write ok");
+    /// assert_eq!(buf.time_span(), Some(Duration::from_secs(4)));
+    /// // Wrap around, "first" is overwritten and the cut "second" is skipped
+    /// buf.write_all(b"[1700000010000000us] fourth\n").expect("write ok");
+    /// buf.write_all(b"[1700000011000000us] fifth\n").expect("write ok");
+    /// assert!(!buf.read_to_vec().starts_with(b"[1700000002500000us]"));
+    /// assert_eq!(buf.time_span(), Some(Duration::from_secs(7)));
+    /// buf.clear();
+    /// assert_eq!(buf.time_span(), None);
+    /// ```
+    pub fn time_span(&self) -> Option<Duration> {
+        let mut range: Option<(SystemTime, SystemTime)> = None;
+        let mut stamped = 0;
+        for record in self.records().filter(|record| !record.is_partial()) {
+            let Some(time) = record.timestamp() else {
+                continue;
+            };
+            stamped += 1;
+            range = Some(match range {
+                Some((min, max)) => (min.min(time), max.max(time)),
+                None => (time, time),
+            });
+        }
+        let (min, max) = range.filter(|_| stamped >= 2)?;
+        Some(max.duration_since(min).unwrap_or_default())
+    }
+
+    /// Discard the content before logical `offset`, which must be a record boundary in framed mode.
+    #[inline]
+    pub(crate) fn discard_before(&mut self, offset: u64) {
+        if offset > self.floor {
+            self.floor = offset.min(self.written);
+            self.first_record = self.first_record.max(self.floor);
+        }
+    }
+
+    /// Return the counters at once.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(8);
+    /// buf.write_all(b"0123456789").expect("write ok");
+    /// let stats = buf.stats();
+    /// assert_eq!((stats.len, stats.capacity, stats.total_written), (8, 8, 10));
+    /// assert_eq!((stats.earliest_retained_offset, stats.wraps), (2, 1));
+    /// ```
+    pub fn stats(&self) -> Stats {
+        Stats {
+            len: self.len(),
+            capacity: self.capacity(),
+            total_written: self.written,
+            earliest_retained_offset: self.earliest_retained_offset(),
+            wraps: self.cur.wraps,
+            repairs: self.cur.repairs,
         }
     }
 
     #[inline]
     pub fn clear(&mut self) {
-        self.end = 0;
-        self.full = false;
+        let (wraps, repairs) = (self.cur.wraps, self.cur.repairs);
+        self.cur = Cursor::new(self.capacity());
+        self.cur.wraps = wraps;
+        self.cur.repairs = repairs;
+        self.floor = self.written;
+        self.first_record = self.written;
+        self.resync = false;
+        self.fill_unused();
+    }
+
+    /// Same as [RingBuffer::clear()], but when the last write ends in the middle of a line,
+    /// the rest of that line written afterward is discarded too, up to and including its
+    /// newline, so the content after the clear always starts with a complete record.
+    ///
+    /// clear() drops the content at once, and a record written by several write() calls
+    /// (e.g. `write!()` of a formatted line) across the clear then leaves its tail at the
+    /// start of the buffer. The bytes discarded count in [RingBuffer::total_written()] as
+    /// cleared content. Without a partial line pending, or in framed mode where every write()
+    /// is a whole record, this is the same as clear().
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(64);
+    /// buf.write_all(b"first\nsecond").expect("write ok");
+    /// buf.clear_to_record_boundary();
+    /// assert!(buf.is_empty());
+    /// // The tail of "second" is discarded, even across several writes
+    /// buf.write_all(b" line").expect("write ok");
+    /// buf.write_all(b" end\nthird\n").expect("write ok");
+    /// assert_eq!(buf.read_to_vec(), b"third\n");
+    /// assert_eq!(buf.first_complete_record_offset(), 0);
+    /// assert_eq!(buf.total_written(), 28);
+    ///
+    /// // On a line boundary, nothing more is discarded
+    /// buf.clear_to_record_boundary();
+    /// buf.write_all(b"fourth\n").expect("write ok");
+    /// assert_eq!(buf.read_to_vec(), b"fourth\n");
+    ///
+    /// // A plain clear keeps the dangling tail
+    /// buf.write_all(b"fifth").expect("write ok");
+    /// buf.clear();
+    /// buf.write_all(b" end\n").expect("write ok");
+    /// assert_eq!(buf.read_to_vec(), b" end\n");
+    /// ```
+    pub fn clear_to_record_boundary(&mut self) {
+        let (head, tail) = self.cur.split(&self.inner[self.reserved..]);
+        let last = tail.last().or(head.last()).copied();
+        let resync = self.resync || (!self.framed && last.is_some_and(|b| b != b'\n'));
+        self.clear();
+        self.resync = resync;
+    }
+
+    /// Discard the bytes just written up to the newline ending the line cut by
+    /// clear_to_record_boundary().
+    #[cold]
+    fn resync(&mut self, buf: &[u8]) {
+        let start = self.written - buf.len() as u64;
+        match buf.iter().position(|b| *b == b'\n') {
+            Some(i) => {
+                self.floor = start + i as u64 + 1;
+                self.resync = false;
+            }
+            None => self.floor = self.written,
+        }
+    }
+
+    /// Move the cursor for writing `l` bytes, return the offset and bytes to copy.
+    #[inline(always)]
+    fn advance(&self, cur: &mut Cursor, l: usize) -> (usize, usize) {
+        self.keep_contiguous(cur, l);
+        self.advance_raw(cur, l)
+    }
+
+    /// Rewind early for the no_split option, before writing a record of `l` bytes.
+    #[inline(always)]
+    fn keep_contiguous(&self, cur: &mut Cursor, l: usize) {
+        cur.keep_contiguous(l, self.no_split, self.capacity());
+    }
+
+    #[inline(always)]
+    fn advance_raw(&self, cur: &mut Cursor, l: usize) -> (usize, usize) {
+        cur.advance_raw(l, self.capacity())
+    }
+}
+
+/// Iterator returned by [RingBuffer::into_line_chunks()]
+pub struct LineChunks<'a> {
+    lines: Lines<'a>,
+    chunk: usize,
+}
+
+impl Iterator for LineChunks<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut out = Vec::new();
+        for line in self.lines.by_ref().take(self.chunk) {
+            out.extend_from_slice(&line);
+        }
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+}
+
+/// Iterator returned by [RingBuffer::lines()]
+pub struct Lines<'a> {
+    head: &'a [u8],
+    tail: &'a [u8],
+    framed: bool,
+    /// Render the stamp of the framed records
+    binary: bool,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Cow<'a, [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.framed {
+            let record = frame::next_record(&mut self.head, &mut self.tail)?;
+            if self.binary {
+                return Some(Cow::Owned(render_stamp(&record)));
+            }
+            return Some(record);
+        }
+        if self.head.is_empty() {
+            self.head = std::mem::take(&mut self.tail);
+            if self.head.is_empty() {
+                return None;
+            }
+        }
+        if let Some(i) = find_newline(self.head) {
+            let line = &self.head[0..=i];
+            self.head = &self.head[i + 1..];
+            return Some(Cow::Borrowed(line));
+        }
+        if self.tail.is_empty() {
+            return Some(Cow::Borrowed(std::mem::take(&mut self.head)));
+        }
+        // Stitch the line across the seam
+        let tail = std::mem::take(&mut self.tail);
+        let n = find_newline(tail).map(|j| j + 1).unwrap_or(tail.len());
+        let mut line = Vec::with_capacity(self.head.len() + n);
+        line.extend_from_slice(self.head);
+        line.extend_from_slice(&tail[0..n]);
+        self.head = &tail[n..];
+        Some(Cow::Owned(line))
+    }
+}
+
+#[inline(always)]
+fn find_newline(s: &[u8]) -> Option<usize> {
+    s.iter().position(|c| *c == b'\n')
+}
+
+/// Skip the first `skip` bytes of the two slices.
+#[inline]
+/// Copy the newest bytes of `head` and `tail` in order into `dst`, return the bytes copied.
+pub(crate) fn copy_segments(head: &[u8], tail: &[u8], dst: &mut [u8]) -> usize {
+    let skip = (head.len() + tail.len()).saturating_sub(dst.len());
+    let mut copied = 0;
+    if skip < head.len() {
+        copied = head.len() - skip;
+        safe_copy(&mut dst[0..copied], &head[skip..]);
+    }
+    let tail = &tail[skip.saturating_sub(head.len())..];
+    safe_copy(&mut dst[copied..copied + tail.len()], tail);
+    copied + tail.len()
+}
+
+fn skip_front<'a>(head: &'a [u8], tail: &'a [u8], skip: usize) -> (&'a [u8], &'a [u8]) {
+    if skip == 0 {
+        (head, tail)
+    } else if skip < head.len() {
+        (&head[skip..], tail)
+    } else {
+        (&tail[(skip - head.len()).min(tail.len())..], &[])
+    }
+}
+
+/// Writer across the files `{prefix}.partNNN` of at most `part_size` bytes,
+/// see [RingBuffer::dump_split()].
+pub(crate) struct SplitWriter {
+    prefix: PathBuf,
+    part_size: usize,
+    mode: u32,
+    parts: Vec<PathBuf>,
+    current: Option<BufWriter<File>>,
+    current_size: usize,
+}
+
+impl SplitWriter {
+    pub(crate) fn new(prefix: &Path, part_size: usize, mode: u32) -> Result<Self> {
+        if part_size == 0 {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, "part_size should not be 0"));
+        }
+        Ok(Self {
+            prefix: prefix.to_path_buf(),
+            part_size,
+            mode,
+            parts: Vec::new(),
+            current: None,
+            current_size: 0,
+        })
+    }
+
+    fn open_next(&mut self) -> Result<()> {
+        if let Some(mut file) = self.current.take() {
+            file.flush()?;
+        }
+        let mut name = self.prefix.as_os_str().to_owned();
+        name.push(format!(".part{:03}", self.parts.len()));
+        let path = PathBuf::from(name);
+        self.current = Some(BufWriter::new(create_file(&path, self.mode)?));
+        self.current_size = 0;
+        self.parts.push(path);
+        Ok(())
+    }
+
+    /// Flush the last part, return all the parts in order.
+    pub(crate) fn finish(mut self) -> Result<Vec<PathBuf>> {
+        if self.current.is_none() {
+            self.open_next()?;
+        }
+        if let Some(mut file) = self.current.take() {
+            file.flush()?;
+        }
+        Ok(self.parts)
+    }
+}
+
+impl Write for SplitWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.current.is_none() || self.current_size == self.part_size {
+            self.open_next()?;
+        }
+        let l = buf.len().min(self.part_size - self.current_size);
+        let l = self.current.as_mut().unwrap().write(&buf[0..l])?;
+        self.current_size += l;
+        Ok(l)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self.current.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Default permissions of the created files, see [RingBuffer::set_dump_mode()].
+pub(crate) const DEFAULT_MODE: u32 = 0o600;
+
+/// Create a truncated file for dump, with permissions `mode` on unix.
+pub(crate) fn create_file(file_path: &Path, mode: u32) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    options.open(file_path)
+}
+
+/// Create the dump file at `file_path` and fill it with `f`, handle the file by `policy` when `f` fails.
+pub(crate) fn dump_file<F>(file_path: &Path, policy: PartialDump, mode: u32, f: F) -> Result<()>
+where
+    F: FnOnce(&mut Counter<&mut File>) -> Result<()>,
+{
+    let target = if policy == PartialDump::KeepPrevious {
+        let mut name = file_path.as_os_str().to_owned();
+        name.push(".tmp");
+        Cow::Owned(PathBuf::from(name))
+    } else {
+        Cow::Borrowed(file_path)
+    };
+    let mut file = create_file(&target, mode)?;
+    let mut writer = Counter::new(&mut file);
+    if let Err(e) = f(&mut writer) {
+        let e = writer.failed(e);
+        drop(file);
+        if policy != PartialDump::Keep {
+            let _ = remove_file(&target);
+        }
+        return Err(e);
+    }
+    drop(file);
+    if policy == PartialDump::KeepPrevious {
+        rename(&target, file_path)?;
+    }
+    Ok(())
+}
+
+/// Write `lines` to `data_path`, and the index of [RingBuffer::dump_with_index()] to `index_path`.
+pub(crate) fn dump_indexed<'a, I>(
+    lines: I, data_path: &Path, index_path: &Path, mode: u32,
+) -> Result<()>
+where
+    I: Iterator<Item = Cow<'a, [u8]>>,
+{
+    let mut data = BufWriter::new(create_file(data_path, mode)?);
+    let mut index = BufWriter::new(create_file(index_path, mode)?);
+    index.write_all(b"# offset\tlen\ttime_ns\n")?;
+    let mut offset = 0;
+    for line in lines {
+        data.write_all(&line)?;
+        write!(index, "{}\t{}\t", offset, line.len())?;
+        match parse_timestamp(&line) {
+            Some((time, _)) => {
+                let nanos = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+                writeln!(index, "{}", nanos)?;
+            }
+            None => index.write_all(b"-\n")?,
+        }
+        offset += line.len();
+    }
+    data.flush()?;
+    index.flush()
+}
+
+/// Count the bytes written, to report the progress on failure.
+pub(crate) struct Counter<W: Write> {
+    inner: W,
+    written: usize,
+}
+
+impl<W: Write> Counter<W> {
+    #[inline]
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, written: 0 }
+    }
+
+    #[inline]
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Wrap the error with the bytes written before it.
+    pub(crate) fn failed(&self, e: Error) -> Error {
+        Error::new(e.kind(), format!("dump failed after {} bytes: {}", self.written, e))
+    }
+}
+
+impl<W: Write> Write for Counter<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let l = self.inner.write(buf)?;
+        self.written += l;
+        Ok(l)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
     }
 }
 
 impl std::io::Write for RingBuffer {
     /// Write will abort when reaching the boundary of buffer, rewind the offset to 0 and return the bytes written.
     /// You can use Write::write_all() provided by the trait to cover the rewinding logic.
+    ///
+    /// An empty `buf` is a no-op returning Ok(0).
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingBuffer;
+    /// use std::io::Write;
+    /// let mut buf = RingBuffer::new(16);
+    /// buf.write_all(b"0123456789abcdef").expect("write ok");
+    /// let offsets = buf.offsets();
+    /// for _ in 0..100 {
+    ///     assert_eq!(buf.write(b"").unwrap(), 0);
+    /// }
+    /// assert_eq!(buf.offsets(), offsets);
+    /// assert_eq!(buf.read_to_vec(), b"0123456789abcdef");
+    /// ```
     #[inline]
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        let bound = self.inner.capacity();
-        let l = buf.len();
-        if self.end + l >= bound {
-            self.full = true;
-            let l1 = safe_copy(&mut self.inner[self.end..], &buf);
-            self.end = 0;
-            return Ok(l1);
-        } else {
-            safe_copy(&mut self.inner[self.end..], buf);
-            self.end += l;
-            return Ok(l);
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.framed {
+            return self.write_record(buf);
+        }
+        let mut cur = self.cur;
+        let (offset, l) = self.advance(&mut cur, buf.len());
+        self.copy_at(cur, offset, &buf[0..l]);
+        if self.resync {
+            self.resync(&buf[0..l]);
         }
+        Ok(l)
     }
 
     #[inline]
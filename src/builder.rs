@@ -0,0 +1,1818 @@
+use crate::buffer::DEFAULT_MODE;
+use crate::config::RingFileConfig;
+use crate::spill::SpillConfig;
+use crate::threads::*;
+use crate::timestamp::TimestampPrecision;
+use crate::transport::{DefaultTransport, MsgTransport};
+#[cfg(feature = "backtrace")]
+use crate::Level;
+use crate::{OnDrop, PartialDump};
+use std::path::Path;
+use std::time::Duration;
+
+/// Construct a [RingFile] with optional settings.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::RingFileBuilder;
+/// use std::path::Path;
+/// let ring = RingFileBuilder::new(1024 * 1024, Path::new("/tmp/ringfile_builder.store").into())
+///     .prefault(true)
+///     .banner("my-service v1.0\n".to_string())
+///     .build()
+///     .expect("build ok");
+/// ring.write("log message\n".to_string());
+/// ring.dump().expect("dump ok");
+/// let content = std::fs::read("/tmp/ringfile_builder.store").unwrap();
+/// assert_eq!(content, b"my-service v1.0\nlog message\n");
+/// ```
+pub struct RingFileBuilder<X: MsgTransport = DefaultTransport> {
+    pub(crate) opts: Options,
+    pub(crate) transport: X,
+}
+
+/// The settings of [RingFileBuilder] apart from the transport, built by [Options::build()].
+pub(crate) struct Options {
+    pub(crate) buf_size: i32,
+    pub(crate) file_path: Box<Path>,
+    pub(crate) path_fn: Option<PathFn>,
+    pub(crate) prefault: bool,
+    pub(crate) banner: Option<String>,
+    pub(crate) sticky_banner: Option<String>,
+    pub(crate) run_id: Option<String>,
+    pub(crate) labels: Vec<(String, String)>,
+    pub(crate) manifest: Option<Box<Path>>,
+    pub(crate) categories: (usize, i32),
+    pub(crate) flush_every: usize,
+    pub(crate) spill: Option<SpillConfig>,
+    pub(crate) live_file: Option<(Box<Path>, u64)>,
+    pub(crate) live_flush_interval: Option<Duration>,
+    pub(crate) mirror: Option<(MirrorRegion, Duration)>,
+    pub(crate) tcp_timeout: Duration,
+    pub(crate) strict_order: Option<(usize, Duration)>,
+    pub(crate) max_dumps_per: Option<(usize, Duration)>,
+    pub(crate) sample: Option<(usize, usize)>,
+    pub(crate) max_queued_bytes: usize,
+    pub(crate) timestamps: bool,
+    pub(crate) timestamp_precision: TimestampPrecision,
+    pub(crate) uptime: bool,
+    pub(crate) binary_uptime: bool,
+    pub(crate) thread_names: bool,
+    pub(crate) per_thread_seq: bool,
+    pub(crate) indent: bool,
+    pub(crate) utf8: Utf8Mode,
+    pub(crate) max_line_len: usize,
+    pub(crate) armed: bool,
+    pub(crate) on_message: Option<OnMessage>,
+    pub(crate) large_writes: Option<(usize, OnLargeWrite)>,
+    pub(crate) on_drop: Option<OnDrop>,
+    pub(crate) skip_empty: bool,
+    pub(crate) strip_ansi: bool,
+    pub(crate) histogram: bool,
+    pub(crate) end_marker: bool,
+    pub(crate) generations: usize,
+    pub(crate) dedup: bool,
+    pub(crate) max_total_bytes: u64,
+    pub(crate) on_frozen: Option<OnFrozen>,
+    pub(crate) reuse_dump_file: bool,
+    pub(crate) partial_dump: PartialDump,
+    pub(crate) dump_mode: u32,
+    #[cfg(feature = "backtrace")]
+    pub(crate) backtrace_levels: u8,
+    pub(crate) align: usize,
+    pub(crate) dump_on_idle: Option<Duration>,
+    pub(crate) no_split: usize,
+    pub(crate) inline: bool,
+    pub(crate) line_mode: bool,
+    pub(crate) framed: bool,
+}
+
+impl RingFileBuilder {
+    /// See [RingFile::new()] for the arguments.
+    pub fn new(buf_size: i32, file_path: Box<Path>) -> Self {
+        Self {
+            opts: Options {
+                buf_size,
+                file_path,
+                path_fn: None,
+                prefault: false,
+                banner: None,
+                sticky_banner: None,
+                run_id: None,
+                labels: Vec::new(),
+                manifest: None,
+                categories: (16, 64 * 1024),
+                flush_every: 0,
+                spill: None,
+                live_file: None,
+                live_flush_interval: None,
+                mirror: None,
+                tcp_timeout: DEFAULT_TCP_TIMEOUT,
+                strict_order: None,
+                max_dumps_per: None,
+                sample: None,
+                max_queued_bytes: 0,
+                timestamps: false,
+                timestamp_precision: TimestampPrecision::Micros,
+                uptime: false,
+                binary_uptime: false,
+                thread_names: false,
+                per_thread_seq: false,
+                indent: false,
+                utf8: Utf8Mode::Off,
+                max_line_len: 0,
+                armed: true,
+                on_message: None,
+                large_writes: None,
+                on_drop: None,
+                skip_empty: false,
+                strip_ansi: false,
+                histogram: false,
+                end_marker: false,
+                generations: 0,
+                dedup: false,
+                max_total_bytes: 0,
+                on_frozen: None,
+                reuse_dump_file: false,
+                partial_dump: PartialDump::Keep,
+                dump_mode: DEFAULT_MODE,
+                #[cfg(feature = "backtrace")]
+                backtrace_levels: 0,
+                align: 0,
+                dump_on_idle: None,
+                no_split: 0,
+                inline: false,
+                line_mode: false,
+                framed: false,
+            },
+            transport: DefaultTransport,
+        }
+    }
+
+    /// Same as [RingFileBuilder::build()], but return the backend as a [RingFileTask] to be
+    /// spawned on an async runtime, see [RingFile::spawn_on()].
+    ///
+    /// Only for the default transport, as the task has its own channel.
+    /// Returns error with [RingFileBuilder::inline()], which has no backend, and with
+    /// [RingFileBuilder::dump_on_idle()] or
+    /// [RingFileBuilder::live_flush_interval()] or [RingFileBuilder::mirror()] or
+    /// [RingFileBuilder::strict_order()], which need a timer the task does not have.
+    pub fn build_task(self) -> std::io::Result<(RingFile, RingFileTask)> {
+        self.opts.build_task()
+    }
+}
+
+impl<X: MsgTransport> RingFileBuilder<X> {
+    /// Commit all the memory on construction, see
+    /// [RingBuffer::prefault()](crate::RingBuffer::prefault()). (default false)
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.opts.prefault = prefault;
+        self
+    }
+
+    /// Write the banner into the ring on construction, before any other content.
+    ///
+    /// **NOTE**: Once the ring rewinds, the banner will be overwritten like other content.
+    /// Use [RingFileBuilder::sticky_banner()] if you want it in every dump.
+    ///
+    /// build() returns error when the banner is larger than buf_size, counting the padding of
+    /// [RingFileBuilder::align()], and the stamp of [RingFileBuilder::binary_uptime()] with the
+    /// prefix of [RingFileBuilder::framed()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_banner.store");
+    /// let banner = "b".repeat(60);
+    /// assert!(RingFileBuilder::new(64, path.into()).banner(banner.clone()).build().is_ok());
+    /// // Too large once stamped
+    /// let stamped = RingFileBuilder::new(64, path.into())
+    ///     .framed(true)
+    ///     .binary_uptime(true)
+    ///     .banner(banner)
+    ///     .build();
+    /// assert_eq!(stamped.err().unwrap().kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn banner(mut self, banner: String) -> Self {
+        self.opts.banner = Some(banner);
+        self
+    }
+
+    /// Keep the banner out of the ring, and write it at the beginning of every dump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let ring = RingFileBuilder::new(16, Path::new("/tmp/ringfile_sticky.store").into())
+    ///     .sticky_banner("banner\n".to_string())
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("0123456789abcdef0123456789\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read("/tmp/ringfile_sticky.store").unwrap();
+    /// assert_eq!(content, b"banner\nbcdef0123456789\n");
+    /// ```
+    pub fn sticky_banner(mut self, banner: String) -> Self {
+        self.opts.sticky_banner = Some(banner);
+        self
+    }
+
+    /// Tag the ring with the identifier of the process run, e.g. a ULID generated at startup,
+    /// to tell the dumps of different runs apart. (default None)
+    ///
+    /// dump() writes to `{stem}.{run_id}.{ext}` instead of file_path, e.g. `ring.01HV3K.store`
+    /// for `ring.store`, and every dump starts with the line `[ring-file: run {run_id}]`.
+    /// The id is not added to the path returned by [RingFileBuilder::path_fn()].
+    ///
+    /// The id should only contain ASCII letters, digits, `-` or `_`, otherwise build()
+    /// returns error of `ErrorKind::InvalidInput`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_run.store");
+    /// let first = RingFileBuilder::new(1024, path.into()).run_id("run-1".to_string()).build().unwrap();
+    /// let second = RingFileBuilder::new(1024, path.into()).run_id("run-2".to_string()).build().unwrap();
+    /// first.write("log message\n".to_string());
+    /// second.write("log message\n".to_string());
+    /// first.dump().expect("dump ok");
+    /// second.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string("/tmp/ringfile_run.run-1.store").unwrap();
+    /// assert_eq!(content, "[ring-file: run run-1]\nlog message\n");
+    /// let content = std::fs::read_to_string("/tmp/ringfile_run.run-2.store").unwrap();
+    /// assert_eq!(content, "[ring-file: run run-2]\nlog message\n");
+    /// assert_eq!(second.dump_size().unwrap(), content.len());
+    /// let res = RingFileBuilder::new(1024, path.into()).run_id("../run".to_string()).build();
+    /// assert_eq!(res.err().unwrap().kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn run_id(mut self, run_id: String) -> Self {
+        self.opts.run_id = Some(run_id);
+        self
+    }
+
+    /// Attach the label `key=value` to the ring, e.g. the host, region or version, to make the
+    /// dumps collected from many instances self-describing. Can be called several times.
+    ///
+    /// Every dump starts with a line `[ring-file: label {key}={value}]` per label, in the order
+    /// of the calls, after the line of [RingFileBuilder::run_id()]. The labels are also written
+    /// to the manifest (see [RingFileBuilder::manifest()]).
+    ///
+    /// The key should only contain ASCII letters, digits, `-`, `_` or `.`, and the value no
+    /// control character or `,`, otherwise build() returns error of `ErrorKind::InvalidInput`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_labels.store");
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .run_id("run-1".to_string())
+    ///     .label("host".to_string(), "web-1".to_string())
+    ///     .label("region".to_string(), "eu west".to_string())
+    ///     .label("version".to_string(), "1.2.3".to_string())
+    ///     .build()
+    ///     .unwrap();
+    /// ring.write("log message\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string("/tmp/ringfile_labels.run-1.store").unwrap();
+    /// assert_eq!(
+    ///     content,
+    ///     "[ring-file: run run-1]\n[ring-file: label host=web-1]\n\
+    ///      [ring-file: label region=eu west]\n[ring-file: label version=1.2.3]\nlog message\n"
+    /// );
+    /// assert_eq!(ring.dump_size().unwrap(), content.len());
+    /// let res = RingFileBuilder::new(1024, path.into())
+    ///     .label("host".to_string(), "web-1\n".to_string())
+    ///     .build();
+    /// assert_eq!(res.err().unwrap().kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn label(mut self, key: String, value: String) -> Self {
+        self.opts.labels.push((key, value));
+        self
+    }
+
+    /// Keep an index of the dump files at `path`, for a collector to enumerate the dumps
+    /// without opening each. (default None)
+    ///
+    /// After every dump() (and the dumps of [RingFileBuilder::flush_every()] and
+    /// [RingFileBuilder::dump_on_idle()]), the manifest is rewritten into `{path}.tmp` and renamed
+    /// over `path`, so a reader never sees it half written. The entries of the files no longer
+    /// existing are dropped, and a file dumped again replaces its entry, so with
+    /// [RingFileBuilder::path_fn()] the manifest follows the files left after a cleanup.
+    /// The entries of a previous run are loaded on build().
+    ///
+    /// The manifest is plain text: a header line starting with `#`, then one line per dump
+    /// in the order of the dumps, of the tab-separated columns:
+    /// milliseconds since UNIX epoch, byte size, wraps of the buffer (see [Stats](crate::Stats)),
+    /// [RingFileBuilder::run_id()] or `-`, the `key=value` of [RingFileBuilder::label()]
+    /// joined by `,` or `-`, and the path last.
+    /// The dump returns the error of writing the manifest, after the dump file is written.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::{Path, PathBuf};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// let manifest = Path::new("/tmp/ringfile_manifest.tsv");
+    /// let _ = std::fs::remove_file(manifest);
+    /// let seq = AtomicUsize::new(0);
+    /// let ring = RingFileBuilder::new(16, Path::new("/tmp/ringfile_unused.store").into())
+    ///     .path_fn(Box::new(move || {
+    ///         let n = seq.fetch_add(1, Ordering::Relaxed) % 3;
+    ///         PathBuf::from(format!("/tmp/ringfile_rotated_{}.store", n))
+    ///     }))
+    ///     .run_id("run-1".to_string())
+    ///     .label("host".to_string(), "web-1".to_string())
+    ///     .manifest(manifest.into())
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..5 {
+    ///     ring.write(format!("line {}\n", i));
+    ///     ring.dump().expect("dump ok");
+    /// }
+    /// // The collector removed a dump
+    /// std::fs::remove_file("/tmp/ringfile_rotated_1.store").unwrap();
+    /// ring.write("line 5\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(manifest).unwrap();
+    /// let mut lines = content.lines();
+    /// assert!(lines.next().unwrap().starts_with('#'));
+    /// let entries: Vec<Vec<&str>> = lines.map(|line| line.split('\t').collect()).collect();
+    /// let files: Vec<&str> = entries.iter().map(|entry| entry[5]).collect();
+    /// assert_eq!(files, ["/tmp/ringfile_rotated_0.store", "/tmp/ringfile_rotated_2.store"]);
+    /// for entry in entries.iter() {
+    ///     let size: u64 = entry[1].parse().unwrap();
+    ///     assert_eq!(size, std::fs::metadata(entry[5]).unwrap().len());
+    ///     assert_eq!((entry[3], entry[4]), ("run-1", "host=web-1"));
+    /// }
+    /// // Dumped after line 3 and line 5, when the 16 bytes have wrapped once and twice
+    /// assert_eq!((entries[0][2], entries[1][2]), ("1", "2"));
+    /// ```
+    pub fn manifest(mut self, path: Box<Path>) -> Self {
+        self.opts.manifest = Some(path);
+        self
+    }
+
+    /// Keep at most `max` categories of [RingFile::write_category()], each in a buffer of
+    /// `buf_size`. (default 16 categories of 64KB)
+    ///
+    /// The buffers are allocated on first use. Both should be positive, otherwise build()
+    /// returns error of `ErrorKind::InvalidInput`.
+    pub fn categories(mut self, max: usize, buf_size: i32) -> Self {
+        self.opts.categories = (max, buf_size);
+        self
+    }
+
+    /// Let the backend dump to file_path after every `n` messages written. (default 0, disabled)
+    ///
+    /// The counter is independent of other triggers, an explicit dump() does not reset it,
+    /// so whichever trigger fires first will do the dump.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_flush_every.store");
+    /// let _ = std::fs::remove_file(path);
+    /// let ring = RingFileBuilder::new(1024, path.into()).flush_every(3).build().expect("build ok");
+    /// ring.write("1\n".to_string());
+    /// ring.write("2\n".to_string());
+    /// ring.flush();
+    /// assert!(!path.exists());
+    /// ring.write("3\n".to_string());
+    /// ring.write("4\n".to_string());
+    /// ring.flush();
+    /// assert_eq!(std::fs::read(path).unwrap(), b"1\n2\n3\n");
+    /// ```
+    pub fn flush_every(mut self, n: usize) -> Self {
+        self.opts.flush_every = n;
+        self
+    }
+
+    /// Instead of losing the content overwritten by rewinding, append it to a spill file.
+    ///
+    /// The spill file is truncated on build(). Concatenating the spill file and a dump
+    /// reproduces the whole history.
+    ///
+    /// **NOTE**: This brings back disk IO into the backend (buffered, only for evicted data),
+    /// which is what the ring tries to avoid. The backend may fall behind under heavy load,
+    /// it does not block the writers though.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_spill.store");
+    /// let spill = Path::new("/tmp/ringfile_spill.store.spill");
+    /// let ring = RingFileBuilder::new(100, path.into()).spill(spill.into()).build().expect("build ok");
+    /// let mut expected = Vec::new();
+    /// for i in 0..100 {
+    ///     let line = format!("line {}\n", i);
+    ///     expected.extend_from_slice(line.as_bytes());
+    ///     ring.write(line);
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// let mut history = std::fs::read(spill).unwrap();
+    /// history.extend(std::fs::read(path).unwrap());
+    /// assert_eq!(history, expected);
+    /// ```
+    pub fn spill(mut self, spill_path: Box<Path>) -> Self {
+        self.opts.spill = Some(SpillConfig::Plain(spill_path));
+        self
+    }
+
+    /// Like [RingFileBuilder::spill()], but compress the evicted content into rotating gzip files
+    /// `{prefix}.0.gz`, `{prefix}.1.gz`, ..., so that a long run keeps bounded RAM and a compact
+    /// history on disk.
+    ///
+    /// A new file starts after `file_size` bytes (before compression) written to the current one.
+    /// (0 for a single file) Only the newest `max_files` files are kept, the older ones are removed.
+    /// (0 for unlimited) Files left by previous runs are not removed, but overwritten when their
+    /// names come up.
+    ///
+    /// Use [RingFile::reconstruct()] to get back the history kept.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_spill_gz.store");
+    /// let prefix = Path::new("/tmp/ringfile_spill_gz");
+    /// let ring = RingFileBuilder::new(100, path.into())
+    ///     .spill_gz(prefix.into(), 1000, 0)
+    ///     .build()
+    ///     .expect("build ok");
+    /// let mut expected = Vec::new();
+    /// for i in 0..1000 {
+    ///     let line = format!("line {}\n", i);
+    ///     expected.extend_from_slice(line.as_bytes());
+    ///     ring.write(line);
+    /// }
+    /// let output = Path::new("/tmp/ringfile_spill_gz.history");
+    /// ring.reconstruct(output).expect("reconstruct ok");
+    /// assert_eq!(std::fs::read(output).unwrap(), expected);
+    /// // The ring goes on after reconstruct
+    /// ring.write("line 1000\n".to_string());
+    /// expected.extend_from_slice(b"line 1000\n");
+    /// ring.reconstruct(output).expect("reconstruct ok");
+    /// assert_eq!(std::fs::read(output).unwrap(), expected);
+    /// assert!(Path::new("/tmp/ringfile_spill_gz.8.gz").exists());
+    /// ```
+    ///
+    /// With `max_files`, only the recent history is reconstructed:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_spill_gz_rotate.store");
+    /// let prefix = Path::new("/tmp/ringfile_spill_gz_rotate");
+    /// let ring = RingFileBuilder::new(100, path.into())
+    ///     .spill_gz(prefix.into(), 1000, 2)
+    ///     .build()
+    ///     .expect("build ok");
+    /// let mut expected = Vec::new();
+    /// for i in 0..1000 {
+    ///     let line = format!("line {}\n", i);
+    ///     expected.extend_from_slice(line.as_bytes());
+    ///     ring.write(line);
+    /// }
+    /// let output = Path::new("/tmp/ringfile_spill_gz_rotate.history");
+    /// ring.reconstruct(output).expect("reconstruct ok");
+    /// let history = std::fs::read(output).unwrap();
+    /// assert!(history.len() <= 2 * 1000 + 100);
+    /// assert!(expected.ends_with(&history));
+    /// assert!(!Path::new("/tmp/ringfile_spill_gz_rotate.0.gz").exists());
+    /// ```
+    pub fn spill_gz(mut self, prefix: Box<Path>, file_size: usize, max_files: usize) -> Self {
+        self.opts.spill = Some(SpillConfig::Gzip { prefix, file_size, max_files });
+        self
+    }
+
+    /// Besides the ring, append every message to an always-open file at `path`,
+    /// so that the log survives even when the process is killed without a chance to dump.
+    ///
+    /// The file is truncated on build(). The appended content is pushed to the OS whenever the
+    /// backend has no more message queued, and on [RingFile::flush()]
+    /// (for [RingFile::new_inline()], after every message), so a SIGKILL loses at most the messages still in the channel.
+    /// See [RingFileBuilder::live_flush_interval()] to flush less often.
+    /// When the file grows beyond `max_size` bytes, it is renamed to `{path}.1` (replacing the
+    /// previous one) and a new file is started. (0 for unlimited)
+    ///
+    /// **NOTE**: This costs a write syscall per batch of messages and up to `2 * max_size` bytes
+    /// of disk, which the pure in-memory ring avoids. The ring itself, and dump(), are not affected.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let live = Path::new("/tmp/ringfile_live.log");
+    /// let ring = RingFileBuilder::new(16, Path::new("/tmp/ringfile_live.store").into())
+    ///     .live_file(live.into(), 16)
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..3 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.flush();
+    /// assert_eq!(std::fs::read("/tmp/ringfile_live.log.1").unwrap(), b"line 0\nline 1\n");
+    /// assert_eq!(std::fs::read(live).unwrap(), b"line 2\n");
+    /// // The options are checked before the file is truncated
+    /// let rejected = RingFileBuilder::new(16, Path::new("/tmp/ringfile_live.store").into())
+    ///     .live_file(live.into(), 16)
+    ///     .max_dumps_per(0, Duration::from_secs(1))
+    ///     .build();
+    /// assert!(rejected.is_err());
+    /// assert_eq!(std::fs::read(live).unwrap(), b"line 2\n");
+    /// ```
+    pub fn live_file(mut self, path: Box<Path>, max_size: u64) -> Self {
+        self.opts.live_file = Some((path, max_size));
+        self
+    }
+
+    /// Push the content of [RingFileBuilder::live_file()] to the OS at most once per `interval`,
+    /// instead of whenever the queue drains, to batch the write syscalls under steady load.
+    /// The live file is still flushed on dump(), [RingFile::flush()] and [RingFile::shutdown()].
+    /// For [RingFile::new_inline()] without the timer, the interval is checked on each message.
+    ///
+    /// **NOTE**: On a hard crash, up to one interval of messages is missing from the live file,
+    /// while the ring still has them for dump().
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let live = Path::new("/tmp/ringfile_live_interval.log");
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_live_interval.store").into())
+    ///     .live_file(live.into(), 0)
+    ///     .live_flush_interval(Duration::from_millis(300))
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("line 0\n".to_string());
+    /// // Round trip to the backend, which does not flush the live file
+    /// ring.dump_size().unwrap();
+    /// std::thread::sleep(Duration::from_millis(100));
+    /// assert_eq!(std::fs::read(live).unwrap(), b"");
+    /// std::thread::sleep(Duration::from_millis(400));
+    /// assert_eq!(std::fs::read(live).unwrap(), b"line 0\n");
+    /// ring.write("line 1\n".to_string());
+    /// ring.flush();
+    /// assert_eq!(std::fs::read(live).unwrap(), b"line 0\nline 1\n");
+    /// ```
+    pub fn live_flush_interval(mut self, interval: Duration) -> Self {
+        self.opts.live_flush_interval = Some(interval);
+        self
+    }
+
+    /// Keep a copy of the newest content in `region`, e.g. a memory range registered with a
+    /// crash reporter which snapshots it on crash. (default None)
+    ///
+    /// The backend copies the buffer with
+    /// [RingBuffer::mirror_into()](crate::RingBuffer::mirror_into()) at most every `interval` after
+    /// the messages, and on shutdown(). The copy is done under the lock of the region: a reader
+    /// locking it sees a whole copy, and while it holds the lock, the backend skips the copy rather
+    /// than wait. A crash reporter reading the memory without the lock may see a copy half updated.
+    /// The Box is never replaced, so its address can be registered once. For
+    /// [RingFile::new_inline()] without the timer, the interval is checked on each message. The
+    /// filler of [RingFileBuilder::align()] is copied as is.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    /// let region = Arc::new(Mutex::new(vec![0u8; 16].into_boxed_slice()));
+    /// let ring = RingFileBuilder::new(64, Path::new("/tmp/ringfile_mirror.store").into())
+    ///     .mirror(region.clone(), Duration::from_millis(50))
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("first\n".to_string());
+    /// ring.flush();
+    /// std::thread::sleep(Duration::from_millis(200));
+    /// assert_eq!(&region.lock().unwrap()[..], b"first\n\0\0\0\0\0\0\0\0\0\0");
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.flush();
+    /// std::thread::sleep(Duration::from_millis(200));
+    /// // The newest 16 bytes
+    /// assert_eq!(&region.lock().unwrap()[..], b"7\nline 8\nline 9\n");
+    /// ```
+    pub fn mirror(mut self, region: MirrorRegion, interval: Duration) -> Self {
+        self.opts.mirror = Some((region, interval));
+        self
+    }
+
+    /// The timeout of [RingFile::dump_to_tcp()] to connect, and of each write. (default 10s)
+    pub fn tcp_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.tcp_timeout = timeout;
+        self
+    }
+
+    /// Drop the message when the bytes queued for the backend would exceed `max`, to bound
+    /// the memory held by the channel even when a few messages are huge. (default 0, unlimited)
+    ///
+    /// The bytes include the prefixes, see [RingFile::queued_bytes()]. The number dropped is
+    /// returned by [RingFile::queue_overflowed()]. A message larger than `max` is always dropped.
+    /// Has no effect with [RingFileBuilder::inline()], which has no queue.
+    pub fn max_queued_bytes(mut self, max: usize) -> Self {
+        self.opts.max_queued_bytes = max;
+        self
+    }
+
+    /// Sample out messages when the writers outpace the backend, instead of letting
+    /// the queue grow without bound. (default disabled)
+    ///
+    /// Once more than `high` messages are queued, only 1 in `2 + depth / high` messages
+    /// is kept, so the dump still reflects the overall pattern. The sampling stops when the queue
+    /// drains below `low`. The number dropped is returned by [RingFile::sampled_out()],
+    /// and noted at the end of every dump as `[ring-file: N messages sampled out]`.
+    ///
+    /// Has no effect with [RingFileBuilder::inline()], which has no queue.
+    /// build() returns error when `high` is 0 or `low` is larger than `high`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::{mpsc, Arc, Mutex};
+    /// let path = Path::new("/tmp/ringfile_sample.store");
+    /// let reported = Arc::new(AtomicU64::new(0));
+    /// let _reported = reported.clone();
+    /// let ring = RingFileBuilder::new(1024 * 1024, path.into())
+    ///     .adaptive_sample(100, 10)
+    ///     .on_drop(Box::new(move |_, _| {
+    ///         _reported.fetch_add(1, Ordering::Relaxed);
+    ///     }))
+    ///     .build()
+    ///     .expect("build ok");
+    /// let ring = Arc::new(ring);
+    /// ring.write("start\n".to_string());
+    /// // Keep the backend busy, to simulate overload
+    /// let (started_tx, started_rx) = mpsc::channel();
+    /// let (release_tx, release_rx) = mpsc::channel::<()>();
+    /// let (started_tx, release_rx) = (Mutex::new(started_tx), Mutex::new(release_rx));
+    /// let busy = ring.clone();
+    /// let th = std::thread::spawn(move || {
+    ///     busy.dump_filtered("/tmp/ringfile_sample.busy", move |_| {
+    ///         started_tx.lock().unwrap().send(()).unwrap();
+    ///         release_rx.lock().unwrap().recv().unwrap();
+    ///         true
+    ///     })
+    /// });
+    /// started_rx.recv().unwrap();
+    /// for i in 0..1000 {
+    ///     ring.write(format!("overload {}\n", i));
+    /// }
+    /// let dropped = ring.sampled_out();
+    /// assert!(dropped > 500 && dropped < 900);
+    /// assert_eq!(reported.load(Ordering::Relaxed), dropped);
+    /// release_tx.send(()).unwrap();
+    /// th.join().unwrap().expect("dump ok");
+    /// ring.flush();
+    /// // The queue is drained, sampling stops
+    /// for i in 0..10 {
+    ///     ring.write(format!("recovered {}\n", i));
+    /// }
+    /// assert_eq!(ring.sampled_out(), dropped);
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert!(content.contains("recovered 9\n"));
+    /// assert_eq!(content.lines().count() as u64, 1 + 1000 - dropped + 10 + 1);
+    /// assert!(content.ends_with(&format!("[ring-file: {} messages sampled out]\n", dropped)));
+    /// ```
+    pub fn adaptive_sample(mut self, high: usize, low: usize) -> Self {
+        self.opts.sample = Some((high, low));
+        self
+    }
+
+    /// Prefix every message with the wall-clock time when written, as the integer count of
+    /// [RingFileBuilder::timestamp_precision()] since UNIX epoch, e.g.
+    /// `[1700000000123456us] log message`. (default false)
+    ///
+    /// The time is taken on the caller's thread, before the message is queued. See
+    /// [RingFile::dump_time_range()] to dump an incident window, and
+    /// [decode_timestamps()](crate::decode_timestamps()) to convert a dump to human-readable time.
+    pub fn timestamps(mut self, timestamps: bool) -> Self {
+        self.opts.timestamps = timestamps;
+        self
+    }
+
+    /// Pad each message with `\0` up to a multiple of `align` bytes, so that every message starts
+    /// at an aligned offset in the buffer, which may let memcpy take the faster path for large
+    /// messages on some platforms. (default 0, disabled)
+    ///
+    /// The padding is skipped in dumps (so is any `\0` in the messages), but costs up to
+    /// `align - 1` bytes of the buffer per message, which is significant for short messages.
+    /// The padding also counts into the limit of [RingFileBuilder::no_split()].
+    ///
+    /// build() returns error unless `align` is a power of two up to 4096 and divides buf_size.
+    /// Measure with `examples/bench_align.rs` before turning it on, the gain depends on the
+    /// platform, and the extra bytes copied may outweigh it.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_align.store");
+    /// let ring = RingFileBuilder::new(64, path.into()).align(16).build().expect("build ok");
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// assert_eq!(ring.dump_size().unwrap(), 4 * 7);
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 6\nline 7\nline 8\nline 9\n");
+    /// ```
+    pub fn align(mut self, align: usize) -> Self {
+        self.opts.align = align;
+        self
+    }
+
+    /// Prefix every message with the monotonic time elapsed since build(), as `[+{nanos}] `,
+    /// e.g. `[+1250000] log message`. (default false)
+    ///
+    /// Unlike the wall-clock, it never jumps backward on clock adjustment, so it gives
+    /// a reliable ordering. The time is taken on the caller's thread, before the message is queued.
+    /// With [RingFileBuilder::timestamps()] also enabled, it follows the wall-clock prefix.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_uptime.store");
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .timestamps(true)
+    ///     .uptime(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("first\n".to_string());
+    /// std::thread::sleep(Duration::from_millis(10));
+    /// ring.write("second\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let uptime: Vec<u64> = content
+    ///     .lines()
+    ///     .map(|line| {
+    ///         let column = line.split(' ').nth(1).unwrap();
+    ///         column.trim_start_matches("[+").trim_end_matches(']').parse().unwrap()
+    ///     })
+    ///     .collect();
+    /// assert!(uptime[1] >= uptime[0] + 10_000_000);
+    /// ```
+    pub fn uptime(mut self, uptime: bool) -> Self {
+        self.opts.uptime = uptime;
+        self
+    }
+
+    /// Stamp each message with the uptime as 8 bytes of binary nanoseconds, instead of the text
+    /// prefix of [RingFileBuilder::uptime()]. (default false) Requires [RingFileBuilder::framed()].
+    ///
+    /// The writer only copies the integer, skipping the formatting of the digits. The stamp is
+    /// rendered as the `[+{nanos}] ` prefix when reading, in the dumps,
+    /// [RingBuffer::lines()](crate::RingBuffer::lines()) and
+    /// [RingBuffer::records()](crate::RingBuffer::records()), so the output is the same as with the
+    /// text prefix. [RingBuffer::segments()](crate::RingBuffer::segments()) gives the raw stamps.
+    /// Takes over [RingFileBuilder::uptime()]. See examples/bench_binary_uptime.rs for the cost of
+    /// both on the writer.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_binary_uptime.store");
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .framed(true)
+    ///     .binary_uptime(true)
+    ///     .timestamps(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("first\n".to_string());
+    /// std::thread::sleep(Duration::from_millis(10));
+    /// ring.write("second\n".to_string());
+    /// let buf = ring.take_buffer().expect("take ok");
+    /// let records: Vec<_> = buf.records().collect();
+    /// assert_eq!(records[0].body(), b"first");
+    /// assert!(records[0].timestamp().is_some());
+    /// let uptime: Vec<Duration> = records.iter().map(|r| r.uptime().unwrap()).collect();
+    /// assert!(uptime[1] >= uptime[0] + Duration::from_millis(10));
+    /// // Stored as 8 bytes, rendered like the text prefix
+    /// let (head, _) = buf.segments();
+    /// assert_eq!(&head[1..9], &(uptime[0].as_nanos() as u64).to_le_bytes());
+    /// buf.dump(path).expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let line = content.lines().next().unwrap();
+    /// let expected = format!("] [+{}] first", uptime[0].as_nanos());
+    /// assert!(line.starts_with('[') && line.ends_with(&expected));
+    /// // Refused without framed
+    /// assert!(RingFileBuilder::new(1024, path.into()).binary_uptime(true).build().is_err());
+    /// ```
+    pub fn binary_uptime(mut self, binary_uptime: bool) -> Self {
+        self.opts.binary_uptime = binary_uptime;
+        self
+    }
+
+    /// Prefix each message with the name of the writing thread as `[@{name}] `, after the time
+    /// prefixes, e.g. `[@worker-1] W retry`. An unnamed thread shows as `ThreadId(N)`.
+    /// (default false) Required by [RingFile::dump_grouped()].
+    pub fn thread_names(mut self, thread_names: bool) -> Self {
+        self.opts.thread_names = thread_names;
+        self
+    }
+
+    /// Prefix every message with its sequence in the writing thread, e.g. `[#42] log message`,
+    /// after the thread name of [RingFileBuilder::thread_names()]. (default false)
+    ///
+    /// The sequence is counted on the caller's thread before any drop, so a gap in the sequence
+    /// of a thread tells exactly how many of its messages were lost, e.g. by
+    /// [RingFileBuilder::max_queued_bytes()] or [RingFileBuilder::adaptive_sample()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_per_thread_seq.store");
+    /// let (ring, driver) = RingFileBuilder::new(1024, path.into())
+    ///     .per_thread_seq(true)
+    ///     .max_queued_bytes(40)
+    ///     .build_detached()
+    ///     .expect("build ok");
+    /// let ring = std::sync::Arc::new(ring);
+    /// // The backend is not running yet, the queue overflows
+    /// for i in 0..5 {
+    ///     ring.write(format!("main {}\n", i));
+    /// }
+    /// let th = std::thread::spawn(move || driver.run());
+    /// ring.flush();
+    /// let other = ring.clone();
+    /// std::thread::spawn(move || other.write("other\n".to_string())).join().unwrap();
+    /// ring.write("main 5\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let lines: Vec<&str> = content.lines().collect();
+    /// assert_eq!(
+    ///     lines[0..5],
+    ///     ["[#1] main 0", "[#2] main 1", "[#3] main 2", "[#1] other", "[#6] main 5"]
+    /// );
+    /// ring.shutdown();
+    /// th.join().unwrap();
+    /// ```
+    pub fn per_thread_seq(mut self, per_thread_seq: bool) -> Self {
+        self.opts.per_thread_seq = per_thread_seq;
+        self
+    }
+
+    /// Store the messages of all the threads in the order of a global sequence taken by each
+    /// write, instead of the order they reach the backend, for reproducible dumps of concurrent
+    /// writers. (default None)
+    ///
+    /// The sequence is prefixed like [RingFileBuilder::per_thread_seq()], e.g. `[#42] message`,
+    /// counting from 1, and parsed by [Record::sequence()](crate::Record::sequence()).
+    /// A message arriving ahead of its turn waits in a reorder buffer until the messages before
+    /// it arrive. When more than `window` messages are waiting, or none of the missing arrives
+    /// within `timeout`, the gap is skipped, e.g. for a message dropped on the queue overflow,
+    /// and a message arriving after its gap was skipped is stored at once, out of order. Every
+    /// request to the backend (dump, flush, ...) also stores the waiting messages first.
+    ///
+    /// Costs: each write contends on one atomic counter, the messages behind a gap are delayed
+    /// by up to `timeout`, and the reorder buffer holds up to `window` messages in memory on top
+    /// of the queue. The messages of [RingFile::write_category()] are not ordered.
+    ///
+    /// build() returns error when `window` is 0, or combined with
+    /// [RingFileBuilder::per_thread_seq()] or [RingFileBuilder::inline()]. The timeout needs the
+    /// backend thread, see [RingFileBuilder::build_task()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_strict_order.store");
+    /// let ring = RingFileBuilder::new(1024 * 1024, path.into())
+    ///     .strict_order(4096, Duration::from_secs(10))
+    ///     .build()
+    ///     .expect("build ok");
+    /// let ring = Arc::new(ring);
+    /// let threads: Vec<_> = (0..8)
+    ///     .map(|t| {
+    ///         let ring = ring.clone();
+    ///         std::thread::spawn(move || {
+    ///             for i in 0..500 {
+    ///                 ring.write(format!("thread {} message {}\n", t, i));
+    ///             }
+    ///         })
+    ///     })
+    ///     .collect();
+    /// for th in threads {
+    ///     th.join().unwrap();
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let seqs: Vec<u64> = content
+    ///     .lines()
+    ///     .map(|line| line[2..line.find(']').unwrap()].parse().unwrap())
+    ///     .collect();
+    /// assert_eq!(seqs, (1..=4000).collect::<Vec<_>>());
+    /// ```
+    pub fn strict_order(mut self, window: usize, timeout: Duration) -> Self {
+        self.opts.strict_order = Some((window, timeout));
+        self
+    }
+
+    /// Indent each message by 2 spaces per [Span](crate::Span) open on the writing thread, so the
+    /// nested spans read like a call tree in the dump. (default false)
+    ///
+    /// The depth is tracked per thread across all the RingFile. The spaces go after the level
+    /// prefix, so [RingFile::dump_min_level()] still works.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_indent.store");
+    /// let ring = RingFileBuilder::new(1024, path.into()).indent(true).build().expect("build ok");
+    /// {
+    ///     let _outer = ring.span("outer");
+    ///     ring.write("in outer\n".to_string());
+    ///     {
+    ///         let _inner = ring.span("inner");
+    ///         ring.write("in inner\n".to_string());
+    ///     }
+    ///     ring.write("back in outer\n".to_string());
+    /// }
+    /// ring.write("top level\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let lines: Vec<&str> = content.lines().map(|l| l.split(" ThreadId").next().unwrap()).collect();
+    /// assert_eq!(
+    ///     lines,
+    ///     vec![
+    ///         "> outer",
+    ///         "  in outer",
+    ///         "  > inner",
+    ///         "    in inner",
+    ///         "  < inner",
+    ///         "  back in outer",
+    ///         "< outer",
+    ///         "top level",
+    ///     ]
+    /// );
+    /// ```
+    pub fn indent(mut self, indent: bool) -> Self {
+        self.opts.indent = indent;
+        self
+    }
+
+    /// Check the messages to be valid UTF-8, so that binary garbage is caught on write
+    /// instead of in a corrupted dump. (default [Utf8Mode::Off])
+    ///
+    /// With [Utf8Mode::Reject], the message is validated on the caller's thread, dropped when
+    /// invalid and counted by [RingFile::utf8_rejected()]. The [std::io::Write] impl returns
+    /// error of `ErrorKind::InvalidData` for it.
+    /// With [Utf8Mode::Replace], the invalid sequences are replaced with U+FFFD on the backend.
+    ///
+    /// **NOTE**: The validation scans every message, which costs in the order of the memcpy
+    /// into the ring, and Replace allocates a new copy for the invalid messages.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{RingFileBuilder, Utf8Mode};
+    /// use std::io::Write;
+    /// use std::path::Path;
+    /// let invalid = b"bad \xff\xfe\n";
+    /// for mode in [Utf8Mode::Off, Utf8Mode::Replace, Utf8Mode::Reject].iter() {
+    ///     let path = Path::new("/tmp/ringfile_utf8.store");
+    ///     let ring = RingFileBuilder::new(1024, path.into()).utf8(*mode).build().expect("build ok");
+    ///     ring.write("good\n".to_string());
+    ///     let res = (&ring).write_all(invalid);
+    ///     ring.dump().expect("dump ok");
+    ///     let content = std::fs::read(path).unwrap();
+    ///     match mode {
+    ///         Utf8Mode::Off => assert_eq!(content, b"good\nbad \xff\xfe\n"),
+    ///         Utf8Mode::Replace => assert_eq!(content, "good\nbad \u{fffd}\u{fffd}\n".as_bytes()),
+    ///         Utf8Mode::Reject => {
+    ///             assert!(res.is_err());
+    ///             assert_eq!(ring.utf8_rejected(), 1);
+    ///             assert_eq!(content, b"good\n[ring-file: 1 messages rejected as invalid UTF-8]\n");
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn utf8(mut self, mode: Utf8Mode) -> Self {
+        self.opts.utf8 = mode;
+        self
+    }
+
+    /// Cut each message longer than `max` bytes on the backend, and append the marker `…(+N)`
+    /// with the number of bytes cut, so that a pathologically long record (e.g. a serialized blob)
+    /// does not push the useful lines out of the ring. (default 0, disabled)
+    ///
+    /// The length includes the prefixes, but not the trailing newline, which is kept after the
+    /// marker. The cut does not split a UTF-8 sequence. Each cut is counted as
+    /// [DropReason::LineTooLong](crate::DropReason::LineTooLong).
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{DropReason, RingFileBuilder};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_max_line_len.store");
+    /// let ring = RingFileBuilder::new(1024, path.into()).max_line_len(8).build().expect("build ok");
+    /// ring.write("short\n".to_string());
+    /// // Exactly max bytes before the newline, kept as is
+    /// ring.write("01234567\n".to_string());
+    /// ring.write("0123456789abcdef\n".to_string());
+    /// // The cut at 8 bytes falls inside the third "é", which is cut whole
+    /// ring.write("caf\u{e9}\u{e9}\u{e9}\u{e9}\n".to_string());
+    /// ring.flush();
+    /// assert_eq!(ring.drop_count(DropReason::LineTooLong), 2);
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert_eq!(
+    ///     content,
+    ///     "short\n01234567\n01234567\u{2026}(+8)\ncaf\u{e9}\u{e9}\u{2026}(+4)\n[ring-file: 2 messages cut by max_line_len]\n"
+    /// );
+    /// ```
+    pub fn max_line_len(mut self, max: usize) -> Self {
+        self.opts.max_line_len = max;
+        self
+    }
+
+    /// Start armed or not, see [RingFile::arm()]. (default true)
+    ///
+    /// Build with false to ignore all the writes until the interesting moment.
+    pub fn armed(mut self, armed: bool) -> Self {
+        self.opts.armed = armed;
+        self
+    }
+
+    /// Call `f` on the backend with every message, in order, before it is written into the ring.
+    ///
+    /// The message includes the prefixes like [RingFileBuilder::timestamps()].
+    /// This is for custom instrumentation, e.g. counting the messages matching a pattern.
+    ///
+    /// **NOTE**: `f` runs on the backend thread (or the caller's thread with
+    /// [RingFileBuilder::inline()]), a slow callback stalls the processing of all messages.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::{Arc, Mutex};
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let _seen = seen.clone();
+    /// let ring = RingFileBuilder::new(16, Path::new("/tmp/ringfile_on_message.store").into())
+    ///     .on_message(Box::new(move |msg| _seen.lock().unwrap().push(msg.to_vec())))
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..100 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.flush();
+    /// let seen = seen.lock().unwrap();
+    /// assert_eq!(seen.len(), 100);
+    /// for (i, msg) in seen.iter().enumerate() {
+    ///     assert_eq!(msg, format!("line {}\n", i).as_bytes());
+    /// }
+    /// ```
+    pub fn on_message(mut self, f: OnMessage) -> Self {
+        self.opts.on_message = Some(f);
+        self
+    }
+
+    /// Call `f` when a message is longer than `threshold` bytes, to catch early the bugs
+    /// logging a whole payload on one line. (default None)
+    ///
+    /// The message is still written as usual, unlike [RingFileBuilder::max_line_len()] which
+    /// cuts it. The size is of the message as written, with the prefixes like
+    /// [RingFileBuilder::timestamps()], and `f` gets its first 64 bytes as a preview.
+    /// `f` is called at most once per second, the number of oversized messages skipped in
+    /// between is passed to the next call. It runs on the backend like
+    /// [RingFileBuilder::on_message()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::{Arc, Mutex};
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let _seen = seen.clone();
+    /// let ring = RingFileBuilder::new(4096, Path::new("/tmp/ringfile_large_write.store").into())
+    ///     .warn_write_threshold(
+    ///         100,
+    ///         Box::new(move |size, preview, suppressed| {
+    ///             _seen.lock().unwrap().push((size, preview.to_vec(), suppressed))
+    ///         }),
+    ///     )
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("a".repeat(99) + "\n");
+    /// ring.flush();
+    /// assert!(seen.lock().unwrap().is_empty());
+    /// ring.write("b".repeat(100) + "\n");
+    /// // Within the second, counted for the next call
+    /// ring.write("c".repeat(200) + "\n");
+    /// ring.write("d".repeat(300) + "\n");
+    /// ring.flush();
+    /// assert_eq!(*seen.lock().unwrap(), vec![(101, vec![b'b'; 64], 0)]);
+    /// std::thread::sleep(std::time::Duration::from_millis(1100));
+    /// ring.write("e".repeat(150) + "\n");
+    /// ring.flush();
+    /// assert_eq!(seen.lock().unwrap()[1], (151, vec![b'e'; 64], 2));
+    /// // Not dropped
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string("/tmp/ringfile_large_write.store").unwrap();
+    /// assert_eq!(content.len(), 100 + 101 + 201 + 301 + 151);
+    /// ```
+    pub fn warn_write_threshold(mut self, threshold: usize, f: OnLargeWrite) -> Self {
+        self.opts.large_writes = Some((threshold, f));
+        self
+    }
+
+    /// Call `f` with the reason and the number of bytes whenever content is lost, so that no
+    /// drop is silent. (default None)
+    ///
+    /// `f` runs on the thread where the drop happens: the writer's thread for
+    /// [DropReason::Sampled](crate::DropReason::Sampled),
+    /// [DropReason::Overflow](crate::DropReason::Overflow) and
+    /// [DropReason::InvalidUtf8](crate::DropReason::InvalidUtf8), the backend for
+    /// [DropReason::Truncated](crate::DropReason::Truncated). Keep it cheap, and do not write to
+    /// the same RingFile from it.
+    ///
+    /// Regardless of `f`, every dump ends with one line per reason with drops, e.g.
+    /// `[ring-file: 3 messages dropped on queue overflow]`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{DropReason, RingFileBuilder, Utf8Mode};
+    /// use std::io::Write;
+    /// use std::path::Path;
+    /// use std::sync::{Arc, Mutex};
+    /// let path = Path::new("/tmp/ringfile_on_drop.store");
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let _seen = seen.clone();
+    /// let ring = RingFileBuilder::new(16, path.into())
+    ///     .utf8(Utf8Mode::Reject)
+    ///     .max_queued_bytes(64)
+    ///     .framed(true)
+    ///     .on_drop(Box::new(move |reason, bytes| _seen.lock().unwrap().push((reason, bytes))))
+    ///     .build()
+    ///     .expect("build ok");
+    /// assert!((&ring).write_all(&[0xff, b'\n']).is_err());
+    /// ring.write("a".repeat(31) + "\n");
+    /// ring.flush();
+    /// ring.write("b".repeat(99) + "\n");
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(
+    ///     *seen.lock().unwrap(),
+    ///     vec![(DropReason::InvalidUtf8, 2), (DropReason::Truncated, 17), (DropReason::Overflow, 100)]
+    /// );
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert_eq!(
+    ///     content,
+    ///     "a".repeat(14) + "\n"
+    ///         + "[ring-file: 1 messages dropped on queue overflow]\n"
+    ///         + "[ring-file: 1 messages rejected as invalid UTF-8]\n"
+    ///         + "[ring-file: 1 messages truncated]\n"
+    /// );
+    /// ```
+    pub fn on_drop(mut self, f: OnDrop) -> Self {
+        self.opts.on_drop = Some(f);
+        self
+    }
+
+    /// Unit of the timestamp prefix, see [TimestampPrecision]. (default Micros)
+    ///
+    /// The prefix is formatted as an integer on the hot path, the precision affects the length
+    /// of the prefix, not the cost. Formatting a calendar date per message instead costs several
+    /// times more, try `examples/bench_timestamp.rs`.
+    pub fn timestamp_precision(mut self, precision: TimestampPrecision) -> Self {
+        self.opts.timestamp_precision = precision;
+        self
+    }
+
+    /// Do not create the dump file while the buffer is empty, for dump() and the triggers like
+    /// [RingFileBuilder::dump_on_idle()] on a quiet ring. (default false)
+    ///
+    /// A previous dump file is left untouched, see also
+    /// [RingBuffer::dump_if_nonempty()](crate::RingBuffer::dump_if_nonempty()).
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_skip_empty.store");
+    /// let _ = std::fs::remove_file(path);
+    /// let ring = RingFileBuilder::new(1024, path.into()).skip_empty(true).build().expect("build ok");
+    /// ring.dump().expect("dump ok");
+    /// assert!(!path.exists());
+    /// ring.write("log message\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"log message\n");
+    /// ```
+    pub fn skip_empty(mut self, skip_empty: bool) -> Self {
+        self.opts.skip_empty = skip_empty;
+        self
+    }
+
+    /// Remove the ANSI CSI escape sequences like the colors `ESC[1;31m` from the content
+    /// on dump, so the file is clean text for an editor. (default false, keep the raw bytes)
+    ///
+    /// Applies to dump(), [RingFile::dump_incremental()] and [RingFile::reconstruct()],
+    /// a sequence split by the rewinding is removed as well.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_strip_ansi.store");
+    /// let ring = RingFileBuilder::new(40, path.into()).strip_ansi(true).build().expect("build ok");
+    /// ring.write("\x1b[1;31merror\x1b[0m: plain!! \x1b[32mok\x1b[m\n".to_string());
+    /// // The buffer rewinds inside the first escape of this message
+    /// ring.write("\x1b[33mwarn\x1b[0m\x1b tail\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let expected = " plain!! ok\nwarn\x1b tail\n";
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), expected);
+    /// assert_eq!(ring.dump_size().unwrap(), expected.len());
+    /// ```
+    pub fn strip_ansi(mut self, strip_ansi: bool) -> Self {
+        self.opts.strip_ansi = strip_ansi;
+        self
+    }
+
+    /// Append to every dump a line with the histogram of the line lengths, bucketed by powers
+    /// of two, e.g. `[ring-file: line lengths <=8: 120, <=64: 30, <=4096: 1]`. (default false)
+    ///
+    /// Each bucket `<=N` counts the lines longer than N/2 bytes up to N, including the newline.
+    /// The dump costs one more scan of the buffer.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_histogram.store");
+    /// let ring = RingFileBuilder::new(64, path.into()).histogram(true).build().expect("build ok");
+    /// ring.write("0123456789abcdef0123456789abcdef\n".to_string());
+    /// for _ in 0..4 {
+    ///     ring.write("xyz\n".to_string());
+    /// }
+    /// for _ in 0..2 {
+    ///     ring.write("0123456\n".to_string());
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// // The first line lost 1 byte at the seam, 32 bytes are left
+    /// assert!(content.starts_with("123456789abcdef0123456789abcdef\nxyz\n"));
+    /// assert!(content.ends_with("0123456\n[ring-file: line lengths <=4: 4, <=8: 2, <=32: 1]\n"));
+    /// assert_eq!(ring.dump_size().unwrap(), content.len());
+    /// ```
+    pub fn histogram(mut self, histogram: bool) -> Self {
+        self.opts.histogram = histogram;
+        self
+    }
+
+    /// End each dump with the line `=== END OF RING DUMP === {n} bytes`, `n` being the bytes
+    /// before it, so that a reader can tell with [is_complete_dump()](crate::is_complete_dump())
+    /// that a dump was not cut short by a crash or a broken connection, see
+    /// [END_MARKER](crate::END_MARKER). (default false)
+    ///
+    /// The marker is written as the last line by [RingFile::dump()] and the dumps sharing its
+    /// content, such as [RingFile::dump_to_tcp()] and [RingFile::dump_split()], not by the dumps
+    /// of the buffer alone like [RingFile::dump_with_budget()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{is_complete_dump, RingFileBuilder, END_MARKER};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_end_marker.store");
+    /// let ring = RingFileBuilder::new(1024, path.into()).end_marker(true).build().expect("build ok");
+    /// ring.write("first\n".to_string());
+    /// ring.write(format!("{} 0 bytes\n", END_MARKER));
+    /// ring.write("last\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read(path).unwrap();
+    /// assert!(content.ends_with(b"last\n=== END OF RING DUMP === 44 bytes\n"));
+    /// assert_eq!(ring.dump_size().unwrap(), content.len());
+    /// assert!(is_complete_dump(&content));
+    /// // Cut anywhere, even right after the message looking like the marker
+    /// for len in 0..content.len() {
+    ///     assert!(!is_complete_dump(&content[..len]), "{}", len);
+    /// }
+    /// ```
+    pub fn end_marker(mut self, end_marker: bool) -> Self {
+        self.opts.end_marker = end_marker;
+        self
+    }
+
+    /// Keep the content of the last `max` fills of the ring: when a message would overwrite the
+    /// oldest bytes, the content is sealed into a generation and the ring starts empty, the
+    /// oldest generation is dropped over `max`. dump() writes the generations from the oldest,
+    /// then the ring. (default 0, disabled)
+    ///
+    /// This multiplies the history kept by about `max + 1`, and the memory is bounded the same:
+    /// each generation holds at most buf_size bytes of content, so the total is at most
+    /// `(max + 1) * buf_size`, the generations being allocated as the ring fills. Unlike a
+    /// larger buf_size, the history is dropped a whole generation at a time, and the dumps of the
+    /// buffer alone, such as [RingFile::dump_filtered()] and [RingFile::snapshot_with_stats()],
+    /// only cover the ring. clear() drops the generations too. Can not be combined with
+    /// [RingFileBuilder::framed()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_generations.store");
+    /// // 4 records of 16 bytes fill the ring
+    /// let ring = RingFileBuilder::new(64, path.into()).generations(2).build().expect("build ok");
+    /// let record = |i: usize| format!("{:015}\n", i);
+    /// // Fill the ring 4 times, then 2 records more
+    /// for i in 0..18 {
+    ///     ring.write(record(i));
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// // The 2 newest generations and the ring
+    /// let expected: String = (8..18).map(record).collect();
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), expected);
+    /// assert_eq!(ring.dump_size().unwrap(), expected.len());
+    /// ring.clear();
+    /// ring.write(record(18));
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), record(18));
+    /// ```
+    pub fn generations(mut self, max: usize) -> Self {
+        self.opts.generations = max;
+        self
+    }
+
+    /// Collapse the repeats of a message into a count, like syslog. (default false)
+    ///
+    /// A message identical to the previous one, apart from the time, thread name and sequence
+    /// prefixes, is not stored. The line `[ring-file: last message repeated N times]` is stored
+    /// when a different message arrives, and before a dump so that it is included.
+    /// The repeats are still passed to [RingFileBuilder::on_message()], but not to the live file.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_dedup.store");
+    /// let ring = RingFileBuilder::new(1024, path.into()).dedup(true).build().expect("build ok");
+    /// ring.write("start\n".to_string());
+    /// for _ in 0..100 {
+    ///     ring.write("retrying\n".to_string());
+    /// }
+    /// ring.write("connected\n".to_string());
+    /// ring.write("connected\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let expected = "start\nretrying\n[ring-file: last message repeated 99 times]\n\
+    ///     connected\n[ring-file: last message repeated 1 times]\n";
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), expected);
+    /// // A new run after the dump
+    /// ring.write("connected\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let expected = format!("{}[ring-file: last message repeated 1 times]\n", expected);
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), expected);
+    /// ```
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.opts.dedup = dedup;
+        self
+    }
+
+    /// Freeze the ring once [RingBuffer::total_written()](crate::RingBuffer::total_written())
+    /// reaches `max` bytes, to keep an early window of a run, e.g. the start of a reproduction.
+    /// (default 0, disabled)
+    ///
+    /// The message reaching the cap is stored whole. The later messages are dropped on the backend
+    /// and counted as [DropReason::Frozen](crate::DropReason::Frozen), the dumps still work. Unlike
+    /// buf_size, which bounds the window kept in memory, this bounds the volume written in the
+    /// lifetime of the ring, including the banner and the prefixes. The freeze is final, also
+    /// across clear() and take_buffer(). See [RingFileBuilder::on_frozen()] to be notified.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{DropReason, RingFileBuilder};
+    /// use std::path::Path;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// let path = Path::new("/tmp/ringfile_max_total.store");
+    /// let frozen = Arc::new(AtomicBool::new(false));
+    /// let _frozen = frozen.clone();
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .max_total_bytes(25)
+    ///     .on_frozen(Box::new(move || _frozen.store(true, Ordering::Relaxed)))
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..5 {
+    ///     ring.write(format!("message {}\n", i));
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// // The third message crosses the cap and is kept whole
+    /// assert!(frozen.load(Ordering::Relaxed));
+    /// assert_eq!(ring.drop_count(DropReason::Frozen), 2);
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let expected = "message 0\nmessage 1\nmessage 2\n\
+    ///     [ring-file: 2 messages dropped after max_total_bytes]\n";
+    /// assert_eq!(content, expected);
+    /// ```
+    pub fn max_total_bytes(mut self, max: u64) -> Self {
+        self.opts.max_total_bytes = max;
+        self
+    }
+
+    /// Call `f` on the backend once the ring is frozen by [RingFileBuilder::max_total_bytes()].
+    /// (default None)
+    pub fn on_frozen(mut self, f: OnFrozen) -> Self {
+        self.opts.on_frozen = Some(f);
+        self
+    }
+
+    /// Keep the dump file open after the first dump, and rewrite it in place on the next dumps
+    /// with `set_len(0)` and `seek(0)`, to save the `open` syscall of each dump when snapshotting
+    /// often, e.g. with [RingFileBuilder::flush_every()] or [RingFileBuilder::dump_on_idle()].
+    /// (default false)
+    ///
+    /// The file content after each dump is the same as opening the file each time.
+    /// The file is reopened when the path changes, e.g. with [RingFileBuilder::path_fn()],
+    /// and on unix when the path no longer points to the kept file, e.g. after it is removed
+    /// or rotated. It is fsynced and closed on [RingFile::shutdown()].
+    /// Only used with [PartialDump::Keep], the other policies open the file each time.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let reused = Path::new("/tmp/ringfile_reuse.store");
+    /// let plain = Path::new("/tmp/ringfile_reuse_plain.store");
+    /// let ring = RingFileBuilder::new(64, reused.into()).reuse_dump_file(true).build().unwrap();
+    /// let other = RingFileBuilder::new(64, plain.into()).build().unwrap();
+    /// for msg in ["a long line to fill the buffer\n", "short\n", "", "last line\n"] {
+    ///     for ring in [&ring, &other] {
+    ///         if msg.is_empty() {
+    ///             ring.clear();
+    ///         } else {
+    ///             ring.write(msg.to_string());
+    ///         }
+    ///         ring.dump().expect("dump ok");
+    ///     }
+    ///     assert_eq!(std::fs::read(reused).unwrap(), std::fs::read(plain).unwrap());
+    /// }
+    /// assert_eq!(std::fs::read_to_string(reused).unwrap(), "last line\n");
+    /// // Recreated when removed
+    /// std::fs::remove_file(reused).unwrap();
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read_to_string(reused).unwrap(), "last line\n");
+    /// ring.shutdown();
+    /// ```
+    pub fn reuse_dump_file(mut self, reuse_dump_file: bool) -> Self {
+        self.opts.reuse_dump_file = reuse_dump_file;
+        self
+    }
+
+    /// What dump() does with the file when the write fails midway, e.g. on disk full.
+    /// (default [PartialDump::Keep])
+    ///
+    /// With [PartialDump::KeepPrevious] the dump goes into `{file_path}.tmp` first,
+    /// see [RingBuffer::set_partial_dump()](crate::RingBuffer::set_partial_dump()).
+    pub fn partial_dump(mut self, partial_dump: PartialDump) -> Self {
+        self.opts.partial_dump = partial_dump;
+        self
+    }
+
+    /// Compute the target of each [RingFile::dump()] on the backend with `f`, instead of the
+    /// fixed file_path, e.g. to name the dump after an incident id or the time. (default None)
+    ///
+    /// Also used by the dumps of [RingFileBuilder::flush_every()] and
+    /// [RingFileBuilder::dump_on_idle()]. The dump returns error of `ErrorKind::InvalidInput`
+    /// when `f` returns an empty path or a directory.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::{Path, PathBuf};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// let seq = AtomicUsize::new(0);
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_unused.store").into())
+    ///     .path_fn(Box::new(move || {
+    ///         let n = seq.fetch_add(1, Ordering::Relaxed);
+    ///         match n {
+    ///             2 => PathBuf::from("/tmp"),
+    ///             _ => PathBuf::from(format!("/tmp/ringfile_incident_{}.store", n)),
+    ///         }
+    ///     }))
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("first\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// ring.write("second\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let err = ring.dump().unwrap_err();
+    /// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    /// assert_eq!(std::fs::read("/tmp/ringfile_incident_0.store").unwrap(), b"first\n");
+    /// assert_eq!(std::fs::read("/tmp/ringfile_incident_1.store").unwrap(), b"first\nsecond\n");
+    /// ```
+    pub fn path_fn(mut self, f: PathFn) -> Self {
+        self.opts.path_fn = Some(f);
+        self
+    }
+
+    /// Permissions of the dump files on unix, see
+    /// [RingBuffer::set_dump_mode()](crate::RingBuffer::set_dump_mode()). (default 0o600)
+    ///
+    /// The spill and live files are always created with 0o600.
+    pub fn dump_mode(mut self, mode: u32) -> Self {
+        self.opts.dump_mode = mode;
+        self
+    }
+
+    /// Capture the backtrace on the caller's thread for [RingFile::write_leveled()] with `level`,
+    /// and store it after the message as a `backtrace:` section. Can be called for several levels.
+    /// Enabled with feature `backtrace`.
+    ///
+    /// **NOTE**: Capturing and formatting a backtrace costs tens of microseconds or more, and the
+    /// symbols require debug info. Only enable it for rare levels like [Level::Error].
+    /// The backtrace lines have no level prefix, so are not included by
+    /// [RingFile::dump_min_level()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::{Level, RingFileBuilder};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_backtrace.store");
+    /// let ring = RingFileBuilder::new(1024 * 1024, path.into())
+    ///     .backtrace(Level::Error)
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write_leveled(Level::Warn, b"retry\n".to_vec());
+    /// ring.write_leveled(Level::Error, b"gave up\n".to_vec());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert!(content.starts_with("W retry\nE gave up\nbacktrace:\n"));
+    /// assert_eq!(content.matches("\nbacktrace:\n").count(), 1);
+    /// ```
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(mut self, level: Level) -> Self {
+        self.opts.backtrace_levels |= level.bit();
+        self
+    }
+
+    /// Let the backend dump to file_path when no message is written for `timeout`,
+    /// which is likely the moment when the threads are stuck.
+    ///
+    /// The dump fires once for each idle period, and re-arms on the next message written.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_idle.store");
+    /// let _ = std::fs::remove_file(path);
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .dump_on_idle(Duration::from_millis(100))
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("before hang\n".to_string());
+    /// std::thread::sleep(Duration::from_millis(500));
+    /// assert_eq!(std::fs::read(path).unwrap(), b"before hang\n");
+    /// std::fs::remove_file(path).unwrap();
+    /// // Only once for one idle period
+    /// std::thread::sleep(Duration::from_millis(500));
+    /// assert!(!path.exists());
+    /// ```
+    pub fn dump_on_idle(mut self, timeout: Duration) -> Self {
+        self.opts.dump_on_idle = Some(timeout);
+        self
+    }
+
+    /// Do at most `max` dumps of the triggers per `interval`, to keep a misbehaving trigger
+    /// from harming the host with a storm of dumps. (default None)
+    ///
+    /// The triggers are [RingFileBuilder::flush_every()], [RingFileBuilder::dump_on_idle()],
+    /// [RingFile::arm_crash_capture()] and [RingFile::trigger_dump()]. The dumps over the limit
+    /// are skipped, the requests are coalesced into the dumps done, and counted by
+    /// [RingFile::dumps_throttled()]. The explicit [RingFile::dump()] and its variants bypass
+    /// the limit, and are not counted in it. The window is sliding: a dump is allowed again
+    /// `interval` after the oldest of the last `max`.
+    ///
+    /// build() returns error when `max` is 0.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_max_dumps.store");
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .max_dumps_per(2, Duration::from_millis(300))
+    ///     .build()
+    ///     .expect("build ok");
+    /// let dumped: Vec<bool> = (0..10).map(|_| ring.trigger_dump().expect("dump ok")).collect();
+    /// assert_eq!(dumped, [true, true, false, false, false, false, false, false, false, false]);
+    /// assert_eq!(ring.dumps_throttled(), 8);
+    /// // The explicit dump bypasses the limit
+    /// ring.write("explicit\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"explicit\n");
+    /// std::thread::sleep(Duration::from_millis(400));
+    /// assert!(ring.trigger_dump().expect("dump ok"));
+    /// ```
+    pub fn max_dumps_per(mut self, max: usize, interval: Duration) -> Self {
+        self.opts.max_dumps_per = Some((max, interval));
+        self
+    }
+
+    /// Keep the messages no longer than `max_record` contiguous in the buffer,
+    /// see [RingBuffer::set_no_split()](crate::RingBuffer::set_no_split()). (default 0, disabled)
+    pub fn no_split(mut self, max_record: usize) -> Self {
+        self.opts.no_split = max_record;
+        self
+    }
+
+    /// Use the channel created by `transport`, see [MsgTransport].
+    pub fn transport<T: MsgTransport>(self, transport: T) -> RingFileBuilder<T> {
+        RingFileBuilder { opts: self.opts, transport }
+    }
+
+    /// Write on the caller's thread instead of a backend thread, see [RingFile::new_inline()].
+    /// (default false)
+    pub fn inline(mut self, inline: bool) -> Self {
+        self.opts.inline = inline;
+        self
+    }
+
+    /// Track each message as a line, to support [RingFile::line_count()] and
+    /// [RingFile::set_max_lines()]. (default false)
+    ///
+    /// The backend keeps 8 bytes of bookkeeping for every line in the buffer.
+    pub fn line_mode(mut self, line_mode: bool) -> Self {
+        self.opts.line_mode = line_mode;
+        self
+    }
+
+    /// Store each message with a length prefix, see
+    /// [RingBuffer::set_framed()](crate::RingBuffer::set_framed()). (default false)
+    ///
+    /// A message with embedded newlines stays one record for [RingFile::dump_filtered()]
+    /// and the dumps built on it, and the partially overwritten message is left out of the dump.
+    /// Costs 1 to 3 bytes per message in the buffer.
+    ///
+    /// build() returns error when combined with [RingFileBuilder::align()],
+    /// [RingFileBuilder::spill()] or [RingFileBuilder::spill_gz()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{Level, RingFileBuilder};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_framed.store");
+    /// let ring = RingFileBuilder::new(48, path.into()).framed(true).build().expect("build ok");
+    /// ring.write("evicted message\n".to_string());
+    /// ring.write_leveled(Level::Info, b"started\n".to_vec());
+    /// ring.write_leveled(Level::Error, b"failed:\n  caused by: timeout\n".to_vec());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert_eq!(content, "I started\nE failed:\n  caused by: timeout\n");
+    /// // The continuation line is kept with its record
+    /// ring.dump_min_level(path, Level::Warn).expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert_eq!(content, "E failed:\n  caused by: timeout\n");
+    /// ```
+    pub fn framed(mut self, framed: bool) -> Self {
+        self.opts.framed = framed;
+        self
+    }
+
+    /// The options set so far, without the paths and callbacks, see [RingFileConfig].
+    pub fn config(&self) -> RingFileConfig {
+        RingFileConfig {
+            buf_size: self.opts.buf_size,
+            prefault: self.opts.prefault,
+            banner: self.opts.banner.clone(),
+            sticky_banner: self.opts.sticky_banner.clone(),
+            run_id: self.opts.run_id.clone(),
+            labels: self.opts.labels.clone(),
+            categories: self.opts.categories,
+            flush_every: self.opts.flush_every,
+            live_flush_interval: self.opts.live_flush_interval,
+            tcp_timeout: self.opts.tcp_timeout,
+            strict_order: self.opts.strict_order,
+            max_dumps_per: self.opts.max_dumps_per,
+            sample: self.opts.sample,
+            max_queued_bytes: self.opts.max_queued_bytes,
+            timestamps: self.opts.timestamps,
+            timestamp_precision: self.opts.timestamp_precision,
+            uptime: self.opts.uptime,
+            binary_uptime: self.opts.binary_uptime,
+            thread_names: self.opts.thread_names,
+            per_thread_seq: self.opts.per_thread_seq,
+            indent: self.opts.indent,
+            utf8: self.opts.utf8,
+            max_line_len: self.opts.max_line_len,
+            armed: self.opts.armed,
+            skip_empty: self.opts.skip_empty,
+            strip_ansi: self.opts.strip_ansi,
+            histogram: self.opts.histogram,
+            end_marker: self.opts.end_marker,
+            generations: self.opts.generations,
+            dedup: self.opts.dedup,
+            max_total_bytes: self.opts.max_total_bytes,
+            reuse_dump_file: self.opts.reuse_dump_file,
+            partial_dump: self.opts.partial_dump,
+            dump_mode: self.opts.dump_mode,
+            align: self.opts.align,
+            dump_on_idle: self.opts.dump_on_idle,
+            no_split: self.opts.no_split,
+            inline: self.opts.inline,
+            line_mode: self.opts.line_mode,
+            framed: self.opts.framed,
+        }
+    }
+
+    /// Allocate the buffer and spawn the backend thread.
+    pub fn build(self) -> std::io::Result<RingFile<X>>
+    where
+        X: 'static,
+    {
+        self.opts.build(self.transport)
+    }
+
+    /// Same as [RingFileBuilder::build()], but return the backend as a [RingFileDriver]
+    /// instead of spawning a thread for it, see [RingFile::new_detached()].
+    ///
+    /// Returns error with [RingFileBuilder::inline()], which has no backend.
+    pub fn build_detached(self) -> std::io::Result<(RingFile<X>, RingFileDriver<X>)> {
+        self.opts.build_detached(self.transport)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::buffer::create_file;
+use crate::tag::file_name;
+use crate::RingBuffer;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The category with the characters not safe in a file name replaced by `_`.
+pub(crate) fn category_name(category: &str) -> String {
+    if category.is_empty() {
+        return "_".to_string();
+    }
+    // ASCII only after file_name()
+    String::from_utf8(file_name(category.as_bytes())).expect("ASCII")
+}
+
+/// The buffers of [RingFile::write_category()](crate::RingFile::write_category()), created on
+/// first use, see [RingFileBuilder::categories()](crate::RingFileBuilder::categories()).
+pub(crate) struct Categories {
+    max: usize,
+    buf_size: i32,
+    /// The least recently written first, only a few categories expected
+    buffers: Vec<(String, RingBuffer)>,
+}
+
+impl Categories {
+    pub(crate) fn new(max: usize, buf_size: i32) -> Self {
+        Self { max, buf_size, buffers: Vec::new() }
+    }
+
+    /// Append `line` to the buffer of `category`, the least recently written buffer is dropped
+    /// when a new category is over the limit.
+    pub(crate) fn write(&mut self, category: &str, line: &[u8]) {
+        let entry = match self.buffers.iter().position(|(name, _)| name == category) {
+            Some(i) => self.buffers.remove(i),
+            None => {
+                if self.buffers.len() >= self.max {
+                    self.buffers.remove(0);
+                }
+                (category.to_string(), RingBuffer::new(self.buf_size))
+            }
+        };
+        self.buffers.push(entry);
+        let buffer = &mut self.buffers.last_mut().expect("pushed").1;
+        let _ = buffer.write_all(line);
+    }
+
+    /// Write each buffer to `dir/{category}.log`, return the paths from the least recently
+    /// written category.
+    pub(crate) fn dump(&self, dir: &Path, mode: u32) -> std::io::Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)?;
+        let mut files = Vec::with_capacity(self.buffers.len());
+        for (name, buffer) in self.buffers.iter() {
+            let path = dir.join(format!("{}.log", name));
+            let mut file = BufWriter::new(create_file(&path, mode)?);
+            buffer.dump_to(&mut file)?;
+            file.flush()?;
+            files.push(path);
+        }
+        Ok(files)
+    }
+}
@@ -0,0 +1,219 @@
+use crate::buffer::{dump_file, DEFAULT_MODE};
+use crate::lz4;
+use crate::PartialDump;
+use std::collections::VecDeque;
+use std::io::{Result, Write};
+use std::path::Path;
+
+/// A sealed block, the content is stored as is when LZ4 does not make it smaller
+struct Block {
+    data: Box<[u8]>,
+    raw_len: usize,
+}
+
+impl Block {
+    #[inline]
+    fn is_compressed(&self) -> bool {
+        self.data.len() < self.raw_len
+    }
+}
+
+/// Same as [RingBuffer](crate::RingBuffer), but the content is kept as LZ4-compressed blocks,
+/// to keep a longer history in the same memory when the retention matters more than the CPU.
+///
+/// The writes fill an open block of `block_size` bytes, which is compressed when full and
+/// pushed into the ring of sealed blocks. When the compressed blocks exceed `capacity` bytes,
+/// the oldest blocks are dropped whole. The dumps decompress the blocks in order, so the
+/// content is the same bytes as written, from the start of the oldest block kept.
+///
+/// Trade-offs, compared to [RingBuffer](crate::RingBuffer):
+/// - Text logs commonly compress 3 to 5 times, the retention grows as much. Random data is
+///   stored uncompressed, so the retention is never below `capacity`.
+/// - Each sealed block costs a compression, each dump a decompression of every block.
+/// - The history is dropped a block at a time, so the retention varies by one block, and
+///   the content may start in the middle of a record as with the byte stream of
+///   [RingBuffer](crate::RingBuffer).
+/// - The memory is `capacity` plus the open block, and a block is allocated on each seal.
+///
+/// `block_size` should be at least [CompressedRingBuffer::MIN_BLOCK_SIZE], smaller blocks
+/// do not give LZ4 enough context to compress. Only the plain byte stream is supported,
+/// without no_split or framed mode. Requires the `lz4` feature.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::{CompressedRingBuffer, RingBuffer};
+/// use std::io::Write;
+/// let mut compressed = CompressedRingBuffer::new(1024 * 1024, 64 * 1024);
+/// let mut plain = RingBuffer::new(16 * 1024 * 1024);
+/// let mut seed = 7u64;
+/// for i in 0..50000 {
+///     seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+///     let record = format!("[{}] worker {} took {}us\n", i, seed % 16, (seed >> 40) % 1000);
+///     compressed.write_all(record.as_bytes()).expect("write ok");
+///     plain.write_all(record.as_bytes()).expect("write ok");
+/// }
+/// // Random bytes are kept as is
+/// for _ in 0..10000 {
+///     seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+///     compressed.write_all(&seed.to_le_bytes()).expect("write ok");
+///     plain.write_all(&seed.to_le_bytes()).expect("write ok");
+/// }
+/// assert_eq!(compressed.total_written(), plain.total_written());
+/// assert_eq!(compressed.read_to_vec(), plain.read_to_vec());
+/// assert!(compressed.compressed_len() < compressed.len() / 2);
+/// compressed.dump("/tmp/ringfile_compressed.store").expect("dump ok");
+/// plain.dump("/tmp/ringfile_plain.store").expect("dump ok");
+/// assert_eq!(
+///     std::fs::read("/tmp/ringfile_compressed.store").unwrap(),
+///     std::fs::read("/tmp/ringfile_plain.store").unwrap()
+/// );
+///
+/// // Past capacity, the oldest blocks are dropped, keeping more than capacity of content
+/// let mut small = CompressedRingBuffer::new(64 * 1024, 16 * 1024);
+/// let mut all = Vec::new();
+/// for i in 0..100000 {
+///     let record = format!("[{}] log message\n", i);
+///     small.write_all(record.as_bytes()).expect("write ok");
+///     all.extend_from_slice(record.as_bytes());
+/// }
+/// let content = small.read_to_vec();
+/// assert!(all.ends_with(&content));
+/// assert!(content.len() > 3 * small.capacity());
+/// assert!(small.compressed_len() <= small.capacity() + small.block_size());
+/// ```
+pub struct CompressedRingBuffer {
+    /// Limit of the bytes of the sealed blocks
+    capacity: usize,
+    block_size: usize,
+    open: Vec<u8>,
+    sealed: VecDeque<Block>,
+    /// Bytes of the sealed blocks
+    used: usize,
+    /// The total bytes ever written
+    written: u64,
+}
+
+impl CompressedRingBuffer {
+    /// The smallest block_size accepted by new().
+    pub const MIN_BLOCK_SIZE: usize = 4096;
+
+    /// Panics when `block_size` is below [CompressedRingBuffer::MIN_BLOCK_SIZE], or `capacity`
+    /// below `block_size`.
+    pub fn new(capacity: usize, block_size: usize) -> Self {
+        assert!(block_size >= Self::MIN_BLOCK_SIZE, "block_size should be at least MIN_BLOCK_SIZE");
+        assert!(capacity >= block_size, "capacity should be at least block_size");
+        Self {
+            capacity,
+            block_size,
+            open: Vec::with_capacity(block_size),
+            sealed: VecDeque::new(),
+            used: 0,
+            written: 0,
+        }
+    }
+
+    /// The limit of the bytes of the sealed blocks, without the open block.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Return the bytes of content currently kept, as they are dumped.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sealed.iter().map(|block| block.raw_len).sum::<usize>() + self.open.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sealed.is_empty() && self.open.is_empty()
+    }
+
+    /// Return the bytes of memory holding the content, the sealed blocks and the open block.
+    #[inline]
+    pub fn compressed_len(&self) -> usize {
+        self.used + self.open.len()
+    }
+
+    /// The total bytes ever written, see
+    /// [RingBuffer::total_written()](crate::RingBuffer::total_written()).
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Discard the content, [CompressedRingBuffer::total_written()] is kept.
+    pub fn clear(&mut self) {
+        self.open.clear();
+        self.sealed.clear();
+        self.used = 0;
+    }
+
+    /// Compress the open block into the ring, drop the oldest blocks beyond capacity.
+    fn seal(&mut self) {
+        let compressed = lz4::compress(&self.open);
+        let data = if compressed.len() < self.open.len() { compressed } else { self.open.clone() };
+        self.used += data.len();
+        self.sealed.push_back(Block { data: data.into_boxed_slice(), raw_len: self.open.len() });
+        self.open.clear();
+        while self.used > self.capacity {
+            let block = self.sealed.pop_front().expect("used counts the sealed blocks");
+            self.used -= block.data.len();
+        }
+    }
+
+    /// Write the content in order to `writer`, decompressing one block at a time.
+    pub fn dump_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut raw = Vec::with_capacity(self.block_size);
+        for block in self.sealed.iter() {
+            if block.is_compressed() {
+                raw.clear();
+                lz4::decompress(&block.data, &mut raw)?;
+                writer.write_all(&raw)?;
+            } else {
+                writer.write_all(&block.data)?;
+            }
+        }
+        writer.write_all(&self.open)
+    }
+
+    /// Copy the content in order into a new Vec.
+    pub fn read_to_vec(&self) -> Vec<u8> {
+        let mut v = Vec::with_capacity(self.len());
+        self.dump_to(&mut v).expect("decompress the blocks compressed by seal()");
+        v
+    }
+
+    /// Will create a truncated file and write all data from mem to disk.
+    pub fn dump<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
+        dump_file(file_path.as_ref(), PartialDump::Keep, DEFAULT_MODE, |file| self.dump_to(file))
+    }
+}
+
+impl Write for CompressedRingBuffer {
+    /// Unlike [RingBuffer](crate::RingBuffer), the whole `buf` is written, across blocks.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let l = rest.len().min(self.block_size - self.open.len());
+            self.open.extend_from_slice(&rest[..l]);
+            rest = &rest[l..];
+            if self.open.len() == self.block_size {
+                self.seal();
+            }
+        }
+        self.written += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
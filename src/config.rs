@@ -0,0 +1,163 @@
+use crate::{PartialDump, RingFileBuilder, TimestampPrecision, Utf8Mode};
+use std::path::Path;
+use std::time::Duration;
+
+/// The options of a [RingFileBuilder] as data, to share how a [RingFile](crate::RingFile) was
+/// set up, or to keep setups as fixtures, see [RingFileBuilder::config()].
+///
+/// Only the plain options are kept: the paths (the dump path, [RingFileBuilder::path_fn()],
+/// [RingFileBuilder::spill()], [RingFileBuilder::live_file()], [RingFileBuilder::manifest()]),
+/// the callbacks, [RingFileBuilder::mirror()], [RingFileBuilder::transport()] and the
+/// backtrace levels are not, set them on the builder of [RingFileConfig::builder()].
+///
+/// With feature `unstable`, the config also has a `key=value` text form through `Display` and
+/// `FromStr`. It is not a compatibility promise: the keys and the value syntax may change in
+/// any release, so do not keep the text where another version of the crate has to read it.
+/// The config does not implement the serde traits yet.
+///
+/// # Example:
+///
+/// ```rust
+/// # if cfg!(feature = "disabled") { return; }
+/// use ring_file::{RingFile, RingFileBuilder, TimestampPrecision};
+/// use std::path::Path;
+/// use std::time::Duration;
+/// let builder = RingFileBuilder::new(4096, Path::new("/tmp/ringfile_config.store").into())
+///     .run_id("run-1".to_string())
+///     .label("host".to_string(), "web-1".to_string())
+///     .timestamps(true)
+///     .timestamp_precision(TimestampPrecision::Millis)
+///     .dedup(true)
+///     .dump_on_idle(Duration::from_millis(1500));
+/// let config = builder.config();
+/// assert_eq!(config.builder(Path::new("/tmp/other.store").into()).config(), config);
+///
+/// // Both rings behave the same
+/// let original = builder.build().expect("build ok");
+/// let path = Path::new("/tmp/ringfile_config.restored.store");
+/// let restored = RingFile::from_config(&config, path.into()).expect("build ok");
+/// for ring in [&original, &restored] {
+///     ring.write("repeated\n".to_string());
+///     ring.write("repeated\n".to_string());
+///     ring.dump().expect("dump ok");
+/// }
+/// let strip = |s: String| s.lines().map(|l| l.split("] ").last().unwrap().to_string()).collect::<Vec<_>>();
+/// let original = strip(std::fs::read_to_string("/tmp/ringfile_config.run-1.store").unwrap());
+/// let restored = strip(std::fs::read_to_string("/tmp/ringfile_config.restored.run-1.store").unwrap());
+/// assert_eq!(original, restored);
+/// assert_eq!(original[1], "[ring-file: label host=web-1]");
+///
+/// // The options inconsistent together are rejected on build
+/// let config = RingFileBuilder::new(64, path.into()).binary_uptime(true).config();
+/// let e = RingFile::from_config(&config, path.into()).err().unwrap();
+/// assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingFileConfig {
+    pub(crate) buf_size: i32,
+    pub(crate) prefault: bool,
+    pub(crate) banner: Option<String>,
+    pub(crate) sticky_banner: Option<String>,
+    pub(crate) run_id: Option<String>,
+    pub(crate) labels: Vec<(String, String)>,
+    pub(crate) categories: (usize, i32),
+    pub(crate) flush_every: usize,
+    pub(crate) live_flush_interval: Option<Duration>,
+    pub(crate) tcp_timeout: Duration,
+    pub(crate) strict_order: Option<(usize, Duration)>,
+    pub(crate) max_dumps_per: Option<(usize, Duration)>,
+    pub(crate) sample: Option<(usize, usize)>,
+    pub(crate) max_queued_bytes: usize,
+    pub(crate) timestamps: bool,
+    pub(crate) timestamp_precision: TimestampPrecision,
+    pub(crate) uptime: bool,
+    pub(crate) binary_uptime: bool,
+    pub(crate) thread_names: bool,
+    pub(crate) per_thread_seq: bool,
+    pub(crate) indent: bool,
+    pub(crate) utf8: Utf8Mode,
+    pub(crate) max_line_len: usize,
+    pub(crate) armed: bool,
+    pub(crate) skip_empty: bool,
+    pub(crate) strip_ansi: bool,
+    pub(crate) histogram: bool,
+    pub(crate) end_marker: bool,
+    pub(crate) generations: usize,
+    pub(crate) dedup: bool,
+    pub(crate) max_total_bytes: u64,
+    pub(crate) reuse_dump_file: bool,
+    pub(crate) partial_dump: PartialDump,
+    pub(crate) dump_mode: u32,
+    pub(crate) align: usize,
+    pub(crate) dump_on_idle: Option<Duration>,
+    pub(crate) no_split: usize,
+    pub(crate) inline: bool,
+    pub(crate) line_mode: bool,
+    pub(crate) framed: bool,
+}
+
+impl RingFileConfig {
+    /// A builder with these options, dumping to `file_path`, to add the options not in the
+    /// config before build().
+    pub fn builder(&self, file_path: Box<Path>) -> RingFileBuilder {
+        let mut b = RingFileBuilder::new(self.buf_size, file_path)
+            .prefault(self.prefault)
+            .categories(self.categories.0, self.categories.1)
+            .flush_every(self.flush_every)
+            .tcp_timeout(self.tcp_timeout)
+            .max_queued_bytes(self.max_queued_bytes)
+            .timestamps(self.timestamps)
+            .timestamp_precision(self.timestamp_precision)
+            .uptime(self.uptime)
+            .binary_uptime(self.binary_uptime)
+            .thread_names(self.thread_names)
+            .per_thread_seq(self.per_thread_seq)
+            .indent(self.indent)
+            .utf8(self.utf8)
+            .max_line_len(self.max_line_len)
+            .armed(self.armed)
+            .skip_empty(self.skip_empty)
+            .strip_ansi(self.strip_ansi)
+            .histogram(self.histogram)
+            .end_marker(self.end_marker)
+            .generations(self.generations)
+            .dedup(self.dedup)
+            .max_total_bytes(self.max_total_bytes)
+            .reuse_dump_file(self.reuse_dump_file)
+            .partial_dump(self.partial_dump)
+            .dump_mode(self.dump_mode)
+            .align(self.align)
+            .no_split(self.no_split)
+            .inline(self.inline)
+            .line_mode(self.line_mode)
+            .framed(self.framed);
+        if let Some(banner) = self.banner.as_ref() {
+            b = b.banner(banner.clone());
+        }
+        if let Some(banner) = self.sticky_banner.as_ref() {
+            b = b.sticky_banner(banner.clone());
+        }
+        if let Some(id) = self.run_id.as_ref() {
+            b = b.run_id(id.clone());
+        }
+        for (key, value) in self.labels.iter() {
+            b = b.label(key.clone(), value.clone());
+        }
+        if let Some(interval) = self.live_flush_interval {
+            b = b.live_flush_interval(interval);
+        }
+        if let Some((window, timeout)) = self.strict_order {
+            b = b.strict_order(window, timeout);
+        }
+        if let Some((max, interval)) = self.max_dumps_per {
+            b = b.max_dumps_per(max, interval);
+        }
+        if let Some((high, low)) = self.sample {
+            b = b.adaptive_sample(high, low);
+        }
+        if let Some(timeout) = self.dump_on_idle {
+            b = b.dump_on_idle(timeout);
+        }
+        b
+    }
+}
@@ -0,0 +1,308 @@
+//! The text form of [RingFileConfig], with feature `unstable`.
+
+use crate::{PartialDump, RingFileBuilder, RingFileConfig, TimestampPrecision, Utf8Mode};
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+fn duration_str(d: Duration) -> String {
+    match d.as_nanos() {
+        0 => "0s".to_string(),
+        n if n % 1_000_000_000 == 0 => format!("{}s", n / 1_000_000_000),
+        n if n % 1_000_000 == 0 => format!("{}ms", n / 1_000_000),
+        n if n % 1_000 == 0 => format!("{}us", n / 1_000),
+        n => format!("{}ns", n),
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `-` for None.
+fn opt_str<T>(v: Option<T>, f: impl FnOnce(T) -> String) -> String {
+    v.map_or_else(|| "-".to_string(), f)
+}
+
+fn precision_name(precision: TimestampPrecision) -> &'static str {
+    match precision {
+        TimestampPrecision::Secs => "s",
+        TimestampPrecision::Millis => "ms",
+        TimestampPrecision::Micros => "us",
+        TimestampPrecision::Nanos => "ns",
+    }
+}
+
+fn utf8_name(mode: Utf8Mode) -> &'static str {
+    match mode {
+        Utf8Mode::Off => "off",
+        Utf8Mode::Replace => "replace",
+        Utf8Mode::Reject => "reject",
+    }
+}
+
+fn partial_dump_name(policy: PartialDump) -> &'static str {
+    match policy {
+        PartialDump::Keep => "keep",
+        PartialDump::Remove => "remove",
+        PartialDump::KeepPrevious => "keep_previous",
+    }
+}
+
+impl fmt::Display for RingFileConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pair = |(n, d): (usize, Duration)| format!("{},{}", n, duration_str(d));
+        writeln!(f, "# ring-file config")?;
+        writeln!(f, "buf_size={}", self.buf_size)?;
+        writeln!(f, "prefault={}", self.prefault)?;
+        writeln!(f, "banner={}", opt_str(self.banner.as_deref(), quote))?;
+        writeln!(f, "sticky_banner={}", opt_str(self.sticky_banner.as_deref(), quote))?;
+        writeln!(f, "run_id={}", opt_str(self.run_id.as_deref(), quote))?;
+        for (key, value) in self.labels.iter() {
+            writeln!(f, "label={}", quote(&format!("{}={}", key, value)))?;
+        }
+        writeln!(f, "categories={},{}", self.categories.0, self.categories.1)?;
+        writeln!(f, "flush_every={}", self.flush_every)?;
+        writeln!(f, "live_flush_interval={}", opt_str(self.live_flush_interval, duration_str))?;
+        writeln!(f, "tcp_timeout={}", duration_str(self.tcp_timeout))?;
+        writeln!(f, "strict_order={}", opt_str(self.strict_order, pair))?;
+        writeln!(f, "max_dumps_per={}", opt_str(self.max_dumps_per, pair))?;
+        let sample = opt_str(self.sample, |(high, low)| format!("{},{}", high, low));
+        writeln!(f, "adaptive_sample={}", sample)?;
+        writeln!(f, "max_queued_bytes={}", self.max_queued_bytes)?;
+        writeln!(f, "timestamps={}", self.timestamps)?;
+        writeln!(f, "timestamp_precision={}", precision_name(self.timestamp_precision))?;
+        writeln!(f, "uptime={}", self.uptime)?;
+        writeln!(f, "binary_uptime={}", self.binary_uptime)?;
+        writeln!(f, "thread_names={}", self.thread_names)?;
+        writeln!(f, "per_thread_seq={}", self.per_thread_seq)?;
+        writeln!(f, "indent={}", self.indent)?;
+        writeln!(f, "utf8={}", utf8_name(self.utf8))?;
+        writeln!(f, "max_line_len={}", self.max_line_len)?;
+        writeln!(f, "armed={}", self.armed)?;
+        writeln!(f, "skip_empty={}", self.skip_empty)?;
+        writeln!(f, "strip_ansi={}", self.strip_ansi)?;
+        writeln!(f, "histogram={}", self.histogram)?;
+        writeln!(f, "end_marker={}", self.end_marker)?;
+        writeln!(f, "generations={}", self.generations)?;
+        writeln!(f, "dedup={}", self.dedup)?;
+        writeln!(f, "max_total_bytes={}", self.max_total_bytes)?;
+        writeln!(f, "reuse_dump_file={}", self.reuse_dump_file)?;
+        writeln!(f, "partial_dump={}", partial_dump_name(self.partial_dump))?;
+        writeln!(f, "dump_mode=0o{:o}", self.dump_mode)?;
+        writeln!(f, "align={}", self.align)?;
+        writeln!(f, "dump_on_idle={}", opt_str(self.dump_on_idle, duration_str))?;
+        writeln!(f, "no_split={}", self.no_split)?;
+        writeln!(f, "inline={}", self.inline)?;
+        writeln!(f, "line_mode={}", self.line_mode)?;
+        writeln!(f, "framed={}", self.framed)
+    }
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let digits = s.bytes().take_while(|b| b.is_ascii_digit()).count();
+    let n: u64 = s[..digits].parse().ok()?;
+    match &s[digits..] {
+        "ns" => Some(Duration::from_nanos(n)),
+        "us" => Some(Duration::from_micros(n)),
+        "ms" => Some(Duration::from_millis(n)),
+        "s" => Some(Duration::from_secs(n)),
+        _ => None,
+    }
+}
+
+fn parse_str(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push(match chars.next()? {
+                '\\' => '\\',
+                '"' => '"',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                _ => return None,
+            }),
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Parse `-` as None, anything else with `f`.
+fn parse_opt<T>(s: &str, f: impl FnOnce(&str) -> Option<T>) -> Option<Option<T>> {
+    if s == "-" {
+        Some(None)
+    } else {
+        f(s).map(Some)
+    }
+}
+
+fn parse_pair<A: FromStr, B>(s: &str, f: impl FnOnce(&str) -> Option<B>) -> Option<(A, B)> {
+    let (a, b) = s.split_once(',')?;
+    Some((a.parse().ok()?, f(b)?))
+}
+
+fn parse_num<T: FromStr>(s: &str) -> Option<T> {
+    s.parse().ok()
+}
+
+/// The text form, also written by `Display`: one `key=value` per line, every option written,
+/// e.g. `buf_size=1048576` or `dump_on_idle=500ms`. Durations take the unit `ns`, `us`, `ms`
+/// or `s`, strings are quoted with the escapes `\\`, `\"`, `\n`, `\r` and `\t`, `-` is none,
+/// and a pair is separated by `,`. Each label is a line `label="key=value"`. Lines starting
+/// with `#` are comments, and a key left out keeps the default of [RingFileBuilder::new()],
+/// except `buf_size` which is required.
+///
+/// The format is unstable, not a compatibility promise, see [RingFileConfig].
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::{RingFileBuilder, RingFileConfig};
+/// use std::path::Path;
+/// use std::time::Duration;
+/// let config = RingFileBuilder::new(4096, Path::new("/tmp/ringfile_config.store").into())
+///     .sticky_banner("svc \"api\"\n".to_string())
+///     .label("host".to_string(), "web-1".to_string())
+///     .dump_on_idle(Duration::from_millis(1500))
+///     .config();
+/// let text = config.to_string();
+/// assert!(text.contains("\nsticky_banner=\"svc \\\"api\\\"\\n\"\n"));
+/// assert!(text.contains("\nlabel=\"host=web-1\"\n"));
+/// assert!(text.contains("\ndump_on_idle=1500ms\n"));
+/// let parsed: RingFileConfig = text.parse().expect("parse ok");
+/// assert_eq!(parsed, config);
+///
+/// // A missing buf_size, an unknown key or a bad value is an error with the line
+/// let e = "timestamps=true\n".parse::<RingFileConfig>().unwrap_err();
+/// assert!(e.to_string().contains("buf_size"));
+/// let e = "buf_size=64\nbogus=1\n".parse::<RingFileConfig>().unwrap_err();
+/// assert!(e.to_string().starts_with("line 2: "));
+/// let e = "buf_size=64\ndedup=yes\n".parse::<RingFileConfig>().unwrap_err();
+/// assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+/// ```
+impl FromStr for RingFileConfig {
+    type Err = Error;
+
+    /// Parse the text form, returns error of `ErrorKind::InvalidData` naming the line of an
+    /// unknown key or a bad value, or when `buf_size` is missing.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut config = RingFileBuilder::new(0, Path::new("").into()).config();
+        let mut has_size = false;
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {}: {}: {:?}", i + 1, reason, line),
+                )
+            };
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid("expect key=value"))?;
+            let ok = match key {
+                "buf_size" => parse_num(value).map(|n| {
+                    has_size = true;
+                    config.buf_size = n
+                }),
+                "prefault" => parse_num(value).map(|b| config.prefault = b),
+                "banner" => parse_opt(value, parse_str).map(|s| config.banner = s),
+                "sticky_banner" => parse_opt(value, parse_str).map(|s| config.sticky_banner = s),
+                "run_id" => parse_opt(value, parse_str).map(|s| config.run_id = s),
+                "label" => parse_str(value).and_then(|label| {
+                    let (key, value) = label.split_once('=')?;
+                    config.labels.push((key.to_string(), value.to_string()));
+                    Some(())
+                }),
+                "categories" => parse_pair(value, parse_num).map(|p| config.categories = p),
+                "flush_every" => parse_num(value).map(|n| config.flush_every = n),
+                "live_flush_interval" => {
+                    parse_opt(value, parse_duration).map(|d| config.live_flush_interval = d)
+                }
+                "tcp_timeout" => parse_duration(value).map(|d| config.tcp_timeout = d),
+                "strict_order" => parse_opt(value, |v| parse_pair(v, parse_duration))
+                    .map(|p| config.strict_order = p),
+                "max_dumps_per" => parse_opt(value, |v| parse_pair(v, parse_duration))
+                    .map(|p| config.max_dumps_per = p),
+                "adaptive_sample" => {
+                    parse_opt(value, |v| parse_pair(v, parse_num)).map(|p| config.sample = p)
+                }
+                "max_queued_bytes" => parse_num(value).map(|n| config.max_queued_bytes = n),
+                "timestamps" => parse_num(value).map(|b| config.timestamps = b),
+                "timestamp_precision" => match value {
+                    "s" => Some(TimestampPrecision::Secs),
+                    "ms" => Some(TimestampPrecision::Millis),
+                    "us" => Some(TimestampPrecision::Micros),
+                    "ns" => Some(TimestampPrecision::Nanos),
+                    _ => None,
+                }
+                .map(|p| config.timestamp_precision = p),
+                "uptime" => parse_num(value).map(|b| config.uptime = b),
+                "binary_uptime" => parse_num(value).map(|b| config.binary_uptime = b),
+                "thread_names" => parse_num(value).map(|b| config.thread_names = b),
+                "per_thread_seq" => parse_num(value).map(|b| config.per_thread_seq = b),
+                "indent" => parse_num(value).map(|b| config.indent = b),
+                "utf8" => match value {
+                    "off" => Some(Utf8Mode::Off),
+                    "replace" => Some(Utf8Mode::Replace),
+                    "reject" => Some(Utf8Mode::Reject),
+                    _ => None,
+                }
+                .map(|m| config.utf8 = m),
+                "max_line_len" => parse_num(value).map(|n| config.max_line_len = n),
+                "armed" => parse_num(value).map(|b| config.armed = b),
+                "skip_empty" => parse_num(value).map(|b| config.skip_empty = b),
+                "strip_ansi" => parse_num(value).map(|b| config.strip_ansi = b),
+                "histogram" => parse_num(value).map(|b| config.histogram = b),
+                "end_marker" => parse_num(value).map(|b| config.end_marker = b),
+                "generations" => parse_num(value).map(|n| config.generations = n),
+                "dedup" => parse_num(value).map(|b| config.dedup = b),
+                "max_total_bytes" => parse_num(value).map(|n| config.max_total_bytes = n),
+                "reuse_dump_file" => parse_num(value).map(|b| config.reuse_dump_file = b),
+                "partial_dump" => match value {
+                    "keep" => Some(PartialDump::Keep),
+                    "remove" => Some(PartialDump::Remove),
+                    "keep_previous" => Some(PartialDump::KeepPrevious),
+                    _ => None,
+                }
+                .map(|p| config.partial_dump = p),
+                "dump_mode" => value
+                    .strip_prefix("0o")
+                    .and_then(|v| u32::from_str_radix(v, 8).ok())
+                    .map(|m| config.dump_mode = m),
+                "align" => parse_num(value).map(|n| config.align = n),
+                "dump_on_idle" => parse_opt(value, parse_duration).map(|d| config.dump_on_idle = d),
+                "no_split" => parse_num(value).map(|n| config.no_split = n),
+                "inline" => parse_num(value).map(|b| config.inline = b),
+                "line_mode" => parse_num(value).map(|b| config.line_mode = b),
+                "framed" => parse_num(value).map(|b| config.framed = b),
+                _ => return Err(invalid("unknown key")),
+            };
+            ok.ok_or_else(|| invalid("invalid value"))?;
+        }
+        if !has_size {
+            return Err(Error::new(ErrorKind::InvalidData, "buf_size is required"));
+        }
+        Ok(config)
+    }
+}
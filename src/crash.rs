@@ -0,0 +1,102 @@
+use std::cell::Cell;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(unix)]
+use std::sync::Mutex;
+
+thread_local! {
+    /// Set on the thread running a backend, where the panic hook must not wait for a dump
+    static IN_BACKEND: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Mark the current thread as running a backend until dropped, see
+/// [RingFile::arm_crash_capture()](crate::RingFile::arm_crash_capture()).
+pub(crate) struct BackendGuard(bool);
+
+impl BackendGuard {
+    #[inline]
+    pub(crate) fn enter() -> Self {
+        Self(IN_BACKEND.with(|flag| flag.replace(true)))
+    }
+}
+
+impl Drop for BackendGuard {
+    #[inline]
+    fn drop(&mut self) {
+        IN_BACKEND.with(|flag| flag.set(self.0));
+    }
+}
+
+#[inline]
+pub(crate) fn in_backend() -> bool {
+    IN_BACKEND.with(|flag| flag.get())
+}
+
+/// Times each signal was received, bumped by the handler and polled by the backends
+#[cfg(unix)]
+static RAISED: [AtomicU64; 64] = [const { AtomicU64::new(0) }; 64];
+
+/// Mask of the signals with the handler installed
+#[cfg(unix)]
+static INSTALLED: Mutex<u64> = Mutex::new(0);
+
+#[cfg(unix)]
+extern "C" fn on_signal(sig: libc::c_int) {
+    // Only an atomic increment, which is async-signal-safe
+    RAISED[sig as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Install the handler of `signals` unless already installed.
+#[cfg(unix)]
+pub(crate) fn install(signals: &[i32]) -> std::io::Result<()> {
+    for &sig in signals {
+        let fatal =
+            [libc::SIGSEGV, libc::SIGBUS, libc::SIGFPE, libc::SIGILL, libc::SIGKILL, libc::SIGSTOP];
+        if sig <= 0 || sig >= 64 || fatal.contains(&sig) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("signal {} can not be captured by polling", sig),
+            ));
+        }
+    }
+    let mut installed = INSTALLED.lock().unwrap();
+    for &sig in signals {
+        if *installed & (1 << sig) != 0 {
+            continue;
+        }
+        // SAFETY: the action is fully initialized, and the handler is async-signal-safe
+        let res = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(sig, &action, std::ptr::null_mut())
+        };
+        if res != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        *installed |= 1 << sig;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn install(signals: &[i32]) -> std::io::Result<()> {
+    if signals.is_empty() {
+        return Ok(());
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "signals are only captured on unix"))
+}
+
+/// How many times `sig` was received since the start.
+#[cfg(unix)]
+#[inline]
+pub(crate) fn raised(sig: i32) -> u64 {
+    RAISED[sig as usize].load(Ordering::Relaxed)
+}
+
+#[cfg(not(unix))]
+#[inline]
+pub(crate) fn raised(_sig: i32) -> u64 {
+    0
+}
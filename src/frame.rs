@@ -0,0 +1,89 @@
+//! The length prefix of the framed storage, see [RingBuffer::set_framed()](crate::RingBuffer::set_framed()).
+//!
+//! Each record is stored as the LEB128 length of the payload followed by the payload,
+//! so the prefix takes 1 byte below 128 bytes, 2 bytes below 16KB, 3 bytes below 2MB.
+
+use std::borrow::Cow;
+
+/// Upper bound of the prefix length, enough for u32.
+pub(crate) const MAX_PREFIX_LEN: usize = 5;
+
+/// Encode the prefix of a `len` bytes payload into `buf`, return the prefix length.
+#[inline]
+pub(crate) fn encode_len(mut len: usize, buf: &mut [u8; MAX_PREFIX_LEN]) -> usize {
+    let mut i = 0;
+    loop {
+        let b = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            buf[i] = b;
+            return i + 1;
+        }
+        buf[i] = b | 0x80;
+        i += 1;
+    }
+}
+
+#[inline]
+pub(crate) fn prefix_len(len: usize) -> usize {
+    encode_len(len, &mut [0u8; MAX_PREFIX_LEN])
+}
+
+/// The largest payload that fits into `capacity` bytes together with its prefix.
+pub(crate) fn max_payload(capacity: usize) -> usize {
+    let mut payload = capacity.saturating_sub(1);
+    while payload > 0 && prefix_len(payload) + payload > capacity {
+        payload -= 1;
+    }
+    payload
+}
+
+/// Take the first byte of the two slices.
+#[inline]
+fn pop_byte<'a>(head: &mut &'a [u8], tail: &mut &'a [u8]) -> Option<u8> {
+    if head.is_empty() {
+        *head = std::mem::take(tail);
+    }
+    let (b, rest) = head.split_first()?;
+    *head = rest;
+    Some(*b)
+}
+
+/// Decode the prefix at the front of the two slices and consume it, return the payload length.
+#[inline]
+pub(crate) fn decode_len<'a>(head: &mut &'a [u8], tail: &mut &'a [u8]) -> Option<usize> {
+    let mut len = 0;
+    for i in 0..MAX_PREFIX_LEN {
+        let b = pop_byte(head, tail)?;
+        len |= ((b & 0x7f) as usize) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some(len);
+        }
+    }
+    None
+}
+
+/// Consume the record at the front of the two slices, return the payload.
+/// The payload across the seam is stitched into an owned copy.
+pub(crate) fn next_record<'a>(head: &mut &'a [u8], tail: &mut &'a [u8]) -> Option<Cow<'a, [u8]>> {
+    let len = decode_len(head, tail)?;
+    if head.is_empty() {
+        *head = std::mem::take(tail);
+    }
+    if len <= head.len() {
+        let (payload, rest) = head.split_at(len);
+        *head = rest;
+        return Some(Cow::Borrowed(payload));
+    }
+    let n = len - head.len();
+    if n > tail.len() {
+        // Truncated, should not happen from a record boundary
+        return None;
+    }
+    let mut payload = Vec::with_capacity(len);
+    payload.extend_from_slice(head);
+    payload.extend_from_slice(&tail[0..n]);
+    *head = &tail[n..];
+    *tail = &[];
+    Some(Cow::Owned(payload))
+}
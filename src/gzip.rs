@@ -0,0 +1,347 @@
+//! Minimal gzip support for the spill files, without extra dependency.
+//!
+//! The encoder emits deflate blocks with fixed Huffman codes and LZ77 matching inside each block.
+//! The decoder only inflates what the encoder produces (stored and fixed Huffman blocks).
+
+use std::io::{Error, ErrorKind, Result, Write};
+
+const BLOCK_SIZE: usize = 64 * 1024;
+const WINDOW: usize = 32 * 1024;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 32;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] =
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+pub(crate) fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    for b in data {
+        c = CRC_TABLE[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+#[inline]
+fn reverse_bits(code: u32, len: u32) -> u32 {
+    code.reverse_bits() >> (32 - len)
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    nbits: u32,
+}
+
+impl BitWriter {
+    #[inline]
+    fn put(&mut self, value: u32, n: u32) {
+        self.bits |= (value as u64) << self.nbits;
+        self.nbits += n;
+        while self.nbits >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.nbits -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting from the most significant bit.
+    #[inline]
+    fn put_code(&mut self, code: u32, len: u32) {
+        self.put(reverse_bits(code, len), len);
+    }
+
+    fn put_literal(&mut self, sym: u32) {
+        match sym {
+            0..=143 => self.put_code(0x30 + sym, 8),
+            144..=255 => self.put_code(0x190 + sym - 144, 9),
+            256..=279 => self.put_code(sym - 256, 7),
+            _ => self.put_code(0xc0 + sym - 280, 8),
+        }
+    }
+
+    fn put_match(&mut self, len: usize, dist: usize) {
+        let i = LEN_BASE.iter().rposition(|b| *b as usize <= len).unwrap();
+        self.put_literal(257 + i as u32);
+        self.put((len - LEN_BASE[i] as usize) as u32, LEN_EXTRA[i] as u32);
+        let d = DIST_BASE.iter().rposition(|b| *b as usize <= dist).unwrap();
+        self.put_code(d as u32, 5);
+        self.put((dist - DIST_BASE[d] as usize) as u32, DIST_EXTRA[d] as u32);
+    }
+
+    fn align(&mut self) {
+        if self.nbits > 0 {
+            self.out.push(self.bits as u8);
+            self.bits = 0;
+            self.nbits = 0;
+        }
+    }
+}
+
+/// Streaming gzip encoder, call finish() to write the trailer.
+pub(crate) struct GzEncoder<W: Write> {
+    inner: W,
+    bits: BitWriter,
+    pending: Vec<u8>,
+    crc: u32,
+    size: u32,
+}
+
+impl<W: Write> GzEncoder<W> {
+    pub(crate) fn new(mut inner: W) -> Result<Self> {
+        inner.write_all(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff])?;
+        Ok(Self {
+            inner,
+            bits: BitWriter { out: Vec::new(), bits: 0, nbits: 0 },
+            pending: Vec::with_capacity(BLOCK_SIZE),
+            crc: 0,
+            size: 0,
+        })
+    }
+
+    fn compress_block(&mut self, last: bool) -> Result<()> {
+        let data = &self.pending;
+        let bits = &mut self.bits;
+        bits.put(last as u32, 1);
+        bits.put(1, 2);
+        let mut head = vec![usize::MAX; 1 << HASH_BITS];
+        let mut prev = vec![usize::MAX; data.len()];
+        let hash = |i: usize| {
+            let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+            (v.wrapping_mul(0x9e3779b1) >> (32 - HASH_BITS)) as usize
+        };
+        let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+            if i + MIN_MATCH <= data.len() {
+                let h = hash(i);
+                prev[i] = head[h];
+                head[h] = i;
+            }
+        };
+        let mut i = 0;
+        while i < data.len() {
+            let mut best_len = 0;
+            let mut best_dist = 0;
+            if i + MIN_MATCH <= data.len() {
+                let mut cand = head[hash(i)];
+                let mut chain = 0;
+                let max_len = MAX_MATCH.min(data.len() - i);
+                while cand != usize::MAX && i - cand <= WINDOW && chain < MAX_CHAIN {
+                    let l = data[cand..cand + max_len]
+                        .iter()
+                        .zip(data[i..i + max_len].iter())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    if l > best_len {
+                        best_len = l;
+                        best_dist = i - cand;
+                        if l == max_len {
+                            break;
+                        }
+                    }
+                    cand = prev[cand];
+                    chain += 1;
+                }
+            }
+            if best_len >= MIN_MATCH {
+                bits.put_match(best_len, best_dist);
+                for j in i..i + best_len {
+                    insert(j, &mut head, &mut prev);
+                }
+                i += best_len;
+            } else {
+                bits.put_literal(data[i] as u32);
+                insert(i, &mut head, &mut prev);
+                i += 1;
+            }
+        }
+        bits.put_literal(256);
+        self.pending.clear();
+        self.inner.write_all(&self.bits.out)?;
+        self.bits.out.clear();
+        Ok(())
+    }
+
+    /// Write the last block and the trailer, return the inner writer.
+    pub(crate) fn finish(mut self) -> Result<W> {
+        self.compress_block(true)?;
+        self.bits.align();
+        self.inner.write_all(&self.bits.out)?;
+        self.inner.write_all(&self.crc.to_le_bytes())?;
+        self.inner.write_all(&self.size.to_le_bytes())?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for GzEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let l = buf.len().min(BLOCK_SIZE - self.pending.len());
+        self.pending.extend_from_slice(&buf[0..l]);
+        self.crc = crc32(self.crc, &buf[0..l]);
+        self.size = self.size.wrapping_add(l as u32);
+        if self.pending.len() >= BLOCK_SIZE {
+            self.compress_block(false)?;
+        }
+        Ok(l)
+    }
+
+    /// Only flush the completed blocks to the inner writer.
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    #[inline]
+    fn bit(&mut self) -> Result<u32> {
+        let byte = *self.data.get(self.pos >> 3).ok_or_else(|| invalid("unexpected end"))?;
+        let b = (byte >> (self.pos & 7)) & 1;
+        self.pos += 1;
+        Ok(b as u32)
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        let mut v = 0;
+        for i in 0..n {
+            v |= self.bit()? << i;
+        }
+        Ok(v)
+    }
+
+    /// Read a Huffman code from the most significant bit.
+    fn code(&mut self, len: u32) -> Result<u32> {
+        let mut v = 0;
+        for _ in 0..len {
+            v = (v << 1) | self.bit()?;
+        }
+        Ok(v)
+    }
+
+    fn literal(&mut self) -> Result<u32> {
+        let mut code = self.code(7)?;
+        if code <= 0x17 {
+            return Ok(256 + code);
+        }
+        code = (code << 1) | self.bit()?;
+        match code {
+            0x30..=0xbf => return Ok(code - 0x30),
+            0xc0..=0xc7 => return Ok(280 + code - 0xc0),
+            _ => {}
+        }
+        code = (code << 1) | self.bit()?;
+        Ok(144 + code - 0x190)
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("gzip: {}", msg))
+}
+
+/// Decompress gzip members produced by [GzEncoder].
+pub(crate) fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let member = &data[offset..];
+        if member.len() < 18 || member[0] != 0x1f || member[1] != 0x8b || member[2] != 8 {
+            return Err(invalid("bad header"));
+        }
+        if member[3] != 0 {
+            return Err(invalid("unsupported header flags"));
+        }
+        let start = out.len();
+        let mut r = BitReader { data: &member[10..], pos: 0 };
+        loop {
+            let last = r.bit()?;
+            match r.bits(2)? {
+                0 => {
+                    r.pos = (r.pos + 7) & !7;
+                    let at = r.pos >> 3;
+                    let hdr = r.data.get(at..at + 4).ok_or_else(|| invalid("unexpected end"))?;
+                    let len = u16::from_le_bytes([hdr[0], hdr[1]]) as usize;
+                    let stored = r
+                        .data
+                        .get(at + 4..at + 4 + len)
+                        .ok_or_else(|| invalid("unexpected end"))?;
+                    out.extend_from_slice(stored);
+                    r.pos = (at + 4 + len) << 3;
+                }
+                1 => loop {
+                    let sym = r.literal()?;
+                    if sym < 256 {
+                        out.push(sym as u8);
+                    } else if sym == 256 {
+                        break;
+                    } else {
+                        let i = (sym - 257) as usize;
+                        if i >= LEN_BASE.len() {
+                            return Err(invalid("bad length code"));
+                        }
+                        let len = LEN_BASE[i] as usize + r.bits(LEN_EXTRA[i] as u32)? as usize;
+                        let d = r.code(5)? as usize;
+                        if d >= DIST_BASE.len() {
+                            return Err(invalid("bad distance code"));
+                        }
+                        let dist = DIST_BASE[d] as usize + r.bits(DIST_EXTRA[d] as u32)? as usize;
+                        if dist > out.len() - start {
+                            return Err(invalid("bad distance"));
+                        }
+                        let from = out.len() - dist;
+                        for k in 0..len {
+                            out.push(out[from + k]);
+                        }
+                    }
+                },
+                _ => return Err(invalid("unsupported block type")),
+            }
+            if last == 1 {
+                break;
+            }
+        }
+        let at = 10 + ((r.pos + 7) >> 3);
+        let trailer = member.get(at..at + 8).ok_or_else(|| invalid("unexpected end"))?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != crc32(0, &out[start..]) || size != (out.len() - start) as u32 {
+            return Err(invalid("checksum mismatch"));
+        }
+        offset += at + 8;
+    }
+    Ok(out)
+}
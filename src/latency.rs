@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Width of a window, the figures cover the current and the previous window
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Time the messages waited before the backend processed them, published by the backend, see
+/// [RingFile::backend_latency()](crate::RingFile::backend_latency()).
+#[derive(Default)]
+pub(crate) struct Latency {
+    max_nanos: AtomicU64,
+    avg_nanos: AtomicU64,
+}
+
+impl Latency {
+    /// Return (max, avg) over the last one to two seconds of messages.
+    #[inline]
+    pub(crate) fn get(&self) -> (Duration, Duration) {
+        (
+            Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed)),
+            Duration::from_nanos(self.avg_nanos.load(Ordering::Relaxed)),
+        )
+    }
+}
+
+/// (sum, count, max) of the samples in a window, in nanoseconds
+#[derive(Clone, Copy, Default)]
+struct Window {
+    sum: u64,
+    count: u64,
+    max: u64,
+}
+
+/// The windows of samples kept by the backend, which publishes into [Latency].
+pub(crate) struct LatencyWindows {
+    shared: Arc<Latency>,
+    started: Instant,
+    cur: Window,
+    prev: Window,
+}
+
+impl LatencyWindows {
+    pub(crate) fn new(shared: Arc<Latency>) -> Self {
+        Self { shared, started: Instant::now(), cur: Window::default(), prev: Window::default() }
+    }
+
+    /// Add the latency of a message and publish the figures.
+    pub(crate) fn record(&mut self, latency: Duration) {
+        let elapsed = self.started.elapsed();
+        if elapsed >= WINDOW {
+            // Nothing for a whole window, the previous one is empty
+            self.prev = if elapsed >= 2 * WINDOW { Window::default() } else { self.cur };
+            self.cur = Window::default();
+            self.started = Instant::now();
+        }
+        let nanos = latency.as_nanos() as u64;
+        self.cur.sum += nanos;
+        self.cur.count += 1;
+        self.cur.max = self.cur.max.max(nanos);
+        let count = self.cur.count + self.prev.count;
+        let avg = (self.cur.sum + self.prev.sum) / count;
+        self.shared.max_nanos.store(self.cur.max.max(self.prev.max), Ordering::Relaxed);
+        self.shared.avg_nanos.store(avg, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,62 @@
+/// Severity attached to a message by [RingFile::write_leveled()](crate::RingFile::write_leveled()).
+///
+/// On the wire, a leveled message starts with a 2-byte prefix: the letter of the level
+/// (`T`, `D`, `I`, `W` or `E`) followed by a space, e.g. `"W disk almost full\n"`.
+/// Plain messages starting with the same pattern are taken as leveled when filtering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Return the wire prefix of the level.
+    #[inline]
+    pub fn prefix(self) -> [u8; 2] {
+        let c = match self {
+            Level::Trace => b'T',
+            Level::Debug => b'D',
+            Level::Info => b'I',
+            Level::Warn => b'W',
+            Level::Error => b'E',
+        };
+        [c, b' ']
+    }
+
+    /// The lowercase name, e.g. `warn`.
+    #[inline]
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[inline]
+    pub(crate) fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Parse the level from the prefix of a record, return None if not leveled.
+    #[inline]
+    pub fn from_prefix(record: &[u8]) -> Option<Self> {
+        if record.len() < 2 || record[1] != b' ' {
+            return None;
+        }
+        match record[0] {
+            b'T' => Some(Level::Trace),
+            b'D' => Some(Level::Debug),
+            b'I' => Some(Level::Info),
+            b'W' => Some(Level::Warn),
+            b'E' => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
@@ -15,6 +15,72 @@
 //! Already integrated into [captain-log](https://docs.rs/captains-log) as `LogRingFile` sink.
 
 mod buffer;
-pub use buffer::RingBuffer;
+pub use buffer::{LineChunks, Lines, PartialDump, RingBuffer, Stats};
+mod array;
+pub use array::{ArrayRingBuffer, FixedRingBuffer, RingBufferRef};
+#[cfg(feature = "lz4")]
+mod compressed;
+#[cfg(feature = "lz4")]
+pub use compressed::CompressedRingBuffer;
+mod level;
+pub use level::Level;
+mod record;
+pub use record::{Markers, Record, Records};
+mod sink;
+pub use sink::{DumpSink, PlainSink, RecordMeta};
+mod loss;
+pub use loss::{DropReason, OnDrop};
+mod view;
+pub use view::{LocalRingBuffer, RingBufferView};
 mod threads;
-pub use threads::RingFile;
+pub use threads::{
+    MirrorRegion, OnFrozen, OnLargeWrite, OnMessage, PathFn, PendingDump, RingFile, RingFileDriver,
+    RingFileTask, Utf8Mode,
+};
+mod builder;
+pub use builder::RingFileBuilder;
+mod ansi;
+mod backlog;
+mod category;
+mod config;
+#[cfg(feature = "unstable")]
+mod config_text;
+pub use config::RingFileConfig;
+mod crash;
+mod frame;
+mod gzip;
+mod latency;
+#[cfg(feature = "lz4")]
+mod lz4;
+mod manifest;
+mod marker;
+pub use marker::{is_complete_dump, END_MARKER};
+mod rate;
+mod sample;
+mod size;
+mod span;
+pub use span::Span;
+mod shard;
+pub use shard::ShardedRingFile;
+mod spill;
+mod spsc;
+pub use spsc::{SpscReader, SpscRingBuffer};
+mod stderr;
+mod tag;
+pub use tag::split_by_thread;
+#[cfg(feature = "tar")]
+mod tar;
+mod task;
+mod timestamp;
+pub use timestamp::{decode_timestamps, TimestampPrecision};
+mod transport;
+#[cfg(feature = "crossbeam")]
+pub use transport::CrossbeamTransport;
+pub use transport::{
+    Channel, MsgReceiver, MsgSender, MsgTransport, RecvTimeoutError, StdTransport,
+};
+mod writers;
+#[cfg(feature = "zip")]
+mod zip;
+#[cfg(feature = "zip")]
+pub use zip::ZipWriter;
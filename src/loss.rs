@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Why a message, or part of it, did not make it into the buffer, see
+/// [RingFileBuilder::on_drop()](crate::RingFileBuilder::on_drop()).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// Sampled out by [RingFileBuilder::adaptive_sample()](crate::RingFileBuilder::adaptive_sample())
+    Sampled,
+    /// Over [RingFileBuilder::max_queued_bytes()](crate::RingFileBuilder::max_queued_bytes())
+    Overflow,
+    /// Rejected by [Utf8Mode::Reject](crate::Utf8Mode::Reject)
+    InvalidUtf8,
+    /// The head of a record larger than the buffer, cut by
+    /// [RingFileBuilder::framed()](crate::RingFileBuilder::framed())
+    Truncated,
+    /// The tail of a record cut by
+    /// [RingFileBuilder::max_line_len()](crate::RingFileBuilder::max_line_len())
+    LineTooLong,
+    /// Written while [RingFile::disarm()](crate::RingFile::disarm())
+    Disarmed,
+    /// Written after [RingFileBuilder::max_total_bytes()](crate::RingFileBuilder::max_total_bytes())
+    Frozen,
+}
+
+impl DropReason {
+    const ALL: [DropReason; 7] = [
+        DropReason::Sampled,
+        DropReason::Overflow,
+        DropReason::InvalidUtf8,
+        DropReason::Truncated,
+        DropReason::LineTooLong,
+        DropReason::Disarmed,
+        DropReason::Frozen,
+    ];
+
+    /// How the drops are noted in the dump trailer
+    fn describe(self) -> &'static str {
+        match self {
+            DropReason::Sampled => "sampled out",
+            DropReason::Overflow => "dropped on queue overflow",
+            DropReason::InvalidUtf8 => "rejected as invalid UTF-8",
+            DropReason::Truncated => "truncated",
+            DropReason::LineTooLong => "cut by max_line_len",
+            DropReason::Disarmed => "dropped while disarmed",
+            DropReason::Frozen => "dropped after max_total_bytes",
+        }
+    }
+}
+
+/// Callback of [RingFileBuilder::on_drop()](crate::RingFileBuilder::on_drop()), with the reason
+/// and the number of bytes dropped.
+pub type OnDrop = Box<dyn Fn(DropReason, usize) + Send + Sync>;
+
+/// Shared by the writers and the backend to account the drops of every reason.
+pub(crate) struct Drops {
+    /// Messages of each reason, in the order of [DropReason::ALL]
+    counts: [AtomicU64; 7],
+    on_drop: Option<OnDrop>,
+}
+
+impl Drops {
+    pub(crate) fn new(on_drop: Option<OnDrop>) -> Self {
+        Self { counts: Default::default(), on_drop }
+    }
+
+    /// Called on the thread where the drop happens.
+    #[cold]
+    pub(crate) fn record(&self, reason: DropReason, bytes: usize) {
+        self.counts[reason as usize].fetch_add(1, Ordering::Relaxed);
+        if let Some(f) = self.on_drop.as_ref() {
+            f(reason, bytes);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn count(&self, reason: DropReason) -> u64 {
+        self.counts[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// The lines appended to the dump, one per reason with drops, None without drops.
+    pub(crate) fn trailer(&self) -> Option<String> {
+        let mut trailer = String::new();
+        for reason in DropReason::ALL {
+            let n = self.count(reason);
+            if n > 0 {
+                trailer.push_str(&format!("[ring-file: {} messages {}]\n", n, reason.describe()));
+            }
+        }
+        if trailer.is_empty() {
+            None
+        } else {
+            Some(trailer)
+        }
+    }
+}
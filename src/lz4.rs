@@ -0,0 +1,148 @@
+//! Minimal LZ4 block format support for [CompressedRingBuffer](crate::CompressedRingBuffer),
+//! without extra dependency.
+//!
+//! The encoder is the greedy single-probe matcher of the reference implementation, the decoder
+//! accepts any valid block.
+
+use std::io::{Error, ErrorKind, Result};
+
+const HASH_BITS: u32 = 12;
+const MIN_MATCH: usize = 4;
+/// The last match starts at least 12 bytes before the end of the block
+const MF_LIMIT: usize = 12;
+/// The last 5 bytes of the block are literals
+const LAST_LITERALS: usize = 5;
+const MAX_OFFSET: usize = 65535;
+
+#[inline]
+fn read_u32(src: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([src[pos], src[pos + 1], src[pos + 2], src[pos + 3]])
+}
+
+#[inline]
+fn hash(seq: u32) -> usize {
+    (seq.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Append the length beyond the 15 of the token, in bytes of 255.
+#[inline]
+fn put_len(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 255 {
+        out.push(255);
+        n -= 255;
+    }
+    out.push(n as u8);
+}
+
+fn put_sequence(out: &mut Vec<u8>, literals: &[u8], offset: usize, match_len: usize) {
+    let (ll, ml) = (literals.len(), match_len - MIN_MATCH);
+    out.push(((ll.min(15) << 4) | ml.min(15)) as u8);
+    if ll >= 15 {
+        put_len(out, ll - 15);
+    }
+    out.extend_from_slice(literals);
+    out.extend_from_slice(&(offset as u16).to_le_bytes());
+    if ml >= 15 {
+        put_len(out, ml - 15);
+    }
+}
+
+/// Compress `src` into a LZ4 block.
+pub(crate) fn compress(src: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len() + src.len() / 255 + 16);
+    // Position + 1 of the last sequence of each hash, 0 for none
+    let mut table = vec![0u32; 1 << HASH_BITS];
+    let mut anchor = 0;
+    if src.len() > MF_LIMIT {
+        let match_limit = src.len() - MF_LIMIT;
+        let end_limit = src.len() - LAST_LITERALS;
+        let mut i = 0;
+        while i < match_limit {
+            let seq = read_u32(src, i);
+            let h = hash(seq);
+            let candidate = table[h] as usize;
+            table[h] = i as u32 + 1;
+            if candidate == 0
+                || i - (candidate - 1) > MAX_OFFSET
+                || read_u32(src, candidate - 1) != seq
+            {
+                i += 1;
+                continue;
+            }
+            let (mut start, mut from) = (i, candidate - 1);
+            let mut len = MIN_MATCH;
+            while i + len < end_limit && src[from + len] == src[i + len] {
+                len += 1;
+            }
+            while start > anchor && from > 0 && src[start - 1] == src[from - 1] {
+                start -= 1;
+                from -= 1;
+                len += 1;
+            }
+            put_sequence(&mut out, &src[anchor..start], start - from, len);
+            i = start + len;
+            anchor = i;
+        }
+    }
+    let ll = src.len() - anchor;
+    out.push((ll.min(15) << 4) as u8);
+    if ll >= 15 {
+        put_len(&mut out, ll - 15);
+    }
+    out.extend_from_slice(&src[anchor..]);
+    out
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("lz4: {}", msg))
+}
+
+/// Read the length beyond the 15 of the token.
+#[inline]
+fn get_len(src: &[u8], pos: &mut usize) -> Result<usize> {
+    let mut n = 0;
+    loop {
+        let b = *src.get(*pos).ok_or_else(|| invalid("truncated length"))?;
+        *pos += 1;
+        n += b as usize;
+        if b != 255 {
+            return Ok(n);
+        }
+    }
+}
+
+/// Decompress the LZ4 block `src`, appending to `out`.
+pub(crate) fn decompress(src: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    let start = out.len();
+    let mut pos = 0;
+    loop {
+        let token = *src.get(pos).ok_or_else(|| invalid("truncated block"))?;
+        pos += 1;
+        let mut ll = (token >> 4) as usize;
+        if ll == 15 {
+            ll += get_len(src, &mut pos)?;
+        }
+        let literals = src.get(pos..pos + ll).ok_or_else(|| invalid("truncated literals"))?;
+        out.extend_from_slice(literals);
+        pos += ll;
+        if pos == src.len() {
+            return Ok(());
+        }
+        let offset = src.get(pos..pos + 2).ok_or_else(|| invalid("truncated offset"))?;
+        let offset = u16::from_le_bytes([offset[0], offset[1]]) as usize;
+        pos += 2;
+        if offset == 0 || offset > out.len() - start {
+            return Err(invalid("offset out of the block"));
+        }
+        let mut ml = (token & 15) as usize + MIN_MATCH;
+        if token & 15 == 15 {
+            ml += get_len(src, &mut pos)?;
+        }
+        // The match may overlap the bytes it produces
+        let from = out.len() - offset;
+        for k in 0..ml {
+            let b = out[from + k];
+            out.push(b);
+        }
+    }
+}
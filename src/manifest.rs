@@ -0,0 +1,105 @@
+use crate::buffer::create_file;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The first line of the manifest, naming the columns
+const HEADER: &str = "# time_ms\tsize\twraps\trun_id\tlabels\tpath\n";
+
+/// Index of the dump files, see [RingFileBuilder::manifest()](crate::RingFileBuilder::manifest()).
+pub(crate) struct Manifest {
+    path: PathBuf,
+    /// Permissions of the manifest on unix
+    mode: u32,
+    /// Of [RingFileBuilder::run_id()](crate::RingFileBuilder::run_id()), `-` for none
+    run_id: String,
+    /// Of [RingFileBuilder::label()](crate::RingFileBuilder::label()) joined by `,`, `-` for none
+    labels: String,
+    /// In the order of the dumps, the path is unique
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    /// Time of the dump since UNIX epoch
+    time_ms: u128,
+    size: u64,
+    wraps: u64,
+    /// `-` for none
+    run_id: String,
+    /// `-` for none
+    labels: String,
+    file: PathBuf,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut columns = line.splitn(6, '\t');
+        Some(Self {
+            time_ms: columns.next()?.parse().ok()?,
+            size: columns.next()?.parse().ok()?,
+            wraps: columns.next()?.parse().ok()?,
+            run_id: columns.next()?.to_string(),
+            labels: columns.next()?.to_string(),
+            file: PathBuf::from(columns.next().filter(|file| !file.is_empty())?),
+        })
+    }
+}
+
+impl Manifest {
+    /// Load the entries of `path` left by a previous run, if any.
+    pub(crate) fn open(
+        path: PathBuf, mode: u32, run_id: Option<&str>, labels: &str,
+    ) -> std::io::Result<Self> {
+        let mut entries = Vec::new();
+        match std::fs::File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    if let Some(entry) = Entry::parse(&line) {
+                        entries.push(entry);
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let labels = if labels.is_empty() { "-" } else { labels }.to_string();
+        Ok(Self { path, mode, run_id: run_id.unwrap_or("-").to_string(), labels, entries })
+    }
+
+    /// Add the dump just written to `file`, drop the entries of the files gone or overwritten,
+    /// and replace the manifest atomically.
+    pub(crate) fn record(&mut self, file: &Path, wraps: u64) -> std::io::Result<()> {
+        let size = std::fs::metadata(file)?.len();
+        let time_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        self.entries.retain(|entry| entry.file != file && entry.file.exists());
+        self.entries.push(Entry {
+            time_ms,
+            size,
+            wraps,
+            run_id: self.run_id.clone(),
+            labels: self.labels.clone(),
+            file: file.to_path_buf(),
+        });
+        let mut tmp: OsString = self.path.as_os_str().into();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let mut out = BufWriter::new(create_file(&tmp, self.mode)?);
+        out.write_all(HEADER.as_bytes())?;
+        for entry in self.entries.iter() {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                entry.time_ms,
+                entry.size,
+                entry.wraps,
+                entry.run_id,
+                entry.labels,
+                entry.file.display()
+            )?;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
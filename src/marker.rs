@@ -0,0 +1,26 @@
+//! The last line of a dump with [RingFileBuilder::end_marker()](crate::RingFileBuilder::end_marker()).
+
+/// The start of the last line of a dump with
+/// [RingFileBuilder::end_marker()](crate::RingFileBuilder::end_marker()), followed by the bytes
+/// of the dump before it, e.g. `=== END OF RING DUMP === 1024 bytes`.
+pub const END_MARKER: &str = "=== END OF RING DUMP ===";
+
+/// The marker line after `len` bytes of dump.
+#[inline]
+pub(crate) fn end_line(len: usize) -> String {
+    format!("{} {} bytes\n", END_MARKER, len)
+}
+
+/// Return true if `content` ends with the line of
+/// [RingFileBuilder::end_marker()](crate::RingFileBuilder::end_marker()), counting the bytes
+/// before it, i.e. the dump was written to the end.
+///
+/// A dump cut after a message which looks like the marker is still detected, as long as the
+/// count of the message is not the bytes before it.
+pub fn is_complete_dump(content: &[u8]) -> bool {
+    let Some(body) = content.strip_suffix(b"\n") else {
+        return false;
+    };
+    let start = body.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    content[start..] == *end_line(start).as_bytes()
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Width of a slot in milliseconds
+const SLOT_MS: u64 = 100;
+/// Slots in the window, the window is one second
+const SLOTS: usize = 10;
+
+#[derive(Default)]
+struct Slot {
+    /// The tick counted in this slot
+    tick: AtomicU64,
+    writes: AtomicU64,
+    bytes: AtomicU64,
+}
+
+/// Rolling write rate over the last second, updated by the writers, see
+/// [RingFile::writes_per_sec()](crate::RingFile::writes_per_sec()).
+///
+/// The slots are reset racily when a new tick starts, the rate is approximate.
+pub(crate) struct Rate {
+    start: Instant,
+    slots: [Slot; SLOTS],
+    /// The bytes ever added
+    total: AtomicU64,
+}
+
+impl Rate {
+    pub(crate) fn new() -> Self {
+        Self { start: Instant::now(), slots: Default::default(), total: AtomicU64::new(0) }
+    }
+
+    /// Ticks start from 1, so that a slot never written is not counted as tick 0.
+    #[inline]
+    fn tick(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64 / SLOT_MS + 1
+    }
+
+    /// Called by the writer with the bytes of the message.
+    #[inline]
+    pub(crate) fn add(&self, bytes: usize) {
+        let tick = self.tick();
+        let slot = &self.slots[tick as usize % SLOTS];
+        let old = slot.tick.load(Ordering::Relaxed);
+        if old != tick
+            && slot.tick.compare_exchange(old, tick, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+        {
+            slot.writes.store(0, Ordering::Relaxed);
+            slot.bytes.store(0, Ordering::Relaxed);
+        }
+        slot.writes.fetch_add(1, Ordering::Relaxed);
+        slot.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.total.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Return (writes, bytes) per second over the last second.
+    pub(crate) fn per_sec(&self) -> (f64, f64) {
+        let tick = self.tick();
+        let (mut writes, mut bytes) = (0, 0);
+        for slot in self.slots.iter() {
+            if slot.tick.load(Ordering::Relaxed) + SLOTS as u64 > tick {
+                writes += slot.writes.load(Ordering::Relaxed);
+                bytes += slot.bytes.load(Ordering::Relaxed);
+            }
+        }
+        let window = (SLOT_MS * SLOTS as u64) as f64 / 1000.0;
+        (writes as f64 / window, bytes as f64 / window)
+    }
+}
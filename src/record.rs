@@ -0,0 +1,177 @@
+use crate::tag::{parse_seq, parse_thread_name};
+use crate::timestamp::{parse_timestamp, parse_uptime};
+use crate::{Level, Lines};
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Start of the line written by [RingFile::mark()](crate::RingFile::mark())
+const MARK_PREFIX: &[u8] = b"=== MARK: ";
+const MARK_SUFFIX: &[u8] = b" ===";
+
+/// The line `=== MARK: {name} @ {micros}us ===` of [RingFile::mark()](crate::RingFile::mark()),
+/// a newline in the name is replaced by a space.
+pub(crate) fn format_mark(name: &str, time: SystemTime) -> String {
+    let micros = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros();
+    format!("=== MARK: {} @ {}us ===\n", name.replace('\n', " "), micros)
+}
+
+/// A line of the buffer with its prefixes parsed, see [RingBuffer::records()](crate::RingBuffer::records()).
+///
+/// The prefixes are recognized in the order the writer puts them: timestamp, uptime,
+/// thread name, sequence and level. A prefix not present gives None, and everything after
+/// the prefixes is the body.
+pub struct Record<'a> {
+    raw: Cow<'a, [u8]>,
+    partial: bool,
+    timestamp: Option<SystemTime>,
+    uptime: Option<Duration>,
+    thread: Option<(usize, usize)>,
+    sequence: Option<u64>,
+    level: Option<Level>,
+    body: usize,
+}
+
+impl<'a> Record<'a> {
+    pub(crate) fn parse(raw: Cow<'a, [u8]>, partial: bool) -> Self {
+        let mut record = Record {
+            raw,
+            partial,
+            timestamp: None,
+            uptime: None,
+            thread: None,
+            sequence: None,
+            level: None,
+            body: 0,
+        };
+        if partial {
+            return record;
+        }
+        let line: &[u8] = &record.raw;
+        let mut pos = 0;
+        if let Some((time, l)) = parse_timestamp(line) {
+            record.timestamp = Some(time);
+            pos += l;
+        }
+        if let Some((elapsed, l)) = parse_uptime(&line[pos..]) {
+            record.uptime = Some(elapsed);
+            pos += l;
+        }
+        if let Some((name, l)) = parse_thread_name(&line[pos..]) {
+            record.thread = Some((pos + 2, name.len()));
+            pos += l;
+        }
+        if let Some((seq, l)) = parse_seq(&line[pos..]) {
+            record.sequence = Some(seq);
+            pos += l;
+        }
+        if let Some(level) = Level::from_prefix(&line[pos..]) {
+            record.level = Some(level);
+            pos += 2;
+        }
+        record.body = pos;
+        record
+    }
+
+    /// The wall time of [RingFileBuilder::timestamps()](crate::RingFileBuilder::timestamps()),
+    /// at the precision of the prefix.
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// The time since build of [RingFileBuilder::uptime()](crate::RingFileBuilder::uptime()).
+    #[inline]
+    pub fn uptime(&self) -> Option<Duration> {
+        self.uptime
+    }
+
+    /// The name of [RingFileBuilder::thread_names()](crate::RingFileBuilder::thread_names()),
+    /// None also when the name is not valid UTF-8.
+    #[inline]
+    pub fn thread(&self) -> Option<&str> {
+        let (start, len) = self.thread?;
+        std::str::from_utf8(&self.raw[start..start + len]).ok()
+    }
+
+    /// The number of [RingFileBuilder::per_thread_seq()](crate::RingFileBuilder::per_thread_seq()).
+    #[inline]
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// The level of [RingFile::write_leveled()](crate::RingFile::write_leveled()).
+    #[inline]
+    pub fn level(&self) -> Option<Level> {
+        self.level
+    }
+
+    /// The content after the prefixes, without the trailing newline.
+    #[inline]
+    pub fn body(&self) -> &[u8] {
+        let body = &self.raw[self.body..];
+        body.strip_suffix(b"\n").unwrap_or(body)
+    }
+
+    /// The whole line as stored, including the prefixes and the trailing newline.
+    #[inline]
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// The name given to [RingFile::mark()](crate::RingFile::mark()) when the record is a marker.
+    pub fn marker(&self) -> Option<&str> {
+        let mark = self.body().strip_prefix(MARK_PREFIX)?.strip_suffix(MARK_SUFFIX)?;
+        let at = mark.windows(3).rposition(|w| w == b" @ ")?;
+        std::str::from_utf8(&mark[..at]).ok()
+    }
+
+    /// True for the first line once some content is overwritten, as its start is likely lost
+    /// (see [RingBuffer::first_complete_record_offset()](crate::RingBuffer::first_complete_record_offset())).
+    /// Nothing is parsed from it, and the body is the bytes left.
+    #[inline]
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
+
+/// Iterator returned by [RingBuffer::records()](crate::RingBuffer::records())
+pub struct Records<'a> {
+    lines: Lines<'a>,
+    /// The next line is the first one, cut by the rewinding
+    cut: bool,
+}
+
+impl<'a> Records<'a> {
+    #[inline]
+    pub(crate) fn new(lines: Lines<'a>, cut: bool) -> Self {
+        Self { lines, cut }
+    }
+
+    /// Keep only the markers written by [RingFile::mark()](crate::RingFile::mark()), to jump
+    /// between the points of interest, see [Record::marker()].
+    #[inline]
+    pub fn markers(self) -> Markers<'a> {
+        Markers(self)
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Record<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        let partial = std::mem::take(&mut self.cut);
+        Some(Record::parse(line, partial))
+    }
+}
+
+/// Iterator returned by [Records::markers()]
+pub struct Markers<'a>(Records<'a>);
+
+impl<'a> Iterator for Markers<'a> {
+    type Item = Record<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.by_ref().find(|record| record.marker().is_some())
+    }
+}
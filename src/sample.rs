@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Shared by the writers and the backend to sample out messages under overload,
+/// see [RingFileBuilder::adaptive_sample()](crate::RingFileBuilder::adaptive_sample()).
+pub(crate) struct Sampler {
+    high: usize,
+    low: usize,
+    /// Messages sent but not yet processed by the backend
+    depth: AtomicUsize,
+    sampling: AtomicBool,
+    seq: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+impl Sampler {
+    pub(crate) fn new(high: usize, low: usize) -> Self {
+        Self {
+            high,
+            low,
+            depth: AtomicUsize::new(0),
+            sampling: AtomicBool::new(false),
+            seq: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Called by the writer before sending, return false when the message should be dropped.
+    #[inline]
+    pub(crate) fn admit(&self) -> bool {
+        let depth = self.depth.load(Ordering::Relaxed);
+        let mut sampling = self.sampling.load(Ordering::Relaxed);
+        if sampling && depth < self.low {
+            sampling = false;
+            self.sampling.store(false, Ordering::Relaxed);
+        } else if !sampling && depth > self.high {
+            sampling = true;
+            self.sampling.store(true, Ordering::Relaxed);
+        }
+        if sampling {
+            // Keep 1 in n, the deeper the queue the fewer kept
+            let n = 2 + depth / self.high;
+            if self.seq.fetch_add(1, Ordering::Relaxed) % n != 0 {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        }
+        self.depth.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Called by the backend after a message is processed.
+    #[inline]
+    pub(crate) fn done(&self) {
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
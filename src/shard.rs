@@ -0,0 +1,195 @@
+use crate::buffer::{create_file, DEFAULT_MODE};
+use crate::timestamp::parse_timestamp;
+use crate::{Level, RingFile, RingFileBuilder};
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+thread_local! {
+    /// Hash of the current thread id, computed on the first write
+    static THREAD_HASH: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+#[inline]
+fn thread_hash() -> u64 {
+    THREAD_HASH.with(|hash| match hash.get() {
+        Some(h) => h,
+        None => {
+            let mut hasher = DefaultHasher::new();
+            std::thread::current().id().hash(&mut hasher);
+            let h = hasher.finish();
+            hash.set(Some(h));
+            h
+        }
+    })
+}
+
+/// Attach to each line the time of its message, a line without timestamp inheriting the time
+/// of the line before it, and sort by time.
+///
+/// The time is taken on the caller's thread, so the threads sharing a shard may queue their
+/// messages slightly out of time order. The sort is stable, the order of a thread is kept.
+fn stamp_lines(lines: Vec<Vec<u8>>) -> Vec<(Option<SystemTime>, Vec<u8>)> {
+    let mut last = None;
+    let mut stamped: Vec<_> = lines
+        .into_iter()
+        .map(|line| {
+            last = parse_timestamp(&line).map(|(time, _)| time).or(last);
+            (last, line)
+        })
+        .collect();
+    stamped.sort_by_key(|(time, _)| *time);
+    stamped
+}
+
+/// Several [RingFile] with a backend thread each, the writes of a thread always going to the
+/// same shard by the hash of its thread id, for the write rates one backend can not drain.
+///
+/// Ordering: the messages of a thread are in order, as they go through the same shard.
+/// Between threads of different shards there is no order, unless the shards stamp the messages
+/// with [RingFileBuilder::timestamps()], which dump() merges on. Use
+/// [TimestampPrecision::Nanos](crate::TimestampPrecision::Nanos) for a fine merge.
+///
+/// # Example:
+///
+/// ```rust
+/// # if cfg!(feature = "disabled") { return; }
+/// use ring_file::{RingFileBuilder, ShardedRingFile, TimestampPrecision};
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// let path = Path::new("/tmp/ringfile_sharded.store");
+/// let ring = ShardedRingFile::new(4, path, |shard| {
+///     let path = format!("/tmp/ringfile_sharded.{}.store", shard);
+///     RingFileBuilder::new(64 * 1024, Path::new(&path).into())
+///         .timestamps(true)
+///         .timestamp_precision(TimestampPrecision::Nanos)
+/// })
+/// .expect("build ok");
+/// let ring = Arc::new(ring);
+/// let threads: Vec<_> = (0..8)
+///     .map(|t| {
+///         let ring = ring.clone();
+///         std::thread::spawn(move || {
+///             for i in 0..100 {
+///                 ring.write(format!("thread {} message {}\n", t, i));
+///             }
+///         })
+///     })
+///     .collect();
+/// for th in threads {
+///     th.join().unwrap();
+/// }
+/// ring.dump().expect("dump ok");
+/// let content = std::fs::read_to_string(path).unwrap();
+/// let lines: Vec<&str> = content.lines().collect();
+/// assert_eq!(lines.len(), 800);
+/// // Merged by time, and each thread in order
+/// let time = |line: &str| line[1..line.find("ns]").unwrap()].parse::<u64>().unwrap();
+/// assert!(lines.windows(2).all(|w| time(w[0]) <= time(w[1])));
+/// for t in 0..8 {
+///     let prefix = format!("thread {} message ", t);
+///     let seq: Vec<usize> = lines
+///         .iter()
+///         .filter_map(|line| line.split("] ").nth(1).unwrap().strip_prefix(prefix.as_str()))
+///         .map(|i| i.parse().unwrap())
+///         .collect();
+///     assert_eq!(seq, (0..100).collect::<Vec<_>>());
+/// }
+/// ```
+pub struct ShardedRingFile {
+    shards: Vec<RingFile>,
+    file_path: Box<Path>,
+}
+
+impl ShardedRingFile {
+    /// Build `workers` shards with the builder returned by `f` for each shard index,
+    /// dump() writes the merged content to `file_path`.
+    ///
+    /// Each shard is a whole [RingFile] with its own buf_size, options and backend thread.
+    /// Returns error of `ErrorKind::InvalidInput` when `workers` is 0.
+    pub fn new<F>(workers: usize, file_path: &Path, mut f: F) -> std::io::Result<Self>
+    where
+        F: FnMut(usize) -> RingFileBuilder,
+    {
+        if workers == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "workers should be at least 1",
+            ));
+        }
+        let mut shards = Vec::with_capacity(workers);
+        for shard in 0..workers {
+            shards.push(f(shard).build()?);
+        }
+        Ok(Self { shards, file_path: file_path.into() })
+    }
+
+    /// The shard taking the writes of the current thread
+    #[inline]
+    fn current(&self) -> &RingFile {
+        &self.shards[(thread_hash() % self.shards.len() as u64) as usize]
+    }
+
+    /// Write the content to the shard of the current thread, see [RingFile::write()].
+    #[inline]
+    pub fn write(&self, content: String) {
+        self.current().write(content)
+    }
+
+    /// See [RingFile::write_leveled()].
+    #[inline]
+    pub fn write_leveled(&self, level: Level, content: Vec<u8>) {
+        self.current().write_leveled(level, content)
+    }
+
+    /// Wait for every shard to process the messages sent before, see [RingFile::flush()].
+    pub fn flush(&self) {
+        for shard in self.shards.iter() {
+            shard.flush();
+        }
+    }
+
+    /// The shards, e.g. to dump or inspect one of them.
+    #[inline]
+    pub fn shards(&self) -> &[RingFile] {
+        &self.shards
+    }
+
+    /// Dump the content of all the shards to file_path, merged by the timestamp prefix.
+    ///
+    /// A line without timestamp (e.g. a continuation line) stays after the line before it
+    /// in its shard. Without timestamps, the shards are written one after another.
+    /// Only the content of the buffers is written, without the additions of [RingFile::dump()]
+    /// of each shard, such as the banners and the footer.
+    pub fn dump(&self) -> std::io::Result<()> {
+        let mut shards = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.iter() {
+            if let Some(lines) = shard.collect_lines()? {
+                shards.push(stamp_lines(lines));
+            }
+        }
+        let mut file = BufWriter::new(create_file(&self.file_path, DEFAULT_MODE)?);
+        // Next line of each shard
+        let mut cursors = vec![0; shards.len()];
+        loop {
+            let mut next: Option<(usize, Option<SystemTime>)> = None;
+            for (i, pos) in cursors.iter().enumerate() {
+                let Some((time, _)) = shards[i].get(*pos) else {
+                    continue;
+                };
+                if next.map_or(true, |(_, best)| *time < best) {
+                    next = Some((i, *time));
+                }
+            }
+            let Some((i, _)) = next else {
+                break;
+            };
+            file.write_all(&shards[i][cursors[i]].1)?;
+            cursors[i] += 1;
+        }
+        file.flush()
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{Level, Record, Stats};
+use std::io::{Result, Write};
+use std::time::{Duration, SystemTime};
+
+/// The metadata of a record given to [DumpSink::record()], parsed like [Record].
+#[derive(Clone, Copy, Debug)]
+pub struct RecordMeta<'a> {
+    /// Position of the record in the dump, from 0
+    pub index: usize,
+    /// Same as [Record::timestamp()]
+    pub timestamp: Option<SystemTime>,
+    /// Same as [Record::uptime()]
+    pub uptime: Option<Duration>,
+    /// Same as [Record::thread()]
+    pub thread: Option<&'a str>,
+    /// Same as [Record::sequence()]
+    pub sequence: Option<u64>,
+    /// Same as [Record::level()]
+    pub level: Option<Level>,
+    /// Same as [Record::is_partial()]
+    pub partial: bool,
+    /// Same as [Record::raw()], the whole line with the prefixes and the newline
+    pub raw: &'a [u8],
+}
+
+impl<'a> RecordMeta<'a> {
+    #[inline]
+    pub(crate) fn new(index: usize, record: &'a Record<'_>) -> Self {
+        Self {
+            index,
+            timestamp: record.timestamp(),
+            uptime: record.uptime(),
+            thread: record.thread(),
+            sequence: record.sequence(),
+            level: record.level(),
+            partial: record.is_partial(),
+            raw: record.raw(),
+        }
+    }
+}
+
+/// The output format of [RingBuffer::dump_with_sink()](crate::RingBuffer::dump_with_sink()),
+/// e.g. to write JSON lines, CSV or a custom framing.
+///
+/// The methods are called in order: begin() once, record() for every record from the oldest
+/// to the newest, then end() once. An error stops the dump and is returned.
+pub trait DumpSink {
+    /// Called before the first record, with the stats of the buffer.
+    fn begin(&mut self, _stats: &Stats) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called with the body of each record (see [Record::body()]) and its metadata.
+    fn record(&mut self, body: &[u8], meta: &RecordMeta<'_>) -> Result<()>;
+
+    /// Called after the last record.
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The sink writing each record as stored, the same as [RingBuffer::dump_to()](crate::RingBuffer::dump_to()).
+pub struct PlainSink<W: Write>(pub W);
+
+impl<W: Write> DumpSink for PlainSink<W> {
+    #[inline]
+    fn record(&mut self, _body: &[u8], meta: &RecordMeta<'_>) -> Result<()> {
+        self.0.write_all(meta.raw)
+    }
+
+    #[inline]
+    fn end(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+}
@@ -0,0 +1,38 @@
+use std::io::{Error, ErrorKind, Result};
+
+/// Parse a size like `512MiB` into bytes, for [RingFile::new_from_size_str()](crate::RingFile::new_from_size_str()).
+///
+/// Accepts an integer followed by an optional unit, case-insensitive, with optional spaces between:
+/// `B`, the binary `KiB` `MiB` `GiB`, or the decimal `KB` `MB` `GB`.
+/// The result should be in `1..=i32::MAX`.
+pub(crate) fn parse_size(size: &str) -> Result<i32> {
+    let invalid = |reason: &str| {
+        Error::new(ErrorKind::InvalidInput, format!("invalid size {:?}: {}", size, reason))
+    };
+    let s = size.trim();
+    let digits = s.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return Err(invalid("expect a number"));
+    }
+    let unit = s[digits..].trim_start();
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        _ => return Err(invalid("unknown unit, expect B, KiB, MiB, GiB, KB, MB or GB")),
+    };
+    let too_large = || invalid("exceeds the limit of i32::MAX bytes");
+    let n: u64 = s[0..digits].parse().map_err(|_| too_large())?;
+    let bytes = n.checked_mul(multiplier).ok_or_else(too_large)?;
+    if bytes == 0 {
+        return Err(invalid("should not be zero"));
+    }
+    if bytes > i32::MAX as u64 {
+        return Err(too_large());
+    }
+    Ok(bytes as i32)
+}
@@ -0,0 +1,45 @@
+use crate::transport::{DefaultTransport, MsgTransport};
+use crate::RingFile;
+use std::cell::Cell;
+use std::thread::{self, ThreadId};
+
+thread_local! {
+    /// Spans open on this thread, across all the RingFile
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Return the number of spans open on the current thread, see
+/// [RingFileBuilder::indent()](crate::RingFileBuilder::indent()).
+#[inline]
+pub(crate) fn depth() -> usize {
+    DEPTH.with(|depth| depth.get())
+}
+
+/// Guard returned by [RingFile::span()], writes the exit marker on drop.
+pub struct Span<'a, X: MsgTransport = DefaultTransport> {
+    ring: &'a RingFile<X>,
+    /// None for the noop handle
+    name: Option<String>,
+    thread: ThreadId,
+}
+
+impl<'a, X: MsgTransport> Span<'a, X> {
+    pub(crate) fn enter(ring: &'a RingFile<X>, name: &str) -> Self {
+        let thread = thread::current().id();
+        if ring.is_noop() {
+            return Self { ring, name: None, thread };
+        }
+        ring.write(format!("> {} {:?}\n", name, thread));
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        Self { ring, name: Some(name.to_string()), thread }
+    }
+}
+
+impl<X: MsgTransport> Drop for Span<'_, X> {
+    fn drop(&mut self) {
+        if let Some(name) = self.name.take() {
+            DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+            self.ring.write(format!("< {} {:?}\n", name, self.thread));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::buffer::{create_file, DEFAULT_MODE};
+use crate::gzip::{gunzip, GzEncoder};
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the evicted content goes, configured by the builder.
+pub(crate) enum SpillConfig {
+    Plain(Box<Path>),
+    Gzip { prefix: Box<Path>, file_size: usize, max_files: usize },
+}
+
+impl SpillConfig {
+    pub(crate) fn open(&self) -> Result<Spill> {
+        match self {
+            SpillConfig::Plain(path) => {
+                Ok(Spill::Plain(path.clone(), BufWriter::new(create_file(path, DEFAULT_MODE)?)))
+            }
+            SpillConfig::Gzip { prefix, file_size, max_files } => Ok(Spill::Gzip(GzSpill {
+                prefix: prefix.clone(),
+                file_size: *file_size,
+                max_files: *max_files,
+                files: VecDeque::new(),
+                current: None,
+                current_size: 0,
+                seq: 0,
+            })),
+        }
+    }
+}
+
+pub(crate) enum Spill {
+    Plain(Box<Path>, BufWriter<File>),
+    Gzip(GzSpill),
+}
+
+impl Spill {
+    /// Copy the whole history kept in the spill to `out`, oldest first.
+    pub(crate) fn copy_to<W: Write>(&mut self, out: &mut W) -> Result<()> {
+        match self {
+            Spill::Plain(path, file) => {
+                file.flush()?;
+                std::io::copy(&mut File::open(path)?, out)?;
+            }
+            Spill::Gzip(gz) => {
+                // The current file can not be read before the trailer is written
+                gz.finish_current()?;
+                for path in gz.files.iter() {
+                    out.write_all(&gunzip(&std::fs::read(path)?)?)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Spill {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match self {
+            Spill::Plain(_, file) => file.write(buf),
+            Spill::Gzip(gz) => gz.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Spill::Plain(_, file) => file.flush(),
+            Spill::Gzip(gz) => gz.flush(),
+        }
+    }
+}
+
+/// Rotating gzip files named `{prefix}.{seq}.gz`, each holding at most `file_size` bytes
+/// before compression.
+pub(crate) struct GzSpill {
+    prefix: Box<Path>,
+    file_size: usize,
+    max_files: usize,
+    /// Files of this run, oldest first, including the current one
+    files: VecDeque<PathBuf>,
+    current: Option<GzEncoder<BufWriter<File>>>,
+    current_size: usize,
+    seq: u64,
+}
+
+impl GzSpill {
+    fn open_next(&mut self) -> Result<()> {
+        let mut name: OsString = self.prefix.as_os_str().into();
+        name.push(format!(".{}.gz", self.seq));
+        let path = PathBuf::from(name);
+        self.current = Some(GzEncoder::new(BufWriter::new(create_file(&path, DEFAULT_MODE)?))?);
+        self.current_size = 0;
+        self.seq += 1;
+        self.files.push_back(path);
+        if self.max_files > 0 {
+            while self.files.len() > self.max_files {
+                if let Some(oldest) = self.files.pop_front() {
+                    std::fs::remove_file(oldest)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish_current(&mut self) -> Result<()> {
+        if let Some(encoder) = self.current.take() {
+            encoder.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for GzSpill {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.current.is_none() {
+            self.open_next()?;
+        }
+        let mut l = buf.len();
+        if self.file_size > 0 {
+            l = l.min(self.file_size - self.current_size);
+        }
+        let l = self.current.as_mut().unwrap().write(&buf[0..l])?;
+        self.current_size += l;
+        if self.file_size > 0 && self.current_size >= self.file_size {
+            self.finish_current()?;
+        }
+        Ok(l)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self.current.as_mut() {
+            Some(encoder) => encoder.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for GzSpill {
+    fn drop(&mut self) {
+        let _ = self.finish_current();
+    }
+}
@@ -0,0 +1,179 @@
+use crate::buffer::{dump_file, DEFAULT_MODE};
+use crate::PartialDump;
+use std::io::{Result, Write};
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+
+struct Shared {
+    inner: Box<[AtomicU8]>,
+    /// The end of the bytes being written, stored before they overwrite the oldest bytes
+    head: AtomicU64,
+    /// The end of the bytes fully written, the total bytes ever written
+    written: AtomicU64,
+}
+
+/// A ring of bytes owned by one writer thread, which other threads read through [SpscReader]
+/// without locking the writer, e.g. one ring per thread, kept in a thread-local, with the
+/// readers held by the thread dumping on a hang.
+///
+/// The writer does not wait for the readers, so a snapshot may be slightly stale, but is always
+/// a contiguous part of the bytes written, from an offset up to the end of a write:
+/// - The writer stores the end of a write in `head` before overwriting the oldest bytes, and
+///   stores it in `written` with release ordering after the bytes.
+/// - A reader loads `written` with acquire ordering, so the bytes before it are visible, copies
+///   the window before it, then loads `head` after an acquire fence. The bytes overwritten
+///   during the copy are older than `head` minus the capacity, and are cut off the snapshot.
+///
+/// The orderings are exercised by the example below with real threads, but not model checked.
+///
+/// Unlike [RingBuffer](crate::RingBuffer), the writes are never short, a write larger than the
+/// capacity leaves its last bytes. Only the plain byte stream is supported, without no_split or
+/// framed mode.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::SpscRingBuffer;
+/// use std::io::Write;
+/// let mut ring = SpscRingBuffer::new(100);
+/// let reader = ring.reader();
+/// let dumper = std::thread::spawn(move || loop {
+///     let done = reader.total_written() == 7 * 20000;
+///     let content = reader.snapshot();
+///     assert!(content.len() <= reader.capacity());
+///     // Skip the partial first record, the rest are consecutive
+///     if let Some(i) = content.iter().position(|b| *b == b'\n') {
+///         let text = std::str::from_utf8(&content[i + 1..]).unwrap();
+///         let seq: Vec<u32> = text.lines().map(|line| line.parse().unwrap()).collect();
+///         assert!(seq.windows(2).all(|w| w[1] == w[0] + 1), "{:?}", text);
+///         assert!(text.is_empty() || text.ends_with('\n'));
+///     }
+///     if done {
+///         break;
+///     }
+/// });
+/// for i in 0..20000 {
+///     ring.write_all(format!("{:06}\n", i).as_bytes()).expect("write ok");
+/// }
+/// dumper.join().unwrap();
+/// assert_eq!(ring.total_written(), 7 * 20000);
+/// let content = ring.reader().snapshot();
+/// assert_eq!(content.len(), 100);
+/// assert!(content.ends_with(b"\n019998\n019999\n"));
+/// ```
+pub struct SpscRingBuffer {
+    shared: Arc<Shared>,
+    /// The writer's copy of `written`
+    written: u64,
+}
+
+impl SpscRingBuffer {
+    /// Panics when `buf_size` is 0.
+    pub fn new(buf_size: usize) -> Self {
+        assert!(buf_size > 0, "buf_size should not be 0");
+        let shared = Shared {
+            inner: (0..buf_size).map(|_| AtomicU8::new(0)).collect(),
+            head: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+        };
+        Self { shared: Arc::new(shared), written: 0 }
+    }
+
+    /// A handle to read the ring from other threads.
+    #[inline]
+    pub fn reader(&self) -> SpscReader {
+        SpscReader { shared: self.shared.clone() }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.inner.len()
+    }
+
+    /// The total bytes ever written, see
+    /// [RingBuffer::total_written()](crate::RingBuffer::total_written()).
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl Write for SpscRingBuffer {
+    /// The whole `buf` is written, only its last capacity bytes are kept.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let inner = &self.shared.inner;
+        let end = self.written + buf.len() as u64;
+        let kept = &buf[buf.len().saturating_sub(inner.len())..];
+        // Published before any byte is overwritten, see SpscReader::snapshot()
+        self.shared.head.store(end, Ordering::Relaxed);
+        fence(Ordering::Release);
+        let mut pos = ((end - kept.len() as u64) % inner.len() as u64) as usize;
+        for b in kept {
+            inner[pos].store(*b, Ordering::Relaxed);
+            pos += 1;
+            if pos == inner.len() {
+                pos = 0;
+            }
+        }
+        self.shared.written.store(end, Ordering::Release);
+        self.written = end;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads a [SpscRingBuffer] from any thread, without blocking its writer.
+#[derive(Clone)]
+pub struct SpscReader {
+    shared: Arc<Shared>,
+}
+
+impl SpscReader {
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.inner.len()
+    }
+
+    /// The total bytes written up to the last completed write.
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.shared.written.load(Ordering::Acquire)
+    }
+
+    /// Copy the content in order, up to the last completed write. Fewer than the bytes kept
+    /// are returned when the writer overwrote the oldest of them during the copy.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let inner = &self.shared.inner;
+        let cap = inner.len() as u64;
+        let end = self.shared.written.load(Ordering::Acquire);
+        let start = end.saturating_sub(cap);
+        let mut out = Vec::with_capacity((end - start) as usize);
+        let mut pos = (start % cap) as usize;
+        for _ in start..end {
+            out.push(inner[pos].load(Ordering::Relaxed));
+            pos += 1;
+            if pos == inner.len() {
+                pos = 0;
+            }
+        }
+        // Pairs with the fence of the writer: a byte copied from a later write is seen with
+        // at least the head of that write.
+        fence(Ordering::Acquire);
+        let valid = self.shared.head.load(Ordering::Relaxed).saturating_sub(cap).clamp(start, end);
+        out.drain(..(valid - start) as usize);
+        out
+    }
+
+    /// Will create a truncated file and write a snapshot to disk.
+    pub fn dump<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
+        let content = self.snapshot();
+        dump_file(file_path.as_ref(), PartialDump::Keep, DEFAULT_MODE, |file| {
+            file.write_all(&content)
+        })
+    }
+}
@@ -0,0 +1,63 @@
+//! The redirection of the process stderr, see
+//! [RingFile::capture_stderr()](crate::RingFile::capture_stderr()).
+
+use std::fs::File;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set while stderr is redirected, only one ring of the process can capture it
+#[cfg(unix)]
+static CAPTURED: AtomicBool = AtomicBool::new(false);
+
+/// Point fd 2 at a new pipe, return the read end and a duplicate of the previous stderr.
+#[cfg(unix)]
+pub(crate) fn redirect() -> std::io::Result<(File, File)> {
+    use std::os::unix::io::FromRawFd;
+    if CAPTURED.swap(true, Ordering::AcqRel) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "stderr is already captured",
+        ));
+    }
+    let mut fds = [0 as libc::c_int; 2];
+    // SAFETY: the fds are created here and each is owned by one File at the end
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            CAPTURED.store(false, Ordering::Release);
+            return Err(std::io::Error::last_os_error());
+        }
+        let (reader, writer) = (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1]));
+        let saved = libc::dup(libc::STDERR_FILENO);
+        if saved < 0 {
+            CAPTURED.store(false, Ordering::Release);
+            return Err(std::io::Error::last_os_error());
+        }
+        let saved = File::from_raw_fd(saved);
+        if libc::dup2(fds[1], libc::STDERR_FILENO) < 0 {
+            CAPTURED.store(false, Ordering::Release);
+            return Err(std::io::Error::last_os_error());
+        }
+        // fd 2 keeps the pipe open
+        drop(writer);
+        Ok((reader, saved))
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn redirect() -> std::io::Result<(File, File)> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "stderr is only captured on unix"))
+}
+
+/// Point fd 2 back at the stderr saved by [redirect()], so that it can be captured again.
+#[cfg(unix)]
+pub(crate) fn restore(saved: &File) {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: both fds are open
+    unsafe {
+        libc::dup2(saved.as_raw_fd(), libc::STDERR_FILENO);
+    }
+    CAPTURED.store(false, Ordering::Release);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn restore(_saved: &File) {}
@@ -0,0 +1,177 @@
+use crate::timestamp::strip_time_prefixes;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Append the prefix `[@{name}] ` of the current thread, see
+/// [RingFileBuilder::thread_names()](crate::RingFileBuilder::thread_names()).
+///
+/// The name is `ThreadId(N)` for an unnamed thread.
+#[inline]
+pub(crate) fn write_thread_name(buf: &mut Vec<u8>) {
+    let current = thread::current();
+    buf.extend_from_slice(b"[@");
+    match current.name() {
+        Some(name) => buf.extend_from_slice(name.as_bytes()),
+        None => buf.extend_from_slice(format!("{:?}", current.id()).as_bytes()),
+    }
+    buf.extend_from_slice(b"] ");
+}
+
+/// Parse the prefix written by [write_thread_name()], return the name and the length of the prefix.
+pub(crate) fn parse_thread_name(line: &[u8]) -> Option<(&[u8], usize)> {
+    if !line.starts_with(b"[@") {
+        return None;
+    }
+    let close = line.windows(2).position(|w| w == b"] ")?;
+    Some((&line[2..close], close + 2))
+}
+
+/// Skip the thread name prefix and the sequence prefix of a line, return the rest.
+#[inline]
+pub(crate) fn strip_thread_name(line: &[u8]) -> &[u8] {
+    let line = match parse_thread_name(line) {
+        Some((_, l)) => &line[l..],
+        None => line,
+    };
+    match parse_seq(line) {
+        Some((_, l)) => &line[l..],
+        None => line,
+    }
+}
+
+/// Append the prefix `[#{seq}] `, see
+/// [RingFileBuilder::per_thread_seq()](crate::RingFileBuilder::per_thread_seq()).
+#[inline]
+pub(crate) fn write_seq(buf: &mut Vec<u8>, seq: u64) {
+    buf.extend_from_slice(b"[#");
+    buf.extend_from_slice(seq.to_string().as_bytes());
+    buf.extend_from_slice(b"] ");
+}
+
+/// Parse the prefix written by [write_seq()], return the sequence and the length of the prefix.
+pub(crate) fn parse_seq(line: &[u8]) -> Option<(u64, usize)> {
+    if !line.starts_with(b"[#") {
+        return None;
+    }
+    let close = line.iter().position(|b| *b == b']')?;
+    if line.get(close + 1) != Some(&b' ') {
+        return None;
+    }
+    let seq = std::str::from_utf8(&line[2..close]).ok()?.parse().ok()?;
+    Some((seq, close + 2))
+}
+
+/// Split the dump `input` into one file per thread in `out_dir`, by the prefix of
+/// [RingFileBuilder::thread_names()](crate::RingFileBuilder::thread_names()),
+/// e.g. to follow each thread of a merged dump offline. Return the files written in the order
+/// of the first line of each thread.
+///
+/// The lines of a thread go to `{name}.log` in their order, with the prefixes kept as is.
+/// The characters of the name other than ASCII letters, digits, `-`, `_` and `.` become `_`,
+/// e.g. `ThreadId(7)` goes to `ThreadId_7_.log`. The lines without the prefix, including the
+/// continuation lines of a multi-line message, go to `untagged.log`.
+/// `out_dir` is created if missing, and the files existing are overwritten.
+/// Compared to [RingFile::dump_grouped()](crate::RingFile::dump_grouped()), this works on a dump
+/// already written, without the ring.
+///
+/// # Example:
+///
+/// ```rust
+/// # if cfg!(feature = "disabled") { return; }
+/// use ring_file::{split_by_thread, RingFileBuilder};
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// let path = Path::new("/tmp/ringfile_split_thread.store");
+/// let ring = RingFileBuilder::new(1024, path.into())
+///     .timestamps(true)
+///     .thread_names(true)
+///     .build()
+///     .expect("build ok");
+/// let ring = Arc::new(ring);
+/// let workers: Vec<_> = ["worker-a", "worker-b"]
+///     .iter()
+///     .map(|name| {
+///         let ring = ring.clone();
+///         std::thread::Builder::new()
+///             .name(name.to_string())
+///             .spawn(move || {
+///                 for i in 0..3 {
+///                     ring.write(format!("step {}\n", i));
+///                 }
+///             })
+///             .unwrap()
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// ring.dump().expect("dump ok");
+/// let mut dump = std::fs::read_to_string(path).unwrap();
+/// dump.push_str("not tagged\n");
+/// std::fs::write(path, &dump).unwrap();
+/// let out_dir = Path::new("/tmp/ringfile_split_thread");
+/// let files = split_by_thread(path, out_dir).expect("split ok");
+/// assert_eq!(files.len(), 3);
+/// assert!(files.contains(&out_dir.join("worker-a.log")));
+/// assert_eq!(files[2], out_dir.join("untagged.log"));
+/// for name in ["worker-a", "worker-b"].iter() {
+///     let content = std::fs::read_to_string(out_dir.join(format!("{}.log", name))).unwrap();
+///     let steps: Vec<&str> = content.lines().map(|line| line.split("] ").nth(2).unwrap()).collect();
+///     assert_eq!(steps, ["step 0", "step 1", "step 2"]);
+///     assert!(content.lines().all(|line| line.contains(&format!("[@{}] ", name))));
+/// }
+/// assert_eq!(std::fs::read_to_string(&files[2]).unwrap(), "not tagged\n");
+/// // Together, the files hold every line of the dump
+/// let total: usize = files.iter().map(|f| std::fs::read(f).unwrap().len()).sum();
+/// assert_eq!(total, dump.len());
+/// ```
+pub fn split_by_thread(input: &Path, out_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(out_dir)?;
+    let mut reader = BufReader::new(File::open(input)?);
+    // (name, path, writer) of each thread, only a few threads expected
+    let mut outputs: Vec<(Vec<u8>, PathBuf, BufWriter<File>)> = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let name = match parse_thread_name(strip_time_prefixes(&line)) {
+            Some((name, _)) => file_name(name),
+            None => b"untagged".to_vec(),
+        };
+        let i = match outputs.iter().position(|(n, _, _)| *n == name) {
+            Some(i) => i,
+            None => {
+                let mut file_name = String::from_utf8_lossy(&name).into_owned();
+                file_name.push_str(".log");
+                let path = out_dir.join(file_name);
+                let file = crate::buffer::create_file(&path, crate::buffer::DEFAULT_MODE)?;
+                outputs.push((name, path, BufWriter::new(file)));
+                outputs.len() - 1
+            }
+        };
+        outputs[i].2.write_all(&line)?;
+    }
+    let mut files = Vec::with_capacity(outputs.len());
+    for (_, path, mut writer) in outputs {
+        writer.flush()?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// The thread name with the characters not safe in a file name replaced by `_`.
+pub(crate) fn file_name(name: &[u8]) -> Vec<u8> {
+    name.iter()
+        .map(|&b| {
+            if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.' {
+                b
+            } else {
+                b'_'
+            }
+        })
+        .collect()
+}
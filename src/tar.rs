@@ -0,0 +1,52 @@
+//! Minimal tar support for [RingBuffer::dump_into_tar()](crate::RingBuffer::dump_into_tar()),
+//! without extra dependency.
+//!
+//! Only the ustar header of a regular file is written.
+
+use std::io::{Error, ErrorKind, Result, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Write `n` as zero-padded octal into `field`, leaving the last byte as NUL.
+fn put_octal(field: &mut [u8], n: u64) {
+    let digits = field.len() - 1;
+    let s = format!("{:0width$o}", n, width = digits);
+    field[0..digits].copy_from_slice(s.as_bytes());
+}
+
+/// Write the header of a regular file entry of `size` bytes.
+pub(crate) fn write_header<W: Write>(out: &mut W, name: &str, size: u64) -> Result<()> {
+    if name.is_empty() || name.len() > 100 {
+        return Err(Error::new(ErrorKind::InvalidInput, "tar entry name should be 1 to 100 bytes"));
+    }
+    if size >= 1 << 33 {
+        return Err(Error::new(ErrorKind::InvalidInput, "tar entry is larger than 8GiB"));
+    }
+    let mtime = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let mut header = [0u8; BLOCK_SIZE];
+    header[0..name.len()].copy_from_slice(name.as_bytes());
+    put_octal(&mut header[100..108], 0o644);
+    put_octal(&mut header[108..116], 0);
+    put_octal(&mut header[116..124], 0);
+    put_octal(&mut header[124..136], size);
+    put_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is computed with its own field as spaces
+    header[148..156].copy_from_slice(b"        ");
+    let sum: u64 = header.iter().map(|b| *b as u64).sum();
+    put_octal(&mut header[148..155], sum);
+    header[155] = b' ';
+    out.write_all(&header)
+}
+
+/// Pad the content of `size` bytes to the block boundary.
+pub(crate) fn write_padding<W: Write>(out: &mut W, size: u64) -> Result<()> {
+    let rem = (size % BLOCK_SIZE as u64) as usize;
+    if rem > 0 {
+        out.write_all(&[0u8; BLOCK_SIZE][rem..])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use crate::transport::MsgSender;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Unbounded channel to the backend task of
+/// [RingFileBuilder::build_task()](crate::RingFileBuilder::build_task()),
+/// the receiver is polled instead of blocking.
+pub(crate) fn channel<T>() -> (TaskSender<T>, TaskReceiver<T>) {
+    let shared = Arc::new(Mutex::new(State {
+        queue: VecDeque::new(),
+        waker: None,
+        sender_gone: false,
+        receiver_gone: false,
+    }));
+    (TaskSender(shared.clone()), TaskReceiver(shared))
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    /// The task to wake on the next message
+    waker: Option<Waker>,
+    sender_gone: bool,
+    receiver_gone: bool,
+}
+
+pub(crate) struct TaskSender<T>(Arc<Mutex<State<T>>>);
+
+impl<T: Send> MsgSender<T> for TaskSender<T> {
+    /// Never blocks, so it is fine for both the sync and the async callers.
+    fn send(&self, msg: T) -> Result<(), T> {
+        let mut state = self.0.lock().unwrap();
+        if state.receiver_gone {
+            return Err(msg);
+        }
+        state.queue.push_back(msg);
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Drop for TaskSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().unwrap();
+        state.sender_gone = true;
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+pub(crate) struct TaskReceiver<T>(Arc<Mutex<State<T>>>);
+
+impl<T> TaskReceiver<T> {
+    /// Return Ready(None) when the sender is gone and the queue is drained,
+    /// otherwise Pending and wake the task on the next message.
+    pub(crate) fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.0.lock().unwrap();
+        if let Some(msg) = state.queue.pop_front() {
+            return Poll::Ready(Some(msg));
+        }
+        if state.sender_gone {
+            return Poll::Ready(None);
+        }
+        match state.waker.as_ref() {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => state.waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for TaskReceiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().unwrap();
+        state.receiver_gone = true;
+        // The callers waiting for a reply see the backend gone
+        let queue = std::mem::take(&mut state.queue);
+        drop(state);
+        drop(queue);
+    }
+}
@@ -1,102 +1,4154 @@
-use std::io::Write;
-use crate::RingBuffer;
-use std::path::Path;
-use crossbeam_channel::*;
+use crate::ansi::StripAnsi;
+use crate::backlog::Backlog;
+use crate::buffer::{create_file, dump_file, dump_indexed, Counter, SplitWriter, DEFAULT_MODE};
+use crate::builder::{Options, RingFileBuilder};
+use crate::category::{category_name, Categories};
+use crate::config::RingFileConfig;
+use crate::crash::{self, BackendGuard};
+use crate::frame::max_payload;
+use crate::latency::{Latency, LatencyWindows};
+use crate::loss::Drops;
+use crate::manifest::Manifest;
+use crate::marker::end_line;
+use crate::rate::Rate;
+use crate::record::format_mark;
+use crate::sample::Sampler;
+use crate::size::parse_size;
+use crate::spill::Spill;
+use crate::stderr;
+use crate::tag::*;
+use crate::task::{self, TaskReceiver, TaskSender};
+use crate::timestamp::*;
+use crate::transport::*;
+use crate::writers::Writers;
+use crate::{DropReason, Level, PartialDump, RingBuffer, Span, Stats};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::task::{Context, Poll};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
+/// The requests to the backend.
+///
+/// A request expecting a result carries the sender of a channel of capacity 1 created for
+/// that call (see RingFile::call()), the backend answers it exactly once with [reply()],
+/// which consumes the sender. So there is no channel shared between the calls to fill up,
+/// and no result is sent without a caller waiting for it, or gone (e.g. a dropped
+/// [PendingDump]) in which case the result is dropped.
 enum Msg {
     Clear,
-    Dump,
-    Msg(String),
+    Exit,
+    Dump(SyncSender<std::io::Result<()>>),
+    /// A dump subject to RingFileBuilder::max_dumps_per()
+    TriggerDump(SyncSender<std::io::Result<bool>>),
+    SyncDump(SyncSender<std::io::Result<()>>),
+    DumpAndClear(SyncSender<std::io::Result<()>>),
+    DumpIncremental(Box<Path>, SyncSender<std::io::Result<()>>),
+    DumpSize(SyncSender<usize>),
+    DumpSplit(Box<Path>, usize, SyncSender<std::io::Result<Vec<PathBuf>>>),
+    DumpByCategory(Box<Path>, SyncSender<std::io::Result<Vec<PathBuf>>>),
+    DumpTcp(TcpStream, SyncSender<std::io::Result<()>>),
+    Flush(SyncSender<()>),
+    LineCount(SyncSender<usize>),
+    Reconstruct(Box<Path>, SyncSender<std::io::Result<()>>),
+    /// Run a closure with the buffer on the backend
+    Inspect(Box<dyn FnOnce(&RingBuffer) + Send>),
+    /// A message of RingFileBuilder::strict_order(), with its global sequence
+    Ordered(u64, Vec<u8>),
+    Resize(i32, SyncSender<std::io::Result<()>>),
+    Boost(i32, Duration, SyncSender<std::io::Result<()>>),
+    LoadSnapshot(Vec<u8>, SyncSender<std::io::Result<()>>),
+    SetFooter(Vec<u8>),
+    SetMaxLines(usize),
+    TakeBuffer(SyncSender<std::io::Result<RingBuffer>>),
+    Snapshot(SyncSender<std::io::Result<(RingBuffer, Stats)>>),
+    WatchSignals(Vec<i32>, SyncSender<std::io::Result<()>>),
+    Write(Vec<u8>),
+    /// A line of RingFile::write_category(), the category made safe for a file name
+    WriteCategory(String, Vec<u8>),
 }
 
 /// RingFile use a backend thread to maintain RingBuffer, which receive messages with unbounded channel,
 /// to prevent lock contention affecting program execution.
 /// When program hang or panic, you can call dump() to collect the logs into file.
-pub struct RingFile {
-    tx: Sender<Msg>,
-    res: Receiver<std::io::Result<()>>,
-    _th: thread::JoinHandle<()>,
+///
+/// `&RingFile` implements [std::io::Write], so it can be used with `write!()` and `io::copy()`
+/// from any thread sharing the handle.
+///
+/// After [RingFile::shutdown()], write() and clear() become no-op, while dump() and the
+/// [std::io::Write] impl return error of `ErrorKind::BrokenPipe`.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::RingFile;
+/// use std::io::Write;
+/// use std::path::Path;
+/// let ring = RingFile::new(1024, Path::new("/tmp/ringfile_write.store").into());
+/// write!(&ring, "hello {}\n", 1).expect("write ok");
+/// std::io::copy(&mut &b"world\n"[..], &mut &ring).expect("copy ok");
+/// ring.dump().expect("dump ok");
+/// assert_eq!(std::fs::read("/tmp/ringfile_write.store").unwrap(), b"hello 1\nworld\n");
+/// ```
+pub struct RingFile<X: MsgTransport = DefaultTransport> {
+    /// None for the noop handle
+    tx: Option<Dispatch<X>>,
+    shutdown: AtomicBool,
+    /// Writes are dropped when false
+    armed: AtomicBool,
+    /// Cleared when the backend is dropped, None for the noop handle
+    alive: Option<Arc<AtomicBool>>,
+    /// The panic hook of arm_crash_capture() is installed
+    crash_hook: AtomicBool,
+    th: Mutex<Option<thread::JoinHandle<()>>>,
+    sampler: Option<Arc<Sampler>>,
+    /// None without the channel
+    backlog: Option<Arc<Backlog>>,
+    /// None for the noop handle
+    drops: Option<Arc<Drops>>,
+    /// None without a time prefix
+    latency: Option<Arc<Latency>>,
+    /// Of RingFileBuilder::tcp_timeout()
+    tcp_timeout: Duration,
+    /// The next global sequence of RingFileBuilder::strict_order()
+    order: Option<Arc<AtomicU64>>,
+    /// The dumps skipped by RingFileBuilder::max_dumps_per(), None for the noop handle
+    dumps_throttled: Option<Arc<AtomicU64>>,
+    /// None for the noop handle
+    writers: Option<Writers>,
+    /// None for the noop handle
+    rate: Option<Rate>,
+    /// None for no wall-clock prefix
+    timestamps: Option<TimestampPrecision>,
+    /// Captured on build() for the uptime prefix
+    start: Option<Instant>,
+    /// Stamp the uptime in binary instead of the text prefix
+    binary_uptime: bool,
+    thread_names: bool,
+    per_thread_seq: bool,
+    indent: bool,
+    utf8: Utf8Mode,
+    utf8_rejected: AtomicU64,
+    /// Bits of the levels to capture backtrace
+    #[cfg(feature = "backtrace")]
+    backtrace_levels: u8,
+}
+
+/// Callback of [RingFileBuilder::on_message()]
+pub type OnMessage = Box<dyn FnMut(&[u8]) + Send>;
+
+/// Callback of [RingFileBuilder::warn_write_threshold()], with the size of the message, the
+/// first bytes of it, and the number of oversized messages not reported since the last call.
+pub type OnLargeWrite = Box<dyn FnMut(usize, &[u8], u64) + Send>;
+
+/// Callback of [RingFileBuilder::path_fn()]
+pub type PathFn = Box<dyn Fn() -> PathBuf + Send>;
+
+/// Callback of [RingFileBuilder::on_frozen()]
+pub type OnFrozen = Box<dyn FnOnce() + Send>;
+
+/// Region of [RingFileBuilder::mirror()]
+pub type MirrorRegion = Arc<Mutex<Box<[u8]>>>;
+
+/// How the messages are checked to be valid UTF-8, see [RingFileBuilder::utf8()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Utf8Mode {
+    /// Store the bytes as is
+    Off,
+    /// Substitute the invalid sequences with U+FFFD on the backend
+    Replace,
+    /// Drop the invalid messages on the caller's thread
+    Reject,
+}
+
+enum Dispatch<X: MsgTransport> {
+    Channel(X::Sender<Msg>),
+    Task(TaskSender<Msg>),
+    Inline(Box<Mutex<RingFileBackend>>),
 }
 
 struct RingFileBackend {
     file_path: Box<Path>,
+    /// Compute the target of each dump instead of file_path
+    path_fn: Option<PathFn>,
     buffer: RingBuffer,
-    rx: Receiver<Msg>,
-    res: Sender<std::io::Result<()>>,
+    /// The line `[ring-file: run {id}]` then the lines of the labels at the start of every dump
+    run_header: Option<String>,
+    /// `run={id} labels={labels}` of the header of RingFile::dump_to_tcp()
+    stream_tags: String,
+    sticky_banner: Option<String>,
+    sticky_footer: Option<Vec<u8>>,
+    /// Logical offset where the last incremental dump ends
+    last_dumped: u64,
+    /// Pad each message to a multiple of align with FILLER, 0 for disabled
+    align: usize,
+    /// Start of the stamps of RingFileBuilder::binary_uptime(), None for disabled
+    binary_start: Option<Instant>,
+    /// Start of the uptime prefix or stamp, to measure the latency
+    uptime_start: Option<Instant>,
+    /// None without a time prefix
+    latency: Option<LatencyWindows>,
+    utf8_replace: bool,
+    /// Cut each message to this many bytes plus a marker, 0 for disabled
+    max_line_len: usize,
+    skip_empty: bool,
+    strip_ansi: bool,
+    histogram: bool,
+    end_marker: bool,
+    dedup: Option<Dedup>,
+    /// Drop the messages once total_written reaches it, 0 for disabled
+    max_total_bytes: u64,
+    frozen: bool,
+    on_frozen: Option<OnFrozen>,
+    reuse_dump_file: bool,
+    /// The dump file kept open when reuse_dump_file, with the path it was created at
+    dump_handle: Option<(PathBuf, File)>,
+    /// Index of the dumps, see RingFileBuilder::manifest()
+    manifest: Option<Manifest>,
+    categories: Categories,
+    on_message: Option<OnMessage>,
+    large_writes: Option<LargeWrites>,
+    flush_every: usize,
+    processed: usize,
+    spill: Option<Spill>,
+    live: Option<LiveFile>,
+    /// Flush the live file at most once per interval, None for after every batch
+    live_flush_interval: Option<Duration>,
+    live_flush_deadline: Option<Instant>,
+    /// The region of RingFileBuilder::mirror() and the interval
+    mirror: Option<(MirrorRegion, Duration)>,
+    mirror_deadline: Option<Instant>,
+    dump_on_idle: Option<Duration>,
+    idle_armed: bool,
+    idle_deadline: Option<Instant>,
+    /// Run by the thread loop, which fires the timed triggers
+    timers: bool,
+    /// The signals watched, with the count of each seen so far
+    signals: Vec<(i32, u64)>,
+    signal_deadline: Option<Instant>,
+    /// The buf_size to shrink back to at the deadline, see RingFile::boost()
+    boost: Option<(i32, Instant)>,
+    /// None unless RingFileBuilder::generations()
+    generations: Option<Generations>,
+    reorder: Option<Reorder>,
+    dump_limit: Option<DumpLimit>,
+    /// Logical offset of each message kept, in line mode
+    lines: Option<VecDeque<u64>>,
+    max_lines: usize,
+    sampler: Option<Arc<Sampler>>,
+    backlog: Option<Arc<Backlog>>,
+    drops: Arc<Drops>,
+    _alive: Alive,
+}
+
+/// The last message stored and the count of its repeats since, see [RingFileBuilder::dedup()].
+#[derive(Default)]
+struct Dedup {
+    /// Without the time, thread name and sequence prefixes
+    last: Option<Vec<u8>>,
+    repeated: usize,
+}
+
+/// The rings sealed when full, see [RingFileBuilder::generations()].
+struct Generations {
+    max: usize,
+    /// The content of each, as dumped, the oldest first
+    sealed: VecDeque<Vec<u8>>,
+}
+
+/// The messages of [RingFileBuilder::strict_order()] arrived ahead of their turn.
+struct Reorder {
+    window: usize,
+    timeout: Duration,
+    /// The sequence to store next
+    next: u64,
+    pending: BTreeMap<u64, Vec<u8>>,
+    /// When to skip the gap before the pending messages
+    deadline: Option<Instant>,
+}
+
+impl Reorder {
+    /// Take the message to store next, skipping the gap when `skip` or the window is full.
+    fn pop(&mut self, skip: bool) -> Option<Vec<u8>> {
+        let (&first, _) = self.pending.first_key_value()?;
+        if first != self.next && !skip && self.pending.len() <= self.window {
+            return None;
+        }
+        self.next = first + 1;
+        self.pending.remove(&first)
+    }
+}
+
+/// The dumps of the triggers in the last interval of [RingFileBuilder::max_dumps_per()].
+struct DumpLimit {
+    max: usize,
+    interval: Duration,
+    /// When the recent dumps were done, the oldest first
+    recent: VecDeque<Instant>,
+    throttled: Arc<AtomicU64>,
+}
+
+impl DumpLimit {
+    fn admit(&mut self, now: Instant) -> bool {
+        while self.recent.front().is_some_and(|t| now.duration_since(*t) >= self.interval) {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= self.max {
+            self.throttled.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        self.recent.push_back(now);
+        true
+    }
+}
+
+/// The state of [RingFileBuilder::warn_write_threshold()], on the backend.
+struct LargeWrites {
+    threshold: usize,
+    f: OnLargeWrite,
+    /// When `f` was last called
+    last: Option<Instant>,
+    /// Oversized messages since the last call
+    suppressed: u64,
+}
+
+impl LargeWrites {
+    #[cold]
+    fn warn(&mut self, message: &[u8]) {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now.duration_since(last) < LARGE_WRITE_INTERVAL) {
+            self.suppressed += 1;
+            return;
+        }
+        self.last = Some(now);
+        let preview = &message[..message.len().min(LARGE_WRITE_PREVIEW)];
+        (self.f)(message.len(), preview, std::mem::take(&mut self.suppressed));
+    }
+}
+
+/// Clear the flag of [RingFile::backend_alive()] when the backend is dropped,
+/// including on panic unwinding.
+struct Alive(Arc<AtomicBool>);
+
+impl Drop for Alive {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
 }
 
 impl RingFileBackend {
+    #[inline]
+    fn dump(&mut self) -> std::io::Result<()> {
+        self.write_dump(false)
+    }
 
-    #[inline(always)]
-    fn process(&mut self, msg: Msg) {
-        match msg {
-            Msg::Clear=>{
-                self.buffer.clear();
+    /// Dump for a trigger, unless over [RingFileBuilder::max_dumps_per()], return whether dumped.
+    fn auto_dump(&mut self) -> std::io::Result<bool> {
+        if let Some(limit) = self.dump_limit.as_mut() {
+            if !limit.admit(Instant::now()) {
+                return Ok(false);
             }
-            Msg::Dump=>{
-                let res = self.buffer.dump(self.file_path.as_ref());
-                self.res.send(res).expect("send res");
+        }
+        self.dump().map(|_| true)
+    }
+
+    /// Dump to file_path, and fsync the dump and the live file when `sync`.
+    fn write_dump(&mut self, sync: bool) -> std::io::Result<()> {
+        self.flush_repeated();
+        if let Some(spill) = self.spill.as_mut() {
+            spill.flush()?;
+        }
+        self.flush_live();
+        if sync {
+            if let Some(live) = self.live.as_ref() {
+                live.file.get_ref().sync_all()?;
             }
-            Msg::Msg(line)=>{
-                let _ = self.buffer.write_all(line.as_bytes());
+        }
+        if self.skip_empty && self.buffer.is_empty() {
+            return Ok(());
+        }
+        let (policy, mode) = (self.buffer.partial_dump(), self.buffer.dump_mode());
+        let path = self.dump_path()?.into_owned();
+        if self.reuse_dump_file && policy == PartialDump::Keep {
+            self.dump_reused(&path, mode, sync)?;
+        } else {
+            dump_file(&path, policy, mode, |file| {
+                self.write_content(file)?;
+                if sync {
+                    file.get_mut().sync_all()?;
+                }
+                Ok(())
+            })?;
+        }
+        let wraps = self.buffer.stats().wraps;
+        match self.manifest.as_mut() {
+            Some(manifest) => manifest.record(&path, wraps),
+            None => Ok(()),
+        }
+    }
+
+    /// Rewrite the dump through the file kept open, see [RingFileBuilder::reuse_dump_file()].
+    fn dump_reused(&mut self, path: &Path, mode: u32, sync: bool) -> std::io::Result<()> {
+        let mut file = match self.dump_handle.take() {
+            Some((kept, file)) if kept == path && same_file(path, &file) => file,
+            _ => create_file(path, mode)?,
+        };
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut writer = Counter::new(&mut file);
+        let res = self.write_content(&mut writer).map_err(|e| writer.failed(e));
+        if res.is_ok() && sync {
+            file.sync_all()?;
+        }
+        self.dump_handle = Some((path.to_path_buf(), file));
+        res
+    }
+
+    /// Send what dump() writes to `stream` after the header line, see [RingFile::dump_to_tcp()].
+    fn dump_tcp(&mut self, stream: TcpStream) -> std::io::Result<()> {
+        self.flush_repeated();
+        let peer = stream.peer_addr()?;
+        let context = |e: std::io::Error| {
+            std::io::Error::new(e.kind(), format!("send the dump to {}: {}", peer, e))
+        };
+        let mut out = BufWriter::new(stream);
+        writeln!(out, "{} {} {}", TCP_MAGIC, self.dump_size(), self.stream_tags)
+            .map_err(context)?;
+        self.write_content(&mut out).map_err(context)?;
+        let stream = out.into_inner().map_err(|e| context(e.into_error()))?;
+        stream.shutdown(Shutdown::Write).map_err(context)
+    }
+
+    /// Write what dump() writes: the run id, the sticky banner, the buffer, the drop trailer, the
+    /// footer and the end marker.
+    fn write_content<W: Write>(&self, file: &mut W) -> std::io::Result<()> {
+        let mut file = Counter::new(file);
+        if let Some(header) = self.run_header.as_ref() {
+            file.write_all(header.as_bytes())?;
+        }
+        if let Some(banner) = self.sticky_banner.as_ref() {
+            file.write_all(banner.as_bytes())?;
+        }
+        if let Some(generations) = self.generations.as_ref() {
+            for content in generations.sealed.iter() {
+                file.write_all(content)?;
             }
         }
+        self.dump_buffer(&mut file)?;
+        if let Some(trailer) = self.drops.trailer() {
+            file.write_all(trailer.as_bytes())?;
+        }
+        if let Some(histogram) = self.histogram() {
+            file.write_all(histogram.as_bytes())?;
+        }
+        if let Some(footer) = self.sticky_footer.as_ref() {
+            file.write_all(footer)?;
+        }
+        if self.end_marker {
+            let len = file.written();
+            file.write_all(end_line(len).as_bytes())?;
+        }
+        Ok(())
     }
 
-    fn run(&mut self) {
-        loop {
-            match self.rx.recv() {
-                Ok(msg)=>{
-                    self.process(msg);
-                    while let Ok(msg) = self.rx.try_recv() {
-                        self.process(msg);
+    /// The line appended to the dump with the lengths of the lines bucketed by powers of two,
+    /// None when disabled or empty.
+    fn histogram(&self) -> Option<String> {
+        if !self.histogram {
+            return None;
+        }
+        let mut buckets = [0usize; usize::BITS as usize + 1];
+        for line in self.buffer.lines() {
+            let l = trim_filler(&line).len();
+            if l > 0 {
+                buckets[l.next_power_of_two().trailing_zeros() as usize] += 1;
+            }
+        }
+        let counts: Vec<String> = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| **n > 0)
+            .map(|(i, n)| format!("<={}: {}", 1usize << i, n))
+            .collect();
+        if counts.is_empty() {
+            return None;
+        }
+        Some(format!("[ring-file: line lengths {}]\n", counts.join(", ")))
+    }
+
+    fn dump_split(&mut self, prefix: &Path, part_size: usize) -> std::io::Result<Vec<PathBuf>> {
+        self.flush_repeated();
+        if let Some(spill) = self.spill.as_mut() {
+            spill.flush()?;
+        }
+        self.flush_live();
+        let mut parts = SplitWriter::new(prefix, part_size, self.buffer.dump_mode())?;
+        self.write_content(&mut parts)?;
+        parts.finish()
+    }
+
+    /// The target of dump(), validated when computed by path_fn.
+    fn dump_path(&self) -> std::io::Result<Cow<'_, Path>> {
+        let Some(f) = self.path_fn.as_ref() else {
+            return Ok(Cow::Borrowed(self.file_path.as_ref()));
+        };
+        let path = f();
+        if path.as_os_str().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "path_fn returned an empty path",
+            ));
+        }
+        if path.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("path_fn returned a directory: {}", path.display()),
+            ));
+        }
+        Ok(Cow::Owned(path))
+    }
+
+    fn dump_size(&self) -> usize {
+        let size = self.run_header.as_ref().map(|header| header.len()).unwrap_or(0)
+            + self.sticky_banner.as_ref().map(|banner| banner.len()).unwrap_or(0)
+            + self.generations.as_ref().map_or(0, |g| g.sealed.iter().map(Vec::len).sum())
+            + self.buffer_dump_size()
+            + self.drops.trailer().map(|trailer| trailer.len()).unwrap_or(0)
+            + self.histogram().map(|histogram| histogram.len()).unwrap_or(0)
+            + self.sticky_footer.as_ref().map(|footer| footer.len()).unwrap_or(0);
+        if self.end_marker {
+            size + end_line(size).len()
+        } else {
+            size
+        }
+    }
+
+    /// Wrap `file` to remove the filler of align, and the ANSI escapes when strip_ansi.
+    fn clean_writer<'a>(&self, file: &'a mut dyn Write) -> Box<dyn Write + 'a> {
+        let file: Box<dyn Write + 'a> =
+            if self.align > 1 { Box::new(SkipFiller(file)) } else { Box::new(file) };
+        if self.strip_ansi {
+            Box::new(StripAnsi::new(file))
+        } else {
+            file
+        }
+    }
+
+    /// Write the content of the buffer, cleaned by [RingFileBackend::clean_writer()].
+    fn dump_buffer<W: Write>(&self, file: &mut W) -> std::io::Result<()> {
+        let mut file = self.clean_writer(file);
+        self.write_since(&mut file, 0)
+    }
+
+    /// Write the content after logical `offset`, without the prefixes of framed storage.
+    fn write_since(&self, out: &mut dyn Write, offset: u64) -> std::io::Result<()> {
+        if self.buffer.framed() {
+            for record in self.buffer.lines_since(offset) {
+                out.write_all(&record)?;
+            }
+            return Ok(());
+        }
+        let (head, tail) = self.buffer.segments_since(offset);
+        out.write_all(head)?;
+        out.write_all(tail)
+    }
+
+    fn buffer_dump_size(&self) -> usize {
+        if self.align > 1 || self.strip_ansi || self.buffer.framed() {
+            let mut counter = Counter::new(std::io::sink());
+            let _ = self.dump_buffer(&mut counter);
+            counter.written()
+        } else {
+            self.buffer.dump_size()
+        }
+    }
+
+    fn reconstruct(&mut self, output: &Path) -> std::io::Result<()> {
+        let mut file = create_file(output, self.buffer.dump_mode())?;
+        let mut out = self.clean_writer(&mut file);
+        if let Some(spill) = self.spill.as_mut() {
+            spill.copy_to(&mut out)?;
+        }
+        self.write_since(&mut out, 0)
+    }
+
+    fn dump_incremental(&mut self, file_path: &Path) -> std::io::Result<()> {
+        self.flush_repeated();
+        let mut file = create_file(file_path, self.buffer.dump_mode())?;
+        let earliest = self.buffer.earliest_retained_offset();
+        if earliest > self.last_dumped {
+            let note = format!(
+                "[ring-file: {} bytes lost since last dump]\n",
+                earliest - self.last_dumped
+            );
+            file.write_all(note.as_bytes())?;
+        }
+        let mut out = self.clean_writer(&mut file);
+        self.write_since(&mut out, self.last_dumped)?;
+        drop(out);
+        self.last_dumped = self.buffer.total_written();
+        Ok(())
+    }
+
+    /// Swap in an empty buffer of the same settings, return the old one.
+    fn take_buffer(&mut self) -> std::io::Result<RingBuffer> {
+        self.flush_repeated();
+        let mut buffer = RingBuffer::alloc(self.buffer.capacity() as i32)?;
+        buffer.set_no_split(self.buffer.no_split());
+        buffer.set_partial_dump(self.buffer.partial_dump());
+        buffer.set_dump_mode(self.buffer.dump_mode());
+        buffer.set_framed(self.buffer.framed());
+        buffer.set_binary_uptime(self.buffer.binary_uptime());
+        if let Some(lines) = self.lines.as_mut() {
+            lines.clear();
+        }
+        self.last_dumped = 0;
+        Ok(std::mem::replace(&mut self.buffer, buffer))
+    }
+
+    /// Replace the buffer by a new one holding `snapshot`, see [RingFile::load_snapshot()].
+    fn load_snapshot(&mut self, snapshot: &[u8]) -> std::io::Result<()> {
+        if self.buffer.binary_uptime() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "load_snapshot can not be combined with binary_uptime",
+            ));
+        }
+        // The repeats are of the content replaced
+        if let Some(dedup) = self.dedup.as_mut() {
+            *dedup = Dedup::default();
+        }
+        self.take_buffer()?;
+        for line in snapshot.split_inclusive(|b| *b == b'\n') {
+            if let Some(lines) = self.lines.as_mut() {
+                lines.push_back(self.buffer.total_written());
+            }
+            self.buffer.write_all(line)?;
+        }
+        self.trim_lines();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        if let Some(generations) = self.generations.as_mut() {
+            generations.sealed.clear();
+        }
+        // The content cleared is not a gap for the incremental dump
+        self.last_dumped = self.buffer.total_written();
+        if let Some(lines) = self.lines.as_mut() {
+            lines.clear();
+        }
+        if let Some(dedup) = self.dedup.as_mut() {
+            *dedup = Dedup::default();
+        }
+    }
+
+    /// Forget the lines overwritten, and enforce max_lines.
+    fn trim_lines(&mut self) {
+        let Some(lines) = self.lines.as_mut() else {
+            return;
+        };
+        let earliest = self.buffer.earliest_retained_offset();
+        // The first line partially overwritten is still counted
+        while lines.len() > 1 && lines[1] <= earliest {
+            lines.pop_front();
+        }
+        if self.max_lines > 0 && lines.len() > self.max_lines {
+            while lines.len() > self.max_lines {
+                lines.pop_front();
+            }
+            self.buffer.discard_before(lines[0]);
+        }
+    }
+
+    /// Return false when the backend should exit.
+    #[inline(always)]
+    fn process(&mut self, msg: Msg) -> bool {
+        if !matches!(msg, Msg::Ordered(..) | Msg::Write(..))
+            && self.reorder.as_ref().is_some_and(|reorder| !reorder.pending.is_empty())
+        {
+            self.store_ordered(true);
+        }
+        match msg {
+            Msg::Clear => {
+                self.clear();
+            }
+            Msg::Exit => {
+                if let Some(spill) = self.spill.as_mut() {
+                    let _ = spill.flush();
+                }
+                self.flush_live();
+                self.mirror_now();
+                if let Some((_, file)) = self.dump_handle.take() {
+                    let _ = file.sync_all();
+                }
+                return false;
+            }
+            Msg::Dump(res) => {
+                reply(res, self.dump());
+            }
+            Msg::TriggerDump(res) => {
+                reply(res, self.auto_dump());
+            }
+            Msg::SyncDump(res) => {
+                reply(res, self.write_dump(true));
+            }
+            Msg::DumpAndClear(res) => {
+                let r = self.dump();
+                if r.is_ok() {
+                    self.clear();
+                }
+                reply(res, r);
+            }
+            Msg::DumpIncremental(file_path, res) => {
+                reply(res, self.dump_incremental(&file_path));
+            }
+            Msg::DumpSplit(prefix, part_size, res) => {
+                reply(res, self.dump_split(&prefix, part_size));
+            }
+            Msg::DumpTcp(stream, res) => {
+                reply(res, self.dump_tcp(stream));
+            }
+            Msg::DumpByCategory(dir, res) => {
+                reply(res, self.categories.dump(&dir, self.buffer.dump_mode()));
+            }
+            Msg::DumpSize(res) => {
+                self.flush_repeated();
+                reply(res, self.dump_size());
+            }
+            Msg::LineCount(res) => {
+                reply(res, self.lines.as_ref().map(|lines| lines.len()).unwrap_or(0));
+            }
+            Msg::Reconstruct(output, res) => {
+                reply(res, self.reconstruct(&output));
+            }
+            Msg::Inspect(f) => {
+                f(&self.buffer);
+            }
+            Msg::Ordered(seq, line) => {
+                let Some(reorder) = self.reorder.as_mut() else {
+                    return self.process(Msg::Write(line));
+                };
+                if seq < reorder.next {
+                    // Its gap was skipped already
+                    return self.process(Msg::Write(line));
+                }
+                reorder.pending.insert(seq, line);
+                self.store_ordered(false);
+            }
+            Msg::Resize(buf_size, res) => {
+                self.boost = None;
+                reply(res, self.resize(buf_size));
+            }
+            Msg::Boost(buf_size, duration, res) => {
+                reply(res, self.boost(buf_size, duration));
+            }
+            Msg::SetFooter(footer) => {
+                self.sticky_footer = Some(footer);
+            }
+            Msg::TakeBuffer(res) => {
+                reply(res, self.take_buffer());
+            }
+            Msg::LoadSnapshot(snapshot, res) => {
+                reply(res, self.load_snapshot(&snapshot));
+            }
+            Msg::Snapshot(res) => {
+                let stats = self.buffer.stats();
+                reply(res, self.take_buffer().map(|buffer| (buffer, stats)));
+            }
+            Msg::SetMaxLines(n) => {
+                self.max_lines = n;
+                self.trim_lines();
+            }
+            Msg::Flush(res) => {
+                self.flush_live();
+                reply(res, ());
+            }
+            Msg::WatchSignals(signals, res) => {
+                reply(res, self.watch_signals(signals));
+            }
+            Msg::Write(mut line) => {
+                if let Some(latency) = self.latency.as_mut() {
+                    let binary = self.binary_start.is_some();
+                    if let Some(age) = line_age(&line, self.uptime_start, binary) {
+                        latency.record(age);
                     }
                 }
-                Err(_)=>{
-                    return;
+                if let Some(backlog) = self.backlog.as_ref() {
+                    backlog.done(line.len());
+                }
+                // The binary stamp is left out of the processing of the text
+                let stamp = if self.binary_start.is_some() { STAMP_LEN } else { 0 };
+                if self.utf8_replace {
+                    if let std::borrow::Cow::Owned(s) = String::from_utf8_lossy(&line[stamp..]) {
+                        line.truncate(stamp);
+                        line.extend_from_slice(s.as_bytes());
+                    }
+                }
+                if let Some(large) = self.large_writes.as_mut() {
+                    if line.len() - stamp > large.threshold {
+                        large.warn(&line[stamp..]);
+                    }
+                }
+                if self.max_line_len > 0 {
+                    let cut = cut_line(&mut line, stamp, stamp + self.max_line_len);
+                    if cut > 0 {
+                        self.drops.record(DropReason::LineTooLong, cut);
+                    }
+                }
+                if let Some(f) = self.on_message.as_mut() {
+                    f(&line[stamp..]);
+                }
+                if let Some(sampler) = self.sampler.as_ref() {
+                    sampler.done();
+                }
+                if self.frozen {
+                    self.drops.record(DropReason::Frozen, line.len());
+                    return true;
+                }
+                self.idle_armed = true;
+                if let Some(dedup) = self.dedup.as_mut() {
+                    let body = strip_thread_name(strip_time_prefixes(&line[stamp..]));
+                    if dedup.last.as_deref() == Some(body) {
+                        dedup.repeated += 1;
+                        return true;
+                    }
+                    dedup.last = Some(body.to_vec());
+                    self.flush_repeated();
+                }
+                self.store(line);
+                if self.max_total_bytes > 0 && self.buffer.total_written() >= self.max_total_bytes {
+                    self.frozen = true;
+                    if let Some(f) = self.on_frozen.take() {
+                        f();
+                    }
+                }
+                if self.flush_every > 0 {
+                    self.processed += 1;
+                    if self.processed >= self.flush_every {
+                        self.processed = 0;
+                        let _ = self.auto_dump();
+                    }
+                }
+            }
+            Msg::WriteCategory(category, line) => {
+                if let Some(backlog) = self.backlog.as_ref() {
+                    backlog.done(line.len());
+                }
+                if let Some(sampler) = self.sampler.as_ref() {
+                    sampler.done();
+                }
+                if self.frozen {
+                    self.drops.record(DropReason::Frozen, line.len());
+                    return true;
+                }
+                if self.binary_start.is_some() {
+                    self.categories.write(&category, &render_stamp(&line));
+                } else {
+                    self.categories.write(&category, &line);
                 }
             }
         }
+        true
     }
-}
 
-impl RingFile {
-    /// # Arguments:
-    ///
-    /// - buf_size: total buffer size
-    ///
-    /// - file_path: The target file to dump
-    pub fn new(buf_size: i32, file_path: Box<Path>) -> Self {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        let (res_tx, res_rx) = crossbeam_channel::bounded(1);
-        let mut backend = RingFileBackend {
-            file_path,
-            buffer: RingBuffer::new(buf_size),
-            rx,
-            res: res_tx,
+    /// Append a message to the buffer, and to the live and spill files.
+    fn store(&mut self, mut line: Vec<u8>) {
+        if let Some(live) = self.live.as_mut() {
+            // Errors on live file can not be reported, the ring is still intact.
+            let _ = match self.binary_start {
+                Some(_) => live.append(&render_stamp(&line)),
+                None => live.append(&line),
+            };
+        }
+        if self.align > 1 {
+            pad_to_align(&mut line, self.align);
+        }
+        if self.buffer.framed() {
+            let kept = max_payload(self.buffer.capacity());
+            if line.len() > kept {
+                self.drops.record(DropReason::Truncated, line.len() - kept);
+            }
+        }
+        if self.generations.is_some() {
+            let (head, tail) = self.buffer.evicted_by(line.len());
+            if !head.is_empty() || !tail.is_empty() {
+                self.seal_generation();
+            }
+        }
+        if let Some(spill) = self.spill.as_mut() {
+            // Errors on spill file can not be reported, the ring is still intact.
+            let _ = spill_evicted(spill, &self.buffer, &line);
+        }
+        if let Some(lines) = self.lines.as_mut() {
+            lines.push_back(self.buffer.total_written());
+        }
+        let _ = self.buffer.write_all(&line);
+        self.trim_lines();
+    }
+
+    /// Move the content of the full ring into the generations, dropping the oldest over the
+    /// limit, and start over with an empty ring.
+    #[cold]
+    fn seal_generation(&mut self) {
+        let mut content = Vec::with_capacity(self.buffer.len());
+        let _ = self.dump_buffer(&mut content);
+        let generations = self.generations.as_mut().expect("generations");
+        if generations.sealed.len() >= generations.max {
+            generations.sealed.pop_front();
+        }
+        generations.sealed.push_back(content);
+        self.buffer.clear();
+        if let Some(lines) = self.lines.as_mut() {
+            lines.clear();
+        }
+    }
+
+    /// Store the count of the messages collapsed by dedup, if any.
+    fn flush_repeated(&mut self) {
+        let Some(dedup) = self.dedup.as_mut() else {
+            return;
         };
-        let _th = thread::spawn(move || backend.run());
-        Self{
-            tx,
-            _th,
-            res: res_rx,
+        if dedup.repeated == 0 {
+            return;
         }
+        let note = format!("[ring-file: last message repeated {} times]\n", dedup.repeated);
+        dedup.repeated = 0;
+        let mut line = Vec::with_capacity(STAMP_LEN + note.len());
+        if let Some(start) = self.binary_start {
+            write_stamp(&mut line, start);
+        }
+        line.extend_from_slice(note.as_bytes());
+        self.store(line);
     }
 
-    /// Trigger dump to the disk.
-    pub fn dump(&self) -> std::io::Result<()> {
-        self.tx.send(Msg::Dump).expect("send");
-        self.res.recv().unwrap()
+    /// Push the appended content of the live file to the OS, called when no message is queued.
+    #[inline]
+    fn flush_live(&mut self) {
+        if let Some(live) = self.live.as_mut() {
+            let _ = live.file.flush();
+        }
+        self.live_flush_deadline = None;
     }
 
-    #[inline(always)]
-    pub fn write(&self, content: String) {
-        self.tx.send(Msg::Msg(content)).expect("send");
+    /// Called after a batch of messages, flush the live file now or on the interval.
+    fn flush_live_batch(&mut self, now: Instant) {
+        if self.live.is_none() {
+            return;
+        }
+        match self.live_flush_interval {
+            None => self.flush_live(),
+            Some(interval) => match self.live_flush_deadline {
+                None => self.live_flush_deadline = Some(now + interval),
+                Some(deadline) if deadline <= now => self.flush_live(),
+                Some(_) => {}
+            },
+        }
     }
 
-    /// Clear previous buffer
-    pub fn clear(&self) {
-        self.tx.send(Msg::Clear).expect("send");
+    /// Copy the buffer into the region of RingFileBuilder::mirror(). The region locked by the
+    /// reader is left for the next batch, as the backend should not wait.
+    fn mirror_now(&mut self) {
+        self.mirror_deadline = None;
+        let Some((region, _)) = self.mirror.as_ref() else {
+            return;
+        };
+        match region.try_lock() {
+            Ok(mut region) => {
+                self.buffer.mirror_into(&mut region);
+            }
+            Err(TryLockError::Poisoned(e)) => {
+                self.buffer.mirror_into(&mut e.into_inner());
+            }
+            Err(TryLockError::WouldBlock) => {}
+        }
+    }
+
+    /// Called after a batch of messages, mirror the buffer once the interval has elapsed.
+    fn mirror_batch(&mut self, now: Instant) {
+        let Some((_, interval)) = self.mirror.as_ref() else {
+            return;
+        };
+        match self.mirror_deadline {
+            None => self.mirror_deadline = Some(now + *interval),
+            Some(deadline) if deadline <= now => self.mirror_now(),
+            Some(_) => {}
+        }
+    }
+
+    /// The earliest deadline of the timed triggers, None when no trigger is pending.
+    fn next_deadline(&self) -> Option<Instant> {
+        [
+            self.idle_deadline,
+            self.live_flush_deadline,
+            self.mirror_deadline,
+            self.signal_deadline,
+            self.boost.map(|(_, deadline)| deadline),
+            self.reorder.as_ref().and_then(|reorder| reorder.deadline),
+        ]
+        .iter()
+        .flatten()
+        .min()
+        .copied()
+    }
+
+    /// Store the pending messages of strict_order in sequence, up to the next gap unless `skip`.
+    fn store_ordered(&mut self, skip: bool) {
+        let mut stored = false;
+        while let Some(line) = self.reorder.as_mut().and_then(|reorder| reorder.pop(skip)) {
+            self.process(Msg::Write(line));
+            stored = true;
+        }
+        let Some(reorder) = self.reorder.as_mut() else {
+            return;
+        };
+        if reorder.pending.is_empty() {
+            reorder.deadline = None;
+        } else if stored || reorder.deadline.is_none() {
+            reorder.deadline = Some(Instant::now() + reorder.timeout);
+        }
+    }
+
+    /// Reallocate the buffer, see [RingFile::resize()].
+    fn resize(&mut self, buf_size: i32) -> std::io::Result<()> {
+        if self.align > 1 && buf_size > 0 && (buf_size as usize) % self.align != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "buf_size should be a multiple of align",
+            ));
+        }
+        self.buffer.resize(buf_size)?;
+        self.trim_lines();
+        Ok(())
+    }
+
+    /// Resize to `buf_size` until `duration` from now, see [RingFile::boost()].
+    fn boost(&mut self, buf_size: i32, duration: Duration) -> std::io::Result<()> {
+        if !self.timers {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "the boost is ended by the backend thread",
+            ));
+        }
+        let original = match self.boost {
+            Some((original, _)) => original,
+            None => self.buffer.capacity() as i32,
+        };
+        self.resize(buf_size)?;
+        self.boost = Some((original, Instant::now() + duration));
+        Ok(())
+    }
+
+    /// Fire the timed triggers due at `now`.
+    fn fire_timers(&mut self, now: Instant) {
+        if self.live_flush_deadline.is_some_and(|deadline| deadline <= now) {
+            self.flush_live();
+        }
+        if self.mirror_deadline.is_some_and(|deadline| deadline <= now) {
+            self.mirror_now();
+        }
+        if self.reorder.as_ref().is_some_and(|r| r.deadline.is_some_and(|d| d <= now)) {
+            self.store_ordered(true);
+        }
+        if let Some((original, deadline)) = self.boost {
+            if deadline <= now {
+                self.boost = None;
+                let _ = self.resize(original);
+            }
+        }
+        if self.idle_deadline.is_some_and(|deadline| deadline <= now) {
+            self.idle_deadline = None;
+            self.idle_armed = false;
+            let _ = self.auto_dump();
+        }
+        if self.signal_deadline.is_some_and(|deadline| deadline <= now) {
+            self.signal_deadline = Some(now + SIGNAL_POLL);
+            let mut hit = false;
+            for (sig, seen) in self.signals.iter_mut() {
+                let raised = crash::raised(*sig);
+                hit |= raised != *seen;
+                *seen = raised;
+            }
+            if hit {
+                let _ = self.auto_dump();
+            }
+        }
+    }
+
+    /// Dump when one of `signals` is received, see [RingFile::arm_crash_capture()].
+    fn watch_signals(&mut self, signals: Vec<i32>) -> std::io::Result<()> {
+        if !self.timers {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "signals are only polled by the backend thread",
+            ));
+        }
+        for sig in signals {
+            if !self.signals.iter().any(|(watched, _)| *watched == sig) {
+                self.signals.push((sig, crash::raised(sig)));
+            }
+        }
+        if !self.signals.is_empty() && self.signal_deadline.is_none() {
+            self.signal_deadline = Some(Instant::now() + SIGNAL_POLL);
+        }
+        Ok(())
+    }
+
+    /// Wait for the next message or the next deadline of the timed triggers, whichever comes first.
+    /// Sleep without polling when idle. Return None when the senders are gone.
+    fn recv<R: MsgReceiver<Msg>>(&mut self, rx: &R) -> Option<Msg> {
+        // The queue is drained when called, so the idle period starts now
+        self.idle_deadline = match self.dump_on_idle {
+            Some(timeout) if self.idle_armed => Some(Instant::now() + timeout),
+            _ => None,
+        };
+        loop {
+            let Some(deadline) = self.next_deadline() else {
+                return rx.recv();
+            };
+            let now = Instant::now();
+            if deadline <= now {
+                self.fire_timers(now);
+                continue;
+            }
+            match rx.recv_timeout(deadline - now) {
+                Ok(msg) => return Some(msg),
+                Err(RecvTimeoutError::Timeout) => self.fire_timers(Instant::now()),
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    /// Process all the queued messages before blocking again.
+    fn run<R: MsgReceiver<Msg>>(&mut self, rx: R) {
+        let _guard = BackendGuard::enter();
+        self.timers = true;
+        while let Some(msg) = self.recv(&rx) {
+            if !self.process(msg) {
+                return;
+            }
+            while let Some(msg) = rx.try_recv() {
+                if !self.process(msg) {
+                    return;
+                }
+            }
+            let now = Instant::now();
+            self.flush_live_batch(now);
+            self.mirror_batch(now);
+        }
+    }
+}
+
+/// Cut `line` to `max` bytes, not inside a UTF-8 sequence nor before `start`, and append the
+/// marker `…(+N)` where N is the bytes cut. The trailing newline is kept and not counted in the
+/// length. Return N, 0 with `line` unchanged when it fits.
+fn cut_line(line: &mut Vec<u8>, start: usize, max: usize) -> usize {
+    let newline = line.last() == Some(&b'\n');
+    if line.len() - newline as usize <= max {
+        return 0;
+    }
+    let mut keep = max;
+    while keep > start && max - keep < 3 && line[keep] & 0xc0 == 0x80 {
+        keep -= 1;
+    }
+    let cut = line.len() - keep - newline as usize;
+    line.truncate(keep);
+    line.extend_from_slice(format!("\u{2026}(+{})", cut).as_bytes());
+    if newline {
+        line.push(b'\n');
+    }
+    cut
+}
+
+/// The buffer returned by the noop handle for the methods taking the buffer, allocated on each
+/// call as a RingBuffer has at least one byte.
+fn empty_buffer() -> RingBuffer {
+    RingBuffer::new(1)
+}
+
+/// Interval to check the signals of [RingFile::arm_crash_capture()]
+const SIGNAL_POLL: Duration = Duration::from_millis(100);
+
+/// Default of [RingFileBuilder::tcp_timeout()]
+pub(crate) const DEFAULT_TCP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First word of the header line of [RingFile::dump_to_tcp()]
+const TCP_MAGIC: &str = "ring-file-dump";
+
+/// The shortest interval between the calls of [RingFileBuilder::warn_write_threshold()]
+const LARGE_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Length of the preview passed to [RingFileBuilder::warn_write_threshold()]
+const LARGE_WRITE_PREVIEW: usize = 64;
+
+/// Prefix of the lines of [RingFile::capture_stderr()]
+const STDERR_TAG: &[u8] = b"[stderr] ";
+
+/// Size of the reads from the stderr pipe, and the longest line kept waiting for its newline
+const STDERR_CHUNK: usize = 4096;
+
+/// Spaces per span level of [RingFileBuilder::indent()]
+const INDENT: usize = 2;
+
+/// The padding byte of [RingFileBuilder::align()], skipped in dumps.
+const FILLER: u8 = 0;
+
+/// Open `path` for writing without truncating, or create and remove it when missing.
+fn check_writable(path: &Path) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, DEFAULT_MODE);
+    match options.open(path) {
+        Ok(file) => {
+            drop(file);
+            std::fs::remove_file(path)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            std::fs::OpenOptions::new().write(true).open(path).map(|_| ())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The lines of the run id and the labels at the start of every dump, None for neither.
+fn run_header(run_id: Option<&str>, labels: &[String]) -> Option<String> {
+    if run_id.is_none() && labels.is_empty() {
+        return None;
+    }
+    let mut header = String::new();
+    if let Some(id) = run_id {
+        header.push_str(&format!("[ring-file: run {}]\n", id));
+    }
+    for label in labels.iter() {
+        header.push_str(&format!("[ring-file: label {}]\n", label));
+    }
+    Some(header)
+}
+
+/// Insert the run id into the file name, `{stem}.{run_id}.{ext}`.
+fn with_run_id(path: &Path, run_id: &str) -> PathBuf {
+    let mut name: OsString = path.file_stem().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(run_id);
+    if let Some(ext) = path.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    path.with_file_name(name)
+}
+
+/// Whether `path` still points to `file`, false when it is removed or replaced.
+#[cfg(unix)]
+fn same_file(path: &Path, file: &File) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(path), file.metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_path: &Path, _file: &File) -> bool {
+    true
+}
+
+/// Append FILLER to the message so that its length is a multiple of `align`.
+#[inline]
+fn pad_to_align(line: &mut Vec<u8>, align: usize) {
+    let rem = line.len() % align;
+    if rem > 0 {
+        line.resize(line.len() + align - rem, FILLER);
+    }
+}
+
+/// Skip a leading filler of a line.
+#[inline]
+fn trim_filler(line: &[u8]) -> &[u8] {
+    let n = line.iter().take_while(|b| **b == FILLER).count();
+    &line[n..]
+}
+
+/// Writer dropping the FILLER bytes.
+struct SkipFiller<W: Write>(W);
+
+impl<W: Write> Write for SkipFiller<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for run in buf.split(|b| *b == FILLER) {
+            self.0.write_all(run)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Append the content to be overwritten by `line` to the spill file.
+fn spill_evicted(spill: &mut Spill, buffer: &RingBuffer, line: &[u8]) -> std::io::Result<()> {
+    let (head, tail) = buffer.evicted_by(line.len());
+    spill.write_all(head)?;
+    spill.write_all(tail)?;
+    let cap = buffer.capacity();
+    if line.len() > cap {
+        // The head of the line itself will not survive in the ring.
+        spill.write_all(&line[0..line.len() - cap])?;
+    }
+    Ok(())
+}
+
+/// The file appended with every message, see [RingFileBuilder::live_file()].
+struct LiveFile {
+    path: Box<Path>,
+    file: BufWriter<File>,
+    written: u64,
+    max_size: u64,
+}
+
+impl LiveFile {
+    fn open(path: Box<Path>, max_size: u64) -> std::io::Result<Self> {
+        let file = BufWriter::new(create_file(&path, DEFAULT_MODE)?);
+        Ok(Self { path, file, written: 0, max_size })
+    }
+
+    fn append(&mut self, line: &[u8]) -> std::io::Result<()> {
+        if self.max_size > 0 && self.written > 0 && self.written + line.len() as u64 > self.max_size
+        {
+            self.file.flush()?;
+            let mut rotated: OsString = self.path.as_os_str().into();
+            rotated.push(".1");
+            std::fs::rename(&self.path, PathBuf::from(rotated))?;
+            self.file = BufWriter::new(create_file(&self.path, DEFAULT_MODE)?);
+            self.written = 0;
+        }
+        self.file.write_all(line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl RingFile {
+    /// # Arguments:
+    ///
+    /// - buf_size: total buffer size
+    ///
+    /// - file_path: The target file to dump
+    pub fn new(buf_size: i32, file_path: Box<Path>) -> Self {
+        RingFileBuilder::new(buf_size, file_path).build().expect("alloc")
+    }
+
+    /// Same as [RingFile::new()], but the buf_size is given as a string like `"512MiB"`,
+    /// e.g. from the configuration of the operator.
+    ///
+    /// The size is an integer with an optional unit (case-insensitive): `B`, the binary `KiB`,
+    /// `MiB`, `GiB`, or the decimal `KB`, `MB`, `GB`. Returns error of `ErrorKind::InvalidInput`
+    /// when malformed, zero, or larger than `i32::MAX` bytes.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_size_str.store");
+    /// let ring = RingFile::new_from_size_str("4KiB", path.into()).expect("build ok");
+    /// ring.write("log message\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// for valid in ["1", "100 B", "64kib", "1MB", " 16 MiB "] {
+    ///     assert!(RingFile::new_from_size_str(valid, path.into()).is_ok(), "{}", valid);
+    /// }
+    /// let invalid = ["", "MiB", "0KiB", "-1", "1.5MiB", "12 parsecs", "2GiB", "3GB", "99999999999999999999"];
+    /// for invalid in invalid {
+    ///     let e = RingFile::new_from_size_str(invalid, path.into()).err().unwrap();
+    ///     assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput, "{}", invalid);
+    /// }
+    /// ```
+    pub fn new_from_size_str(size: &str, file_path: Box<Path>) -> std::io::Result<Self> {
+        RingFileBuilder::new(parse_size(size)?, file_path).build()
+    }
+
+    /// Same as [RingFile::new()], with the options of `config`, e.g. parsed from a file,
+    /// see [RingFileConfig]. Returns the errors of [RingFileBuilder::build()].
+    pub fn from_config(config: &RingFileConfig, file_path: Box<Path>) -> std::io::Result<Self> {
+        config.builder(file_path).build()
+    }
+
+    /// Same as [RingFile::new()], but check first that the dump file can be written, so that
+    /// a wrong path fails at startup instead of at the first dump.
+    ///
+    /// An existing file is opened for writing without truncating, a missing one is created
+    /// and removed. Returns the error of opening or creating the file, and the error of the
+    /// allocation. The check tells nothing about the free space.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_checked.store");
+    /// std::fs::write(path, "previous dump\n").unwrap();
+    /// let ring = RingFile::new_checked(1024, path.into()).expect("build ok");
+    /// // Not clobbered by the check
+    /// assert_eq!(std::fs::read_to_string(path).unwrap(), "previous dump\n");
+    /// let _ = std::fs::remove_file(path);
+    /// RingFile::new_checked(1024, path.into()).expect("build ok");
+    /// assert!(!path.exists());
+    /// ring.write("log message\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let missing = Path::new("/tmp/ringfile_checked_missing/dir/ring.store");
+    /// let e = RingFile::new_checked(1024, missing.into()).err().unwrap();
+    /// assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+    /// // The parent is a file
+    /// let e = RingFile::new_checked(1024, path.join("ring.store").into()).err().unwrap();
+    /// assert_eq!(e.kind(), std::io::ErrorKind::NotADirectory);
+    /// ```
+    pub fn new_checked(buf_size: i32, file_path: Box<Path>) -> std::io::Result<Self> {
+        check_writable(&file_path)?;
+        RingFileBuilder::new(buf_size, file_path).build()
+    }
+
+    /// Same as [RingFile::new()], but the backend thread is not spawned, instead the backend loop
+    /// is returned as a [RingFileDriver] for the caller to run on its own thread or pool.
+    ///
+    /// [RingFile::new()] is equivalent to spawning a thread for [RingFileDriver::run()],
+    /// except that [RingFile::shutdown()] does not wait for a detached driver to exit.
+    /// Until the driver runs, the messages are queued, and the calls waiting for the backend
+    /// like dump() block. See [RingFileBuilder::build_detached()] for the other options.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_detached.store");
+    /// let (ring, driver) = RingFile::new_detached(1024, path.into());
+    /// ring.write("queued before run\n".to_string());
+    /// // On a thread owned by the application
+    /// let th = std::thread::Builder::new()
+    ///     .name("app-managed".to_string())
+    ///     .spawn(move || driver.run())
+    ///     .unwrap();
+    /// ring.write("log message\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"queued before run\nlog message\n");
+    /// ring.shutdown();
+    /// th.join().unwrap();
+    /// ```
+    pub fn new_detached(buf_size: i32, file_path: Box<Path>) -> (Self, RingFileDriver) {
+        RingFileBuilder::new(buf_size, file_path).build_detached().expect("alloc")
+    }
+
+    /// Same as [RingFile::new()], but the backend is a future instead of a thread, passed to
+    /// `spawner` to schedule it on the caller's runtime, e.g. `|task| { tokio::spawn(task); }`.
+    ///
+    /// There are two backends:
+    /// - The thread (default) blocks on the channel, and supports all the options.
+    /// - The [RingFileTask] (opt-in) awaits the channel, and saves the OS thread in a fully
+    ///   async program. The writes never block, from a task or from a plain thread.
+    ///
+    /// The calls waiting for the backend like dump() block the calling thread until the task
+    /// has processed them: on a single-threaded runtime, do not call them from a task of the
+    /// same runtime, use `spawn_blocking` or a multi-threaded runtime instead.
+    /// As for [RingFile::new_detached()], [RingFile::shutdown()] does not wait for the task.
+    /// See [RingFileBuilder::build_task()] for the other options.
+    ///
+    /// # Example:
+    ///
+    /// With a minimal executor running the task on its own thread:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::future::Future;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// use std::task::{Context, Poll, Wake};
+    ///
+    /// struct Unpark(std::thread::Thread);
+    ///
+    /// impl Wake for Unpark {
+    ///     fn wake(self: Arc<Self>) {
+    ///         self.0.unpark();
+    ///     }
+    /// }
+    ///
+    /// fn block_on<F: Future>(fut: F) -> F::Output {
+    ///     let mut fut = Box::pin(fut);
+    ///     let waker = Arc::new(Unpark(std::thread::current())).into();
+    ///     let mut cx = Context::from_waker(&waker);
+    ///     loop {
+    ///         if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+    ///             return out;
+    ///         }
+    ///         std::thread::park();
+    ///     }
+    /// }
+    ///
+    /// let path = Path::new("/tmp/ringfile_task.store");
+    /// let mut executor = None;
+    /// let ring = RingFile::spawn_on(1024, path.into(), |task| {
+    ///     executor = Some(std::thread::spawn(move || block_on(task)));
+    /// });
+    /// for i in 0..3 {
+    ///     ring.write(format!("log message {}\n", i));
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert_eq!(content, "log message 0\nlog message 1\nlog message 2\n");
+    /// ring.shutdown();
+    /// // The task completes on shutdown
+    /// executor.unwrap().join().unwrap();
+    /// assert!(!ring.backend_alive());
+    /// ```
+    pub fn spawn_on<S: FnOnce(RingFileTask)>(
+        buf_size: i32, file_path: Box<Path>, spawner: S,
+    ) -> Self {
+        let (ring, task) = RingFileBuilder::new(buf_size, file_path).build_task().expect("alloc");
+        spawner(task);
+        ring
+    }
+
+    /// Same as [RingFile::new()], but without the backend thread.
+    ///
+    /// The RingBuffer is kept behind a Mutex, and written on the caller's thread.
+    /// This is for environments where spawning a thread is not desired,
+    /// at the cost of lock contention between the writers, which the threaded default avoids.
+    /// Timer based triggers like [RingFileBuilder::dump_on_idle()] have no effect.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let threaded = RingFile::new(16, Path::new("/tmp/ringfile_threaded.store").into());
+    /// let inline = RingFile::new_inline(16, Path::new("/tmp/ringfile_inline.store").into());
+    /// for ring in [&threaded, &inline].iter() {
+    ///     ring.write("dropped\n".to_string());
+    ///     ring.clear();
+    ///     ring.write("0123456789abcdef0123456789\n".to_string());
+    ///     ring.dump().expect("dump ok");
+    /// }
+    /// let expected = b"bcdef0123456789\n";
+    /// assert_eq!(std::fs::read("/tmp/ringfile_threaded.store").unwrap(), expected);
+    /// assert_eq!(std::fs::read("/tmp/ringfile_inline.store").unwrap(), expected);
+    /// ```
+    pub fn new_inline(buf_size: i32, file_path: Box<Path>) -> Self {
+        RingFileBuilder::new(buf_size, file_path).inline(true).build().expect("alloc")
+    }
+
+    /// Same as [RingFile::new()], but with the channel created by `transport`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::{RingFile, StdTransport};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_std_transport.store");
+    /// let ring: RingFile<StdTransport> = RingFile::with_transport(1024, path.into(), StdTransport);
+    /// let span = ring.span("request");
+    /// ring.write("log message\n".to_string());
+    /// drop(span);
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// assert!(content.starts_with("> request "));
+    /// assert!(content.contains("\nlog message\n< request "));
+    /// ```
+    pub fn with_transport<T: MsgTransport + 'static>(
+        buf_size: i32, file_path: Box<Path>, transport: T,
+    ) -> RingFile<T> {
+        RingFileBuilder::new(buf_size, file_path).transport(transport).build().expect("alloc")
+    }
+
+    /// Return a handle that does nothing, without a ring buffer or backend thread.
+    ///
+    /// Every method has no effect and succeeds: the methods returning a Result return Ok with
+    /// an empty or default value, e.g. an empty Vec of files, and no file is created. The only
+    /// allocation is the empty buffer of [RingFile::take_buffer()] and
+    /// [RingFile::snapshot_with_stats()], one byte on each call, as a [RingBuffer] is never
+    /// zero sized. With feature `disabled`, [RingFile::new()] and
+    /// [RingFileBuilder::build()] return this instead, so that the calls can be compiled out
+    /// without changing the call sites.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::time::Duration;
+    /// let ring = RingFile::noop();
+    /// ring.write("log message\n".to_string());
+    /// assert!(ring.dump().is_ok());
+    /// let path = "/tmp/ringfile_noop_budget.store";
+    /// let _ = std::fs::remove_file(path);
+    /// assert_eq!(ring.dump_with_budget(path, Duration::from_secs(1)).unwrap(), (0, false));
+    /// assert!(!std::path::Path::new(path).exists());
+    /// assert!(ring.take_buffer().unwrap().is_empty());
+    /// assert!(ring.load_snapshot(b"ignored\n").is_ok());
+    /// let (buffer, stats) = ring.snapshot_with_stats().unwrap();
+    /// assert!(buffer.is_empty());
+    /// assert_eq!(stats.total_written, 0);
+    /// ```
+    pub fn noop() -> Self {
+        Self::empty()
+    }
+}
+
+impl<X: MsgTransport> RingFile<X> {
+    /// The noop handle of any transport, see [RingFile::noop()].
+    fn empty() -> Self {
+        Self {
+            tx: None,
+            shutdown: AtomicBool::new(false),
+            armed: AtomicBool::new(true),
+            alive: None,
+            crash_hook: AtomicBool::new(false),
+            th: Mutex::new(None),
+            sampler: None,
+            backlog: None,
+            drops: None,
+            latency: None,
+            tcp_timeout: DEFAULT_TCP_TIMEOUT,
+            order: None,
+            dumps_throttled: None,
+            writers: None,
+            rate: None,
+            timestamps: None,
+            start: None,
+            binary_uptime: false,
+            thread_names: false,
+            per_thread_seq: false,
+            indent: false,
+            utf8: Utf8Mode::Off,
+            utf8_rejected: AtomicU64::new(0),
+            #[cfg(feature = "backtrace")]
+            backtrace_levels: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_noop(&self) -> bool {
+        cfg!(feature = "disabled") || self.tx.is_none()
+    }
+
+    /// Whether the next message passes the adaptive sampling.
+    #[inline(always)]
+    fn admit(&self) -> bool {
+        match self.sampler.as_ref() {
+            Some(sampler) => sampler.admit(),
+            None => true,
+        }
+    }
+
+    #[inline(always)]
+    fn send(&self, msg: Msg) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        if self.shutdown.load(Ordering::Acquire) {
+            return Err(closed());
+        }
+        self.dispatch(msg)
+    }
+
+    /// Hand `msg` to the backend without checking the shutdown flag.
+    /// Should not be called on noop handle.
+    fn dispatch(&self, msg: Msg) -> std::io::Result<()> {
+        match self.tx.as_ref().unwrap() {
+            Dispatch::Channel(tx) => tx.send(msg).map_err(|_| self.disconnected()),
+            Dispatch::Task(tx) => tx.send(msg).map_err(|_| self.disconnected()),
+            Dispatch::Inline(backend) => {
+                // A panic while holding the lock does not break the buffer
+                let mut backend = backend.lock().unwrap_or_else(|e| e.into_inner());
+                let _guard = BackendGuard::enter();
+                backend.process(msg);
+                let now = Instant::now();
+                backend.flush_live_batch(now);
+                backend.mirror_batch(now);
+                Ok(())
+            }
+        }
+    }
+
+    /// Account a drop on the writer.
+    #[cold]
+    fn record_drop(&self, reason: DropReason, bytes: usize) {
+        if let Some(drops) = self.drops.as_ref() {
+            drops.record(reason, bytes);
+        }
+    }
+
+    /// Prefix and send a message from the writer.
+    #[inline(always)]
+    fn send_line(&self, level: Option<Level>, content: Vec<u8>) -> std::io::Result<()> {
+        self.send_to(None, level, content)
+    }
+
+    /// Prefix and send a message to the ring, or to the buffer of `category`.
+    #[inline(always)]
+    fn send_to(
+        &self, category: Option<String>, level: Option<Level>, content: Vec<u8>,
+    ) -> std::io::Result<()> {
+        let (Some(writers), Some(rate)) = (self.writers.as_ref(), self.rate.as_ref()) else {
+            return Ok(());
+        };
+        if !self.armed.load(Ordering::Acquire) {
+            self.record_drop(DropReason::Disarmed, content.len());
+            return Ok(());
+        }
+        let seq = writers.see();
+        rate.add(content.len());
+        if self.utf8 == Utf8Mode::Reject && std::str::from_utf8(&content).is_err() {
+            self.utf8_rejected.fetch_add(1, Ordering::Relaxed);
+            self.record_drop(DropReason::InvalidUtf8, content.len());
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "message is not valid UTF-8",
+            ));
+        }
+        if !self.admit() {
+            self.record_drop(DropReason::Sampled, content.len());
+            return Ok(());
+        }
+        let depth = if self.indent { crate::span::depth() } else { 0 };
+        // Taken last before the prefixes, as close as possible to the send
+        let order = match (category.is_none(), self.order.as_ref()) {
+            (true, Some(next)) => Some(next.fetch_add(1, Ordering::Relaxed)),
+            _ => None,
+        };
+        let line = if self.timestamps.is_some()
+            || self.start.is_some()
+            || self.thread_names
+            || self.per_thread_seq
+            || order.is_some()
+            || level.is_some()
+            || depth > 0
+        {
+            let mut line =
+                Vec::with_capacity(2 * MAX_PREFIX_LEN + 2 + INDENT * depth + content.len());
+            match self.start {
+                // A fixed size stamp ahead of the text, rendered on read
+                Some(start) if self.binary_uptime => write_stamp(&mut line, start),
+                _ => {}
+            }
+            if let Some(precision) = self.timestamps {
+                write_timestamp(&mut line, SystemTime::now(), precision);
+            }
+            match self.start {
+                Some(start) if !self.binary_uptime => write_uptime(&mut line, start),
+                _ => {}
+            }
+            if self.thread_names {
+                write_thread_name(&mut line);
+            }
+            if self.per_thread_seq {
+                write_seq(&mut line, seq);
+            }
+            if let Some(order) = order {
+                write_seq(&mut line, order);
+            }
+            if let Some(level) = level {
+                line.extend_from_slice(&level.prefix());
+            }
+            line.resize(line.len() + INDENT * depth, b' ');
+            line.extend_from_slice(&content);
+            #[cfg(feature = "backtrace")]
+            if level.is_some_and(|level| self.backtrace_levels & level.bit() != 0) {
+                append_backtrace(&mut line);
+            }
+            line
+        } else {
+            content
+        };
+        let len = line.len();
+        let msg = match (category, order) {
+            (Some(category), _) => Msg::WriteCategory(category, line),
+            (None, Some(order)) => Msg::Ordered(order, line),
+            (None, None) => Msg::Write(line),
+        };
+        let Some(backlog) = self.backlog.as_ref() else {
+            return self.send(msg);
+        };
+        if !backlog.admit(len) {
+            if let Some(sampler) = self.sampler.as_ref() {
+                sampler.done();
+            }
+            self.record_drop(DropReason::Overflow, len);
+            return Ok(());
+        }
+        self.send(msg).inspect_err(|_| backlog.done(len))
+    }
+
+    /// Send a request and wait for the reply on a channel for this call only, see [Msg].
+    /// Should not be called on noop handle.
+    fn call<R>(&self, msg: impl FnOnce(SyncSender<R>) -> Msg) -> std::io::Result<R> {
+        let (res_tx, res_rx) = sync_channel(1);
+        self.send(msg(res_tx))?;
+        res_rx.recv().map_err(|_| self.disconnected())
+    }
+
+    /// The error when the backend is gone.
+    #[cold]
+    fn disconnected(&self) -> std::io::Error {
+        if self.is_shutdown() {
+            closed()
+        } else {
+            backend_dead()
+        }
+    }
+
+    /// Run `f` with the buffer on the backend, and wait for the result.
+    /// Should not be called on noop handle.
+    fn inspect<R, F>(&self, f: F) -> std::io::Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&RingBuffer) -> R + Send + 'static,
+    {
+        self.call(|res: SyncSender<R>| {
+            Msg::Inspect(Box::new(move |buffer| {
+                reply(res, f(buffer));
+            }))
+        })
+    }
+
+    /// Copy the lines of the buffer without the filler of align, None for the noop handle.
+    pub(crate) fn collect_lines(&self) -> std::io::Result<Option<Vec<Vec<u8>>>> {
+        if self.is_noop() {
+            return Ok(None);
+        }
+        self.inspect(|buffer| {
+            Some(buffer.lines().map(|line| trim_filler(&line).to_vec()).collect())
+        })
+    }
+
+    /// Dump the content and its index of records, see [RingBuffer::dump_with_index()].
+    ///
+    /// Only the content of the buffer is written, without the additions of [RingFile::dump()]
+    /// such as the banners and the footer, so that every byte of the data file is indexed.
+    /// Noop handle returns Ok without writing.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let (data, index) = ("/tmp/ringfile_file_indexed.store", "/tmp/ringfile_file_indexed.index");
+    /// let ring = RingFileBuilder::new(1024, Path::new(data).into())
+    ///     .timestamps(true)
+    ///     .sticky_banner("not indexed\n".to_string())
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.dump_with_index(data, index).expect("dump ok");
+    /// let data = std::fs::read_to_string(data).unwrap();
+    /// let index = std::fs::read_to_string(index).unwrap();
+    /// for (i, entry) in index.lines().skip(1).enumerate() {
+    ///     let fields: Vec<usize> = entry.split('\t').map(|f| f.parse().unwrap()).collect();
+    ///     let line = &data[fields[0]..fields[0] + fields[1]];
+    ///     assert!(line.ends_with(&format!("] line {}\n", i)));
+    ///     assert!(line.starts_with(&format!("[{}us]", fields[2] / 1000)));
+    /// }
+    /// assert_eq!(index.lines().count(), 11);
+    /// ```
+    pub fn dump_with_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self, data_path: P, index_path: Q,
+    ) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let data_path: Box<Path> = data_path.as_ref().into();
+        let index_path: Box<Path> = index_path.as_ref().into();
+        self.inspect(move |buffer| {
+            // Skip the filler of align
+            let lines = buffer.lines().map(|line| match line {
+                Cow::Borrowed(line) => Cow::Borrowed(trim_filler(line)),
+                Cow::Owned(line) => Cow::Owned(trim_filler(&line).to_vec()),
+            });
+            dump_indexed(lines, &data_path, &index_path, buffer.dump_mode())
+        })?
+    }
+
+    /// Dump only the lines matching `pred` to `file_path`, see [RingBuffer::dump_filtered()].
+    ///
+    /// `pred` runs on the backend thread.
+    pub fn dump_filtered<P, F>(&self, file_path: P, pred: F) -> std::io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Fn(&[u8]) -> bool + Send + 'static,
+    {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let file_path: Box<Path> = file_path.as_ref().into();
+        self.inspect(move |buffer| {
+            // Same as RingBuffer::dump_filtered(), but skip the filler of align
+            let mut file = create_file(&file_path, buffer.dump_mode())?;
+            for line in buffer.lines() {
+                let line = trim_filler(&line);
+                if pred(line) {
+                    file.write_all(line)?;
+                }
+            }
+            Ok(())
+        })?
+    }
+
+    /// Dump the lines transformed by `f` to `file_path`, see [RingBuffer::dump_map()].
+    ///
+    /// `f` runs on the backend thread. Only the content of the buffer is written, without the
+    /// additions of dump() such as the run header, the banners and the footer.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::borrow::Cow;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_dump_map.store");
+    /// let ring = RingFile::new(1024, path.into());
+    /// ring.write("user=alice\n".to_string());
+    /// ring.write("plain\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let plain = std::fs::read(path).unwrap();
+    /// let mapped = Path::new("/tmp/ringfile_dump_map.mapped");
+    /// ring.dump_map(mapped, |line| Cow::Borrowed(line)).expect("dump ok");
+    /// assert_eq!(std::fs::read(mapped).unwrap(), plain);
+    /// ring.dump_map(mapped, |line| match line.strip_prefix(b"user=") {
+    ///     Some(_) => Cow::Borrowed(b"user=***\n"),
+    ///     None => Cow::Borrowed(line),
+    /// })
+    /// .expect("dump ok");
+    /// assert_eq!(std::fs::read(mapped).unwrap(), b"user=***\nplain\n");
+    /// ```
+    pub fn dump_map<P, F>(&self, file_path: P, mut f: F) -> std::io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&[u8]) -> Cow<'_, [u8]> + Send + 'static,
+    {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let file_path: Box<Path> = file_path.as_ref().into();
+        self.inspect(move |buffer| {
+            // Same as RingBuffer::dump_map(), but skip the filler of align
+            let mut file = create_file(&file_path, buffer.dump_mode())?;
+            for line in buffer.lines() {
+                file.write_all(&f(trim_filler(&line)))?;
+            }
+            Ok(())
+        })?
+    }
+
+    /// Dump the buffer to `file_path` within the time budget `max`, newest bytes first,
+    /// see [RingBuffer::dump_with_budget()].
+    ///
+    /// The budget counts from when the backend gets to the request, after the messages queued
+    /// before. Only the content of the buffer is written, without the additions of dump() such
+    /// as the run header, the banners and the footer, and with the filler of
+    /// [RingFileBuilder::align()] left as is.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_budget.store");
+    /// let ring = RingFile::new(1024, path.into());
+    /// ring.write("log message\n".to_string());
+    /// let (written, timed_out) = ring.dump_with_budget(path, Duration::from_secs(1)).unwrap();
+    /// assert_eq!((written, timed_out), (12, false));
+    /// assert_eq!(std::fs::read(path).unwrap(), b"log message\n");
+    /// ```
+    pub fn dump_with_budget<P: AsRef<Path>>(
+        &self, file_path: P, max: Duration,
+    ) -> std::io::Result<(usize, bool)> {
+        if self.is_noop() {
+            return Ok((0, false));
+        }
+        let file_path: Box<Path> = file_path.as_ref().into();
+        self.inspect(move |buffer| buffer.dump_with_budget(&file_path, max))?
+    }
+
+    /// Dump only the records written by [RingFile::write_leveled()] with level at or above `min`.
+    ///
+    /// Each record is expected to be a single line, the lines without a level prefix are skipped,
+    /// unless with [RingFileBuilder::framed()].
+    /// The prefixes of [RingFileBuilder::timestamps()], [RingFileBuilder::uptime()] and
+    /// [RingFileBuilder::thread_names()] are skipped over.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{Level, RingFile};
+    /// use std::path::Path;
+    /// let ring = RingFile::new(1024, Path::new("/tmp/ringfile_leveled.store").into());
+    /// ring.write_leveled(Level::Debug, b"connecting\n".to_vec());
+    /// ring.write_leveled(Level::Warn, b"retry\n".to_vec());
+    /// ring.write("plain\n".to_string());
+    /// ring.write_leveled(Level::Error, b"gave up\n".to_vec());
+    /// ring.write_leveled(Level::Info, b"exit\n".to_vec());
+    /// let path = Path::new("/tmp/ringfile_leveled_warn.store");
+    /// ring.dump_min_level(path, Level::Warn).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"W retry\nE gave up\n");
+    /// ring.dump_min_level(path, Level::Trace).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"D connecting\nW retry\nE gave up\nI exit\n");
+    /// ```
+    pub fn dump_min_level<P: AsRef<Path>>(&self, file_path: P, min: Level) -> std::io::Result<()> {
+        self.dump_filtered(file_path, move |line| {
+            let line = strip_thread_name(strip_time_prefixes(line));
+            Level::from_prefix(line).is_some_and(|l| l >= min)
+        })
+    }
+
+    /// Dump the records of each level into `dir/{level}.log`, e.g. `error.log`, and all the
+    /// lines into `dir/all.log`, in order, to find the important records without searching the
+    /// whole ring. `dir` is created if missing.
+    ///
+    /// The records are matched as with [RingFile::dump_min_level()], the lines without a level,
+    /// such as plain writes, are only in `all.log`. A file is written for each level, empty
+    /// when the ring has no record of the level. Returns the paths from `error.log` down to
+    /// `trace.log`, then `all.log`, or an empty Vec for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{Level, RingFileBuilder};
+    /// use std::path::Path;
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_by_level.store").into())
+    ///     .thread_names(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write_leveled(Level::Info, b"started\n".to_vec());
+    /// ring.write_leveled(Level::Error, b"disk failed\n".to_vec());
+    /// ring.write("plain\n".to_string());
+    /// ring.write_leveled(Level::Warn, b"retry\n".to_vec());
+    /// ring.write_leveled(Level::Error, b"gave up\n".to_vec());
+    /// let dir = Path::new("/tmp/ringfile_by_level");
+    /// let files = ring.dump_by_level(dir).expect("dump ok");
+    /// let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap().to_str().unwrap()).collect();
+    /// assert_eq!(names, ["error.log", "warn.log", "info.log", "debug.log", "trace.log", "all.log"]);
+    /// let read = |name: &str| {
+    ///     let content = std::fs::read_to_string(dir.join(name)).unwrap();
+    ///     content.lines().map(|l| l.split("] ").last().unwrap().to_string()).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(read("error.log"), ["E disk failed", "E gave up"]);
+    /// assert_eq!(read("warn.log"), ["W retry"]);
+    /// assert_eq!(read("info.log"), ["I started"]);
+    /// assert!(read("debug.log").is_empty());
+    /// assert_eq!(read("all.log"), ["I started", "E disk failed", "plain", "W retry", "E gave up"]);
+    /// ```
+    pub fn dump_by_level(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        if self.is_noop() {
+            return Ok(Vec::new());
+        }
+        let dir: Box<Path> = dir.into();
+        self.inspect(move |buffer| {
+            std::fs::create_dir_all(&dir)?;
+            let levels = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+            let mut paths: Vec<PathBuf> =
+                levels.iter().map(|level| dir.join(format!("{}.log", level.name()))).collect();
+            paths.push(dir.join("all.log"));
+            let mut files = Vec::with_capacity(paths.len());
+            for path in paths.iter() {
+                files.push(BufWriter::new(create_file(path, buffer.dump_mode())?));
+            }
+            for line in buffer.lines() {
+                let line = trim_filler(&line);
+                if let Some(level) =
+                    Level::from_prefix(strip_thread_name(strip_time_prefixes(line)))
+                {
+                    let i = levels.iter().position(|l| *l == level).expect("all levels");
+                    files[i].write_all(line)?;
+                }
+                files[levels.len()].write_all(line)?;
+            }
+            for file in files.iter_mut() {
+                file.flush()?;
+            }
+            Ok(paths)
+        })?
+    }
+
+    /// Dump only the lines stamped within `[start, end]` by [RingFileBuilder::timestamps()], in order.
+    ///
+    /// A line without timestamp (e.g. the continuation of a multi-line message) takes the time
+    /// of the previous line, and is skipped when no line before it has a timestamp.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::{Duration, SystemTime};
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_time_range.store").into())
+    ///     .timestamps(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// let pause = || std::thread::sleep(Duration::from_millis(20));
+    /// ring.write("before\n".to_string());
+    /// pause();
+    /// let start = SystemTime::now();
+    /// ring.write("first\n".to_string());
+    /// ring.write("second\ncontinued\n".to_string());
+    /// ring.flush();
+    /// let end = SystemTime::now();
+    /// pause();
+    /// ring.write("after\n".to_string());
+    /// let path = Path::new("/tmp/ringfile_time_range.window");
+    /// ring.dump_time_range(path, start, end).expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let lines: Vec<&str> = content.lines().collect();
+    /// assert_eq!(lines.len(), 3);
+    /// assert!(lines[0].starts_with('[') && lines[0].ends_with("] first"));
+    /// assert!(lines[1].ends_with("] second"));
+    /// assert_eq!(lines[2], "continued");
+    /// ```
+    pub fn dump_time_range<P: AsRef<Path>>(
+        &self, file_path: P, start: SystemTime, end: SystemTime,
+    ) -> std::io::Result<()> {
+        let last = std::cell::Cell::new(None);
+        self.dump_filtered(file_path, move |line| {
+            if let Some((time, _)) = parse_timestamp(line) {
+                last.set(Some(time));
+            }
+            last.get().is_some_and(|time| time >= start && time <= end)
+        })
+    }
+
+    /// Dump the lines prefixed with the time elapsed since the previous line, e.g.
+    /// `+0.000123s [+1234567] message`, so the biggest gaps reveal where the execution paused.
+    ///
+    /// The time is taken from [RingFileBuilder::uptime()] when enabled, as it is monotonic,
+    /// otherwise from [RingFileBuilder::timestamps()]. The first line with a time gets `+0.000000s`.
+    /// A line without time (e.g. the continuation of a multi-line message, or the leading line cut by
+    /// the rewinding) is copied as is.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let ring = RingFileBuilder::new(128, Path::new("/tmp/ringfile_deltas.store").into())
+    ///     .uptime(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..10 {
+    ///     if i == 7 {
+    ///         std::thread::sleep(Duration::from_millis(30));
+    ///     }
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// let path = Path::new("/tmp/ringfile_deltas.txt");
+    /// ring.dump_with_deltas(path).expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let mut prev: Option<u64> = None;
+    /// let mut gaps = Vec::new();
+    /// for line in content.lines().filter(|line| line.starts_with('+')) {
+    ///     let (delta, rest) = line.split_at(line.find(' ').unwrap());
+    ///     let uptime: u64 = rest[3..rest.find(']').unwrap()].parse().unwrap();
+    ///     let expected = prev.map(|prev| uptime - prev).unwrap_or(0);
+    ///     let micros = format!("{:06}", expected % 1_000_000_000 / 1_000);
+    ///     assert_eq!(delta, format!("+{}.{}s", expected / 1_000_000_000, micros));
+    ///     gaps.push((expected, rest.to_string()));
+    ///     prev = Some(uptime);
+    /// }
+    /// // The ring has rewinded, the leading line is cut
+    /// assert!(gaps.len() < 10);
+    /// assert_eq!(gaps[0].0, 0);
+    /// let longest = gaps.iter().max_by_key(|(gap, _)| *gap).unwrap();
+    /// assert!(longest.0 >= 30_000_000);
+    /// assert!(longest.1.ends_with("] line 7"));
+    /// ```
+    pub fn dump_with_deltas<P: AsRef<Path>>(&self, file_path: P) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let file_path: Box<Path> = file_path.as_ref().into();
+        self.inspect(move |buffer| {
+            let mut file = BufWriter::new(create_file(&file_path, buffer.dump_mode())?);
+            let mut prev = None;
+            let mut prefix = Vec::with_capacity(32);
+            for line in buffer.lines() {
+                let line = trim_filler(&line);
+                if let Some(nanos) = line_nanos(line) {
+                    prefix.clear();
+                    write_delta(&mut prefix, nanos - prev.unwrap_or(nanos));
+                    file.write_all(&prefix)?;
+                    prev = Some(nanos);
+                }
+                file.write_all(line)?;
+            }
+            file.flush()
+        })?
+    }
+
+    /// Dump the lines grouped into one section per thread, named by [RingFileBuilder::thread_names()].
+    ///
+    /// Each section starts with a header `=== thread {name}: {bytes} bytes ===`, followed by the lines
+    /// of that thread in order. The sections are ordered by the first line of each thread kept in
+    /// the ring. A line without the name (e.g. the continuation of a multi-line message) goes with
+    /// the previous line, the leading lines without any name go to a section named `?`.
+    ///
+    /// The merged dump() is better to follow the interaction between the threads, e.g. around a
+    /// deadlock, while the grouped one is easier to follow what each thread was doing,
+    /// e.g. to find which worker stopped making progress.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_grouped.store").into())
+    ///     .thread_names(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// let ring = Arc::new(ring);
+    /// for name in ["worker-a", "worker-b"] {
+    ///     let ring = ring.clone();
+    ///     std::thread::Builder::new()
+    ///         .name(name.to_string())
+    ///         .spawn(move || {
+    ///             for i in 0..2 {
+    ///                 ring.write(format!("step {}\n", i));
+    ///             }
+    ///         })
+    ///         .unwrap()
+    ///         .join()
+    ///         .unwrap();
+    /// }
+    /// let path = Path::new("/tmp/ringfile_grouped.txt");
+    /// ring.dump_grouped(path).expect("dump ok");
+    /// assert_eq!(
+    ///     std::fs::read_to_string(path).unwrap(),
+    ///     "=== thread worker-a: 38 bytes ===\n\
+    ///      [@worker-a] step 0\n[@worker-a] step 1\n\
+    ///      === thread worker-b: 38 bytes ===\n\
+    ///      [@worker-b] step 0\n[@worker-b] step 1\n"
+    /// );
+    /// ```
+    pub fn dump_grouped<P: AsRef<Path>>(&self, file_path: P) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let file_path: Box<Path> = file_path.as_ref().into();
+        self.inspect(move |buffer| {
+            // (name, content) of each thread, only a few threads expected
+            let mut groups: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+            let mut current = None;
+            for line in buffer.lines() {
+                let line = trim_filler(&line);
+                if let Some((name, _)) = parse_thread_name(strip_time_prefixes(line)) {
+                    current = match groups.iter().position(|(n, _)| n == name) {
+                        Some(i) => Some(i),
+                        None => {
+                            groups.push((name.to_vec(), Vec::new()));
+                            Some(groups.len() - 1)
+                        }
+                    };
+                }
+                let i = *current.get_or_insert_with(|| {
+                    groups.push((b"?".to_vec(), Vec::new()));
+                    groups.len() - 1
+                });
+                groups[i].1.extend_from_slice(line);
+            }
+            let mut file = BufWriter::new(create_file(&file_path, buffer.dump_mode())?);
+            for (name, content) in groups.iter() {
+                file.write_all(b"=== thread ")?;
+                file.write_all(name)?;
+                writeln!(file, ": {} bytes ===", content.len())?;
+                file.write_all(content)?;
+            }
+            file.flush()
+        })?
+    }
+
+    /// Trigger dump to the disk.
+    ///
+    /// An acknowledged dump is a consistent snapshot: it holds every message sent before the
+    /// call on the same thread, and for each writer, a run of its messages in order, of which
+    /// only the oldest may have been overwritten.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::sync::{Arc, Mutex};
+    /// let path = Path::new("/tmp/ringfile_dump.store");
+    /// let ring = Arc::new(RingFile::new(4096, path.into()));
+    /// // Keep the file until it is read, the dumps still race with the writes of the others
+    /// let file = Arc::new(Mutex::new(()));
+    /// let writers: Vec<_> = (0..4)
+    ///     .map(|t| {
+    ///         let (ring, file) = (ring.clone(), file.clone());
+    ///         std::thread::spawn(move || {
+    ///             for i in 0..400 {
+    ///                 let line = format!("writer {} seq {}\n", t, i);
+    ///                 if i % 8 != 7 {
+    ///                     ring.write(line);
+    ///                     continue;
+    ///                 }
+    ///                 // Under the lock, the others write at most 7 lines each before the dump,
+    ///                 // so this line is not overwritten yet
+    ///                 let _file = file.lock().unwrap();
+    ///                 ring.write(line);
+    ///                 ring.dump().expect("dump ok");
+    ///                 let content = std::fs::read_to_string(path).unwrap();
+    ///                 let mut last = [None; 4];
+    ///                 // The first line may be cut by the overwrite
+    ///                 for line in content.lines().skip(1) {
+    ///                     let v: Vec<usize> =
+    ///                         line.split(' ').filter_map(|w| w.parse().ok()).collect();
+    ///                     let (w, seq) = (v[0], v[1]);
+    ///                     if let Some(prev) = last[w] {
+    ///                         assert_eq!(seq, prev + 1, "writer {} in {:?}", w, content);
+    ///                     }
+    ///                     last[w] = Some(seq);
+    ///                 }
+    ///                 assert_eq!(last[t], Some(i));
+    ///             }
+    ///         })
+    ///     })
+    ///     .collect();
+    /// for th in writers {
+    ///     th.join().unwrap();
+    /// }
+    /// ```
+    pub fn dump(&self) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(Msg::Dump)?
+    }
+
+    /// Same as [RingFile::dump()], for a custom trigger like a deadlock watchdog, subject to
+    /// [RingFileBuilder::max_dumps_per()]. Return false when skipped over the limit.
+    ///
+    /// Noop handle returns Ok(false).
+    pub fn trigger_dump(&self) -> std::io::Result<bool> {
+        if self.is_noop() {
+            return Ok(false);
+        }
+        self.call(Msg::TriggerDump)?
+    }
+
+    /// Make everything written so far durable, for the shutdown path of a logging framework:
+    /// drain the messages queued before this call, dump, and fsync the dump file
+    /// (and the live file, see [RingFileBuilder::live_file()]) as one ordered operation.
+    ///
+    /// Blocks until the data reaches the disk. Every write() returned before this call
+    /// is in the dump; writes racing with the call may or may not be.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::collections::HashSet;
+    /// use std::path::Path;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// let path = Path::new("/tmp/ringfile_sync_dump.store");
+    /// let ring = Arc::new(RingFile::new(1024 * 1024, path.into()));
+    /// let done: Arc<Vec<AtomicUsize>> = Arc::new((0..4).map(|_| AtomicUsize::new(0)).collect());
+    /// let writers: Vec<_> = (0..4)
+    ///     .map(|t| {
+    ///         let (ring, done) = (ring.clone(), done.clone());
+    ///         std::thread::spawn(move || {
+    ///             for i in 1..=2000 {
+    ///                 ring.write(format!("{} {}\n", t, i));
+    ///                 done[t].store(i, Ordering::Release);
+    ///             }
+    ///         })
+    ///     })
+    ///     .collect();
+    /// std::thread::sleep(std::time::Duration::from_millis(1));
+    /// let returned: Vec<usize> = done.iter().map(|n| n.load(Ordering::Acquire)).collect();
+    /// ring.sync_dump().expect("sync_dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let lines: HashSet<&str> = content.lines().collect();
+    /// for (t, n) in returned.iter().enumerate() {
+    ///     for i in 1..=*n {
+    ///         assert!(lines.contains(format!("{} {}", t, i).as_str()));
+    ///     }
+    /// }
+    /// for th in writers {
+    ///     th.join().unwrap();
+    /// }
+    /// ```
+    pub fn sync_dump(&self) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(Msg::SyncDump)?
+    }
+
+    /// Dump only the content written since the previous call (or since the last clear),
+    /// so that periodic dumps of a large ring stay cheap. The sticky banner and footer are not
+    /// included.
+    ///
+    /// If some of the new content has been overwritten in between, a line
+    /// `[ring-file: N bytes lost since last dump]` is written before the content available.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_incremental.store");
+    /// let ring = RingFile::new(16, path.into());
+    /// ring.write("line 0\n".to_string());
+    /// ring.dump_incremental(path).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 0\n");
+    /// // No new data
+    /// ring.dump_incremental(path).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"");
+    /// ring.write("line 1\n".to_string());
+    /// ring.dump_incremental(path).expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 1\n");
+    /// // Wrapped past the last dump
+    /// for i in 2..6 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.dump_incremental(path).expect("dump ok");
+    /// let content = std::fs::read(path).unwrap();
+    /// assert_eq!(content, b"[ring-file: 12 bytes lost since last dump]\n3\nline 4\nline 5\n");
+    /// ```
+    pub fn dump_incremental<P: AsRef<Path>>(&self, file_path: P) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(|res| Msg::DumpIncremental(file_path.as_ref().into(), res))?
+    }
+
+    /// Return the number of bytes dump() would write at this moment, including the sticky banner,
+    /// so that the free space can be checked first.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_dump_size.store");
+    /// let ring = RingFileBuilder::new(16, path.into())
+    ///     .sticky_banner("banner\n".to_string())
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("0123456789abcdef0123456789\n".to_string());
+    /// let size = ring.dump_size().expect("backend alive");
+    /// assert_eq!(size, 7 + 16);
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::metadata(path).unwrap().len(), size as u64);
+    /// ```
+    pub fn dump_size(&self) -> std::io::Result<usize> {
+        if self.is_noop() {
+            return Ok(0);
+        }
+        self.call(Msg::DumpSize)
+    }
+
+    /// Return how much wall-clock time the buffer covers, see [RingBuffer::time_span()],
+    /// e.g. to check the buffer is large enough to hold the history of an incident.
+    ///
+    /// Requires [RingFileBuilder::timestamps()]. None for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let ring = RingFileBuilder::new(4096, Path::new("/tmp/ringfile_time_span.store").into())
+    ///     .timestamps(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("first\n".to_string());
+    /// assert_eq!(ring.time_span().unwrap(), None);
+    /// std::thread::sleep(Duration::from_millis(50));
+    /// ring.write("second\n".to_string());
+    /// assert!(ring.time_span().unwrap().unwrap() >= Duration::from_millis(50));
+    /// ```
+    pub fn time_span(&self) -> std::io::Result<Option<Duration>> {
+        if self.is_noop() {
+            return Ok(None);
+        }
+        self.inspect(|buffer| buffer.time_span())
+    }
+
+    /// Reallocate the buffer to `buf_size` on the backend, keeping the newest content that
+    /// fits, see [RingBuffer::resize()].
+    ///
+    /// The memory peaks at the old plus the new size during the copy. A pending
+    /// [RingFile::boost()] is cancelled, `buf_size` stays. Returns error of
+    /// `ErrorKind::InvalidInput` when `buf_size` is not a multiple of [RingFileBuilder::align()].
+    /// Noop handle returns Ok.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_resize.store");
+    /// let ring = RingFileBuilder::new(16, path.into()).build().expect("build ok");
+    /// ring.resize(1024).expect("resize ok");
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read_to_string(path).unwrap().lines().count(), 10);
+    /// ring.resize(14).expect("resize ok");
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 8\nline 9\n");
+    /// ```
+    pub fn resize(&self, buf_size: i32) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(|res| Msg::Resize(buf_size, res))?
+    }
+
+    /// Resize the buffer to `buf_size` now, and back to the size before after `duration`,
+    /// for a deep capture of a suspect window without keeping the memory afterward.
+    ///
+    /// Same as [RingFile::resize()] twice; the shrink back keeps the newest content that fits.
+    /// Both buffers are allocated during each copy, the memory peaks at the sum of the two
+    /// sizes, e.g. 1.1GB for a boost from 100MB to 1GB. Another boost while boosted replaces
+    /// the size and the deadline, and still shrinks back to the size before the first.
+    ///
+    /// The shrink is timed by the backend thread: returns error of `ErrorKind::Unsupported`
+    /// with [RingFileBuilder::inline()], [RingFileBuilder::build_task()] and
+    /// [RingFileBuilder::build_detached()] until the driver runs. Noop handle returns Ok.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let path = Path::new("/tmp/ringfile_boost.store");
+    /// let ring = RingFileBuilder::new(14, path.into()).build().expect("build ok");
+    /// ring.boost(4096, Duration::from_millis(200)).expect("boost ok");
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read_to_string(path).unwrap().lines().count(), 10);
+    /// std::thread::sleep(Duration::from_millis(400));
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 8\nline 9\n");
+    /// ```
+    pub fn boost(&self, buf_size: i32, duration: Duration) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(|res| Msg::Boost(buf_size, duration, res))?
+    }
+
+    /// Same as [RingFile::dump()], but across the files `{prefix}.part000`, `{prefix}.part001`,
+    /// ... of at most `part_size` bytes each, see [RingBuffer::dump_split()].
+    ///
+    /// Return the files written in order, empty for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_file_split.store");
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .sticky_banner("banner\n".to_string())
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..100 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// let parts = ring.dump_split(path, 100).expect("dump ok");
+    /// assert_eq!(parts.len(), 8);
+    /// let joined: Vec<u8> = parts.iter().flat_map(|part| std::fs::read(part).unwrap()).collect();
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(joined, std::fs::read(path).unwrap());
+    /// ```
+    pub fn dump_split<P: AsRef<Path>>(
+        &self, prefix: P, part_size: usize,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        if self.is_noop() {
+            return Ok(Vec::new());
+        }
+        self.call(|res| Msg::DumpSplit(prefix.as_ref().into(), part_size, res))?
+    }
+
+    /// Trigger dump to the disk without waiting, return a handle to get the result later.
+    ///
+    /// Every request to the backend carries its own reply channel, so any number of dumps can be
+    /// in flight, each handle receives the result of its own dump. The dumps are done in the order
+    /// of the calls, with the messages sent before each call.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let ring = RingFile::new(1024, Path::new("/tmp/ringfile_dump_nowait.store").into());
+    /// let mut pending = Vec::new();
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    ///     pending.push(ring.dump_nowait().expect("send ok"));
+    /// }
+    /// for p in pending {
+    ///     p.wait().expect("dump ok");
+    /// }
+    /// let content = std::fs::read_to_string("/tmp/ringfile_dump_nowait.store").unwrap();
+    /// assert_eq!(content.lines().count(), 10);
+    /// ```
+    pub fn dump_nowait(&self) -> std::io::Result<PendingDump> {
+        if self.is_noop() {
+            return Ok(PendingDump { rx: None });
+        }
+        let (res_tx, res_rx) = sync_channel(1);
+        self.send(Msg::Dump(res_tx))?;
+        Ok(PendingDump { rx: Some(res_rx) })
+    }
+
+    /// Send the dump to a collector at `addr` over TCP, for the hosts without a disk to keep it.
+    ///
+    /// The connection is opened on the caller's thread, trying each address of `addr` in turn.
+    /// The backend then sends the header line `ring-file-dump {size} run={id} labels={labels}`,
+    /// with the byte size of the content, [RingFileBuilder::run_id()] and the `key=value` of
+    /// [RingFileBuilder::label()] joined by `,` (`-` for none), followed by the content as
+    /// dump() writes it, and shuts the connection down for writing. Both the connection and each
+    /// write time out after [RingFileBuilder::tcp_timeout()], the error names the address.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::io::{BufRead, BufReader, Read};
+    /// use std::net::TcpListener;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_tcp.store").into())
+    ///     .run_id("run-1".to_string())
+    ///     .label("host".to_string(), "web-1".to_string())
+    ///     .tcp_timeout(Duration::from_secs(5))
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..3 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// // The connection waits in the backlog of the listener, and the dump fits in its buffer
+    /// ring.dump_to_tcp(addr).expect("dump ok");
+    /// listener.set_nonblocking(true).unwrap();
+    /// let (stream, _) = listener.accept().expect("dump sent");
+    /// drop(listener);
+    /// stream.set_nonblocking(false).unwrap();
+    /// let mut reader = BufReader::new(stream);
+    /// let mut header = String::new();
+    /// reader.read_line(&mut header).unwrap();
+    /// let mut content = Vec::new();
+    /// reader.read_to_end(&mut content).unwrap();
+    /// assert_eq!(header, format!("ring-file-dump {} run=run-1 labels=host=web-1\n", content.len()));
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(content, std::fs::read("/tmp/ringfile_tcp.run-1.store").unwrap());
+    /// // Nobody listening any more
+    /// let err = ring.dump_to_tcp(addr).unwrap_err();
+    /// assert!(err.to_string().contains(&addr.to_string()));
+    /// ```
+    pub fn dump_to_tcp<A: ToSocketAddrs>(&self, addr: A) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let stream = connect_tcp(addr, self.tcp_timeout)?;
+        self.call(|res| Msg::DumpTcp(stream, res))?
+    }
+
+    /// Dump to the disk and then clear the buffer, as one operation of the backend.
+    ///
+    /// Messages are either in this dump or kept after the clear, never lost in between.
+    /// The buffer is kept when the dump fails.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// let path = Path::new("/tmp/ringfile_dump_and_clear.store");
+    /// let ring = Arc::new(RingFile::new(1024 * 1024, path.into()));
+    /// let mut ths = Vec::new();
+    /// for i in 0..4 {
+    ///     let ring = ring.clone();
+    ///     ths.push(std::thread::spawn(move || {
+    ///         for j in 0..1000 {
+    ///             ring.write(format!("{}-{}\n", i, j));
+    ///         }
+    ///     }));
+    /// }
+    /// ring.dump_and_clear().expect("dump ok");
+    /// let first = std::fs::read_to_string(path).unwrap();
+    /// for th in ths {
+    ///     th.join().unwrap();
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// let second = std::fs::read_to_string(path).unwrap();
+    /// let mut lines: Vec<&str> = first.lines().chain(second.lines()).collect();
+    /// assert_eq!(lines.len(), 4000);
+    /// lines.sort();
+    /// lines.dedup();
+    /// assert_eq!(lines.len(), 4000);
+    ///
+    /// // The dump can not be written, nothing is cleared
+    /// let ring = RingFile::new(1024, Path::new("/nonexistent/ringfile_dump_and_clear.store").into());
+    /// ring.write("kept\n".to_string());
+    /// assert!(ring.dump_and_clear().is_err());
+    /// let (buffer, _) = ring.snapshot_with_stats().expect("snapshot ok");
+    /// assert_eq!(buffer.read_to_vec(), b"kept\n");
+    /// ```
+    pub fn dump_and_clear(&self) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(Msg::DumpAndClear)?
+    }
+
+    #[inline(always)]
+    pub fn write(&self, content: String) {
+        let _ = self.send_line(None, content.into_bytes());
+    }
+
+    /// Write the history kept by the spill (see [RingFileBuilder::spill()] and
+    /// [RingFileBuilder::spill_gz()]), followed by the content of the ring, into `output`.
+    ///
+    /// Without spill, this is the same as a dump to `output` without the sticky banner.
+    pub fn reconstruct(&self, output: &Path) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(|res| Msg::Reconstruct(output.into(), res))?
+    }
+
+    /// Write `content` tagged with `level`, see [Level] for the format.
+    #[inline]
+    pub fn write_leveled(&self, level: Level, content: Vec<u8>) {
+        let _ = self.send_line(Some(level), content);
+    }
+
+    /// Write `content` to the buffer of `category` instead of the ring, with the same prefixes,
+    /// for [RingFile::dump_by_category()].
+    ///
+    /// The buffer of a category is created on first use, of the size set by
+    /// [RingFileBuilder::categories()], which also bounds the number of categories: a new
+    /// category over the limit drops the buffer of the least recently written one.
+    /// The characters of `category` not safe in a file name are replaced by `_`.
+    #[inline]
+    pub fn write_category(&self, category: &str, content: Vec<u8>) {
+        if self.is_noop() {
+            return;
+        }
+        let _ = self.send_to(Some(category_name(category)), None, content);
+    }
+
+    /// Write the buffer of each category (see [RingFile::write_category()]) to
+    /// `dir/{category}.log`, creating `dir` if missing. Return the paths written, from the least
+    /// recently written category.
+    ///
+    /// The files only hold the records of their category, the ring is dumped by dump().
+    /// clear() does not affect the categories. Returns an empty Vec for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_category.store");
+    /// let ring = RingFileBuilder::new(1024, path.into())
+    ///     .categories(2, 1024)
+    ///     .build()
+    ///     .expect("build ok");
+    /// ring.write("main\n".to_string());
+    /// ring.write_category("net", b"connect\n".to_vec());
+    /// ring.write_category("disk", b"open\n".to_vec());
+    /// ring.write_category("net", b"send\n".to_vec());
+    /// let dir = Path::new("/tmp/ringfile_category");
+    /// let _ = std::fs::remove_dir_all(dir);
+    /// let files = ring.dump_by_category(dir).expect("dump ok");
+    /// assert_eq!(files, [dir.join("disk.log"), dir.join("net.log")]);
+    /// assert_eq!(std::fs::read(dir.join("net.log")).unwrap(), b"connect\nsend\n");
+    /// assert_eq!(std::fs::read(dir.join("disk.log")).unwrap(), b"open\n");
+    /// // Over the limit, the least recently written "disk" is dropped
+    /// ring.write_category("gpu/0", b"launch\n".to_vec());
+    /// let files = ring.dump_by_category(dir).expect("dump ok");
+    /// assert_eq!(files, [dir.join("net.log"), dir.join("gpu_0.log")]);
+    /// assert_eq!(std::fs::read(dir.join("gpu_0.log")).unwrap(), b"launch\n");
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"main\n");
+    /// ```
+    pub fn dump_by_category(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        if self.is_noop() {
+            return Ok(Vec::new());
+        }
+        self.call(|res| Msg::DumpByCategory(dir.into(), res))?
+    }
+
+    /// Return the total bytes of the messages sent but not yet taken by the backend,
+    /// including the prefixes. Always 0 without the backend thread.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_queued_bytes.store");
+    /// let (ring, driver) = RingFileBuilder::new(1024 * 1024, path.into())
+    ///     .max_queued_bytes(100_000)
+    ///     .build_detached()
+    ///     .expect("build ok");
+    /// // The backend is not running yet, so the messages stay queued
+    /// ring.write("small\n".to_string());
+    /// let large = "x".repeat(40_000) + "\n";
+    /// for _ in 0..4 {
+    ///     ring.write(large.clone());
+    /// }
+    /// // The byte limit trips with only 3 messages queued
+    /// assert_eq!(ring.queued_bytes(), 6 + 2 * large.len());
+    /// assert_eq!(ring.queue_overflowed(), 2);
+    /// ring.write("small again\n".to_string());
+    /// let th = std::thread::spawn(move || driver.run());
+    /// ring.flush();
+    /// assert_eq!(ring.queued_bytes(), 0);
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let trailer = "[ring-file: 2 messages dropped on queue overflow]\n";
+    /// assert_eq!(content.len(), 6 + 2 * large.len() + 12 + trailer.len());
+    /// ring.shutdown();
+    /// th.join().unwrap();
+    /// ```
+    #[inline]
+    pub fn queued_bytes(&self) -> usize {
+        self.backlog.as_ref().map(|backlog| backlog.bytes()).unwrap_or(0)
+    }
+
+    /// Return the number of messages sent but not yet taken by the backend, like
+    /// [RingFile::queued_bytes()]. Always 0 without the backend thread.
+    #[inline]
+    pub fn queue_len(&self) -> usize {
+        self.backlog.as_ref().map(|backlog| backlog.messages()).unwrap_or(0)
+    }
+
+    /// Return the most messages ever queued at once, since build() or
+    /// [RingFile::reset_peak_queue_len()], to size [RingFileBuilder::max_queued_bytes()] or
+    /// the channel after a run. Always 0 without the backend thread.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_peak_queue.store");
+    /// let (ring, driver) = RingFileBuilder::new(1024 * 1024, path.into())
+    ///     .build_detached()
+    ///     .expect("build ok");
+    /// // A burst while the backend is not running yet
+    /// for i in 0..10 {
+    ///     ring.write(format!("burst {}\n", i));
+    /// }
+    /// assert_eq!((ring.queue_len(), ring.peak_queue_len()), (10, 10));
+    /// let th = std::thread::spawn(move || driver.run());
+    /// ring.flush();
+    /// // Drained, the peak is kept
+    /// assert_eq!((ring.queue_len(), ring.peak_queue_len()), (0, 10));
+    /// ring.reset_peak_queue_len();
+    /// assert_eq!(ring.peak_queue_len(), 0);
+    /// ring.write("after\n".to_string());
+    /// ring.flush();
+    /// assert_eq!(ring.peak_queue_len(), 1);
+    /// ring.shutdown();
+    /// th.join().unwrap();
+    /// ```
+    #[inline]
+    pub fn peak_queue_len(&self) -> usize {
+        self.backlog.as_ref().map(|backlog| backlog.peak()).unwrap_or(0)
+    }
+
+    /// Restart [RingFile::peak_queue_len()] from the messages queued now.
+    #[inline]
+    pub fn reset_peak_queue_len(&self) {
+        if let Some(backlog) = self.backlog.as_ref() {
+            backlog.reset_peak();
+        }
+    }
+
+    /// Return the number of messages dropped by [RingFileBuilder::max_queued_bytes()].
+    #[inline]
+    pub fn queue_overflowed(&self) -> u64 {
+        self.backlog.as_ref().map(|backlog| backlog.dropped()).unwrap_or(0)
+    }
+
+    /// Return the number of dumps of the triggers skipped by [RingFileBuilder::max_dumps_per()].
+    #[inline]
+    pub fn dumps_throttled(&self) -> u64 {
+        self.dumps_throttled.as_ref().map(|n| n.load(Ordering::Relaxed)).unwrap_or(0)
+    }
+
+    /// Return the number of messages dropped by [RingFileBuilder::adaptive_sample()].
+    #[inline]
+    pub fn sampled_out(&self) -> u64 {
+        self.sampler.as_ref().map(|sampler| sampler.dropped()).unwrap_or(0)
+    }
+
+    /// Keep `content` out of the ring, and write it at the end of every dump,
+    /// e.g. the reason detected by a deadlock watchdog. Only the latest footer is kept.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_footer.store");
+    /// let ring = RingFile::new(16, path.into());
+    /// ring.write_sticky_footer(b"reason: first\n".to_vec());
+    /// ring.write_sticky_footer(b"reason: deadlock\n".to_vec());
+    /// for i in 0..100 {
+    ///     ring.write(format!("line {:02}\n", i));
+    /// }
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 98\nline 99\nreason: deadlock\n");
+    /// ```
+    pub fn write_sticky_footer(&self, content: Vec<u8>) {
+        let _ = self.send(Msg::SetFooter(content));
+    }
+
+    /// Write `> name ThreadId(..)` now, and `< name ThreadId(..)` when the returned [Span] drops,
+    /// including on panic unwinding.
+    ///
+    /// In a dump, a span entered but never exited tells which critical section a thread was in
+    /// when the program hangs.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::panic::{catch_unwind, AssertUnwindSafe};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_span.store");
+    /// let ring = RingFile::new(1024, path.into());
+    /// let res = catch_unwind(AssertUnwindSafe(|| {
+    ///     let _outer = ring.span("outer");
+    ///     let _inner = ring.span("inner");
+    ///     panic!("in span");
+    /// }));
+    /// assert!(res.is_err());
+    /// let _held = ring.span("held");
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let markers: Vec<&str> = content.lines().map(|l| l.split(' ').next().unwrap()).collect();
+    /// assert_eq!(markers, vec![">", ">", "<", "<", ">"]);
+    /// assert!(content.ends_with(&format!("> held {:?}\n", std::thread::current().id())));
+    /// ```
+    pub fn span(&self, name: &str) -> Span<'_, X> {
+        Span::enter(self, name)
+    }
+
+    /// Write the marker line `=== MARK: {name} @ {micros}us ===` now, with the microseconds
+    /// since UNIX epoch, to find a moment of the repro in the dump, e.g. right before taking a lock.
+    ///
+    /// The marker goes through the same pipeline as [RingFile::write()], so it is ordered with
+    /// the messages of the same thread and gets the same prefixes. Use
+    /// [Records::markers()](crate::Records::markers()) to jump between the markers in the records.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_mark.store");
+    /// let ring = RingFile::new(1024, path.into());
+    /// ring.write("starting\n".to_string());
+    /// ring.mark("before lock");
+    /// ring.write("locked\n".to_string());
+    /// ring.mark("after lock");
+    /// ring.write("done\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// let content = std::fs::read_to_string(path).unwrap();
+    /// let lines: Vec<&str> = content.lines().collect();
+    /// assert_eq!(lines[0], "starting");
+    /// assert!(lines[1].starts_with("=== MARK: before lock @ ") && lines[1].ends_with("us ==="));
+    /// assert_eq!(lines[2], "locked");
+    /// assert!(lines[3].starts_with("=== MARK: after lock @ "));
+    /// assert_eq!(lines[4], "done");
+    /// let buffer = ring.take_buffer().expect("take ok");
+    /// let markers: Vec<String> =
+    ///     buffer.records().markers().map(|r| r.marker().unwrap().to_string()).collect();
+    /// assert_eq!(markers, ["before lock", "after lock"]);
+    /// ```
+    pub fn mark(&self, name: &str) {
+        if self.is_noop() {
+            return;
+        }
+        self.write(format_mark(name, SystemTime::now()));
+    }
+
+    /// Take the current buffer, and let the backend continue with a new empty one.
+    ///
+    /// The backend only swaps the buffers without copying the content, the caller can then
+    /// inspect or dump the frozen buffer at leisure. Returns an empty buffer for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_take_buffer.store");
+    /// let ring = RingFile::new(1024, path.into());
+    /// ring.write("before\n".to_string());
+    /// let frozen = ring.take_buffer().expect("take ok");
+    /// ring.write("after\n".to_string());
+    /// assert_eq!(frozen.read_to_vec(), b"before\n");
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"after\n");
+    /// ```
+    pub fn take_buffer(&self) -> std::io::Result<RingBuffer> {
+        if self.is_noop() {
+            return Ok(empty_buffer());
+        }
+        self.call(Msg::TakeBuffer)?
+    }
+
+    /// Replace the content of the ring by `snapshot`, e.g. to start a replay test from a known
+    /// state, then keep on writing. The snapshot is the content as given by
+    /// [RingBuffer::read_to_vec()], or a previous dump.
+    ///
+    /// The backend swaps in a new buffer of the same settings, like
+    /// [RingBuffer::from_snapshot()], and only the newest buf_size bytes are kept.
+    /// In framed mode each line becomes a record. Writes from other threads are ordered against
+    /// the load in the order the backend receives the messages: those sent before the call
+    /// are replaced, those after are appended. The live and spill files are not affected.
+    /// Returns error with [RingFileBuilder::binary_uptime()].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_load_snapshot.store");
+    /// let ring = RingFile::new(1024, path.into());
+    /// ring.write("replaced\n".to_string());
+    /// ring.load_snapshot(b"captured 1\ncaptured 2\n").expect("load ok");
+    /// ring.write("replayed\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"captured 1\ncaptured 2\nreplayed\n");
+    /// ```
+    pub fn load_snapshot(&self, snapshot: &[u8]) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(|res| Msg::LoadSnapshot(snapshot.to_vec(), res))?
+    }
+
+    /// Same as [RingFile::take_buffer()], and return the [Stats] of the buffer taken
+    /// in the same backend message, so that the stats always match the content.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let ring = RingFile::new(8, Path::new("/tmp/ringfile_snapshot_stats.store").into());
+    /// ring.write("0123456789".to_string());
+    /// let (frozen, stats) = ring.snapshot_with_stats().expect("snapshot ok");
+    /// ring.write("after".to_string());
+    /// ring.flush();
+    /// // Neither the content nor the stats see the later write
+    /// assert_eq!(frozen.read_to_vec(), b"23456789");
+    /// assert_eq!(stats, frozen.stats());
+    /// assert_eq!((stats.len, stats.total_written, stats.wraps), (8, 10, 1));
+    /// ```
+    pub fn snapshot_with_stats(&self) -> std::io::Result<(RingBuffer, Stats)> {
+        if self.is_noop() {
+            let buffer = empty_buffer();
+            let stats = buffer.stats();
+            return Ok((buffer, stats));
+        }
+        self.call(Msg::Snapshot)?
+    }
+
+    /// Return the number of messages dropped or cut for `reason`, see [RingFileBuilder::on_drop()].
+    ///
+    /// Drops on the backend are counted once processed, call [RingFile::flush()] first to count
+    /// the messages already written.
+    pub fn drop_count(&self, reason: DropReason) -> u64 {
+        self.drops.as_ref().map(|drops| drops.count(reason)).unwrap_or(0)
+    }
+
+    /// Return the (max, avg) time the messages waited before the backend processed them,
+    /// over the last one to two seconds of messages. A growing latency shows the backend
+    /// can not keep up, e.g. slowed down by the dumps or an [RingFileBuilder::on_message()] hook.
+    ///
+    /// The time is measured from the prefix of [RingFileBuilder::uptime()] (or
+    /// [RingFileBuilder::binary_uptime()]), otherwise of [RingFileBuilder::timestamps()] at its
+    /// precision. Without any of them, or before the first message, return zeros.
+    /// The figures are published by the backend with every message, so reading them does not
+    /// wait for the backend.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_latency.store").into())
+    ///     .uptime(true)
+    ///     .build()
+    ///     .expect("build ok");
+    /// assert_eq!(ring.backend_latency(), (Duration::ZERO, Duration::ZERO));
+    /// ring.write("fast\n".to_string());
+    /// ring.flush();
+    /// let (fast, _) = ring.backend_latency();
+    /// // Each message takes 20ms on the backend, the last one waits for the 9 before it
+    /// let slow = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_latency.store").into())
+    ///     .uptime(true)
+    ///     .on_message(Box::new(|_| std::thread::sleep(Duration::from_millis(20))))
+    ///     .build()
+    ///     .expect("build ok");
+    /// for i in 0..10 {
+    ///     slow.write(format!("slow {}\n", i));
+    /// }
+    /// slow.flush();
+    /// let (max, avg) = slow.backend_latency();
+    /// assert!(max >= Duration::from_millis(150), "{:?}", max);
+    /// assert!(avg > Duration::ZERO && avg <= max);
+    /// assert!(max > fast, "{:?} {:?}", max, fast);
+    /// ```
+    #[inline]
+    pub fn backend_latency(&self) -> (Duration, Duration) {
+        self.latency.as_ref().map(|latency| latency.get()).unwrap_or_default()
+    }
+
+    /// Return the approximate rate of write() calls over the last second, to spot the moment
+    /// a service stalls. Every call is counted, including the messages dropped.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// let ring = RingFile::new(1024 * 1024, Path::new("/tmp/ringfile_rate.store").into());
+    /// assert_eq!(ring.writes_per_sec(), 0.0);
+    /// for i in 0..1000 {
+    ///     ring.write(format!("burst {}\n", i));
+    /// }
+    /// let spike = ring.writes_per_sec();
+    /// assert!(spike >= 500.0, "{}", spike);
+    /// assert!(ring.bytes_per_sec() >= spike * 8.0);
+    /// std::thread::sleep(Duration::from_millis(1300));
+    /// assert_eq!(ring.writes_per_sec(), 0.0);
+    /// assert_eq!(ring.bytes_per_sec(), 0.0);
+    /// ```
+    #[inline]
+    pub fn writes_per_sec(&self) -> f64 {
+        self.rate.as_ref().map(|rate| rate.per_sec().0).unwrap_or(0.0)
+    }
+
+    /// Return the approximate bytes per second of the messages written over the last second,
+    /// without the prefixes, see [RingFile::writes_per_sec()].
+    #[inline]
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.rate.as_ref().map(|rate| rate.per_sec().1).unwrap_or(0.0)
+    }
+
+    /// Return the total bytes of the messages submitted, without the prefixes, counted on the
+    /// caller's thread before the send, so that a watchdog can poll it without waiting for the
+    /// backend, and compare with an earlier value to tell a stalled subsystem.
+    ///
+    /// Like [RingFile::writes_per_sec()], the messages dropped afterwards by sampling or
+    /// overflow are counted, the writes while disarmed are not. See
+    /// [RingBuffer::total_written()] for the bytes the backend stored, with the prefixes.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// use std::sync::mpsc::channel;
+    /// let (release, wait) = channel::<()>();
+    /// let ring = RingFileBuilder::new(1024, Path::new("/tmp/ringfile_total_written.store").into())
+    ///     // Hold the backend on the first message
+    ///     .on_message(Box::new(move |_| {
+    ///         let _ = wait.recv();
+    ///     }))
+    ///     .build()
+    ///     .expect("build ok");
+    /// let before = ring.total_written();
+    /// assert_eq!(before, 0);
+    /// for i in 0..10 {
+    ///     ring.write(format!("message {}\n", i));
+    /// }
+    /// // Counted before the backend gets to the messages
+    /// assert_eq!(ring.total_written(), 100);
+    /// assert!(ring.total_written() > before);
+    /// drop(release);
+    /// ring.flush();
+    /// let (_, stats) = ring.snapshot_with_stats().expect("snapshot ok");
+    /// assert_eq!(stats.total_written, 100);
+    /// ```
+    #[inline]
+    pub fn total_written(&self) -> u64 {
+        self.rate.as_ref().map(|rate| rate.total()).unwrap_or(0)
+    }
+
+    /// Return the number of distinct threads that have written to the ring, to gauge the
+    /// concurrency at hang time. Threads which have exited are still counted.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// let ring = Arc::new(RingFile::new(1024, Path::new("/tmp/ringfile_writers.store").into()));
+    /// let threads: Vec<_> = (0..3)
+    ///     .map(|i| {
+    ///         let ring = ring.clone();
+    ///         std::thread::Builder::new()
+    ///             .name(format!("worker-{}", i))
+    ///             .spawn(move || {
+    ///                 for j in 0..10 {
+    ///                     ring.write(format!("{} {}\n", i, j));
+    ///                 }
+    ///             })
+    ///             .unwrap()
+    ///     })
+    ///     .collect();
+    /// for th in threads {
+    ///     th.join().unwrap();
+    /// }
+    /// assert_eq!(ring.writer_thread_count(), 3);
+    /// let mut names = ring.writer_thread_names();
+    /// names.sort();
+    /// assert_eq!(names, vec!["worker-0", "worker-1", "worker-2"]);
+    /// ```
+    #[inline]
+    pub fn writer_thread_count(&self) -> usize {
+        self.writers.as_ref().map(|writers| writers.count()).unwrap_or(0)
+    }
+
+    /// Return the names of the threads counted by [RingFile::writer_thread_count()], in the order
+    /// of their first write. An unnamed thread is listed as `ThreadId(N)`.
+    ///
+    /// Only the first 1024 threads are listed, so the list stays bounded with short-lived threads.
+    pub fn writer_thread_names(&self) -> Vec<String> {
+        self.writers.as_ref().map(|writers| writers.names()).unwrap_or_default()
+    }
+
+    /// Return the number of messages dropped by [Utf8Mode::Reject].
+    #[inline]
+    pub fn utf8_rejected(&self) -> u64 {
+        self.utf8_rejected.load(Ordering::Relaxed)
+    }
+
+    /// Start capturing the writes after [RingFile::disarm()] or
+    /// [RingFileBuilder::armed(false)](RingFileBuilder::armed()), clearing the buffer first
+    /// when `clear`, so the capture is exactly the activity after this call.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::{DropReason, RingFileBuilder};
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_arm.store");
+    /// let ring = RingFileBuilder::new(1024, path.into()).armed(false).build().expect("build ok");
+    /// assert!(!ring.is_armed());
+    /// ring.write("before arm\n".to_string());
+    /// ring.arm(true);
+    /// ring.write("after arm\n".to_string());
+    /// ring.disarm();
+    /// ring.write("after disarm\n".to_string());
+    /// assert_eq!(ring.drop_count(DropReason::Disarmed), 2);
+    /// ring.arm(false);
+    /// ring.write("armed again\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(
+    ///     std::fs::read_to_string(path).unwrap(),
+    ///     "after arm\narmed again\n[ring-file: 2 messages dropped while disarmed]\n"
+    /// );
+    /// ```
+    pub fn arm(&self, clear: bool) {
+        if self.is_noop() {
+            return;
+        }
+        if clear {
+            // Queued before any write seeing the armed state
+            self.clear();
+        }
+        self.armed.store(true, Ordering::Release);
+    }
+
+    /// Drop the writes immediately on the caller's thread until [RingFile::arm()], counted as
+    /// [DropReason::Disarmed]. Unlike a pause, nothing is buffered.
+    #[inline]
+    pub fn disarm(&self) {
+        self.armed.store(false, Ordering::Release);
+    }
+
+    /// Return false between [RingFile::disarm()] and [RingFile::arm()].
+    #[inline]
+    pub fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::Acquire)
+    }
+
+    /// Clear previous buffer
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// let ring = Arc::new(RingFile::new(4096, Path::new("/tmp/ringfile_clear.store").into()));
+    /// let (done_tx, done_rx) = std::sync::mpsc::channel();
+    /// for t in 0..4 {
+    ///     let (ring, done_tx) = (ring.clone(), done_tx.clone());
+    ///     std::thread::spawn(move || {
+    ///         for i in 0..200 {
+    ///             ring.write(format!("thread {} line {}\n", t, i));
+    ///             match (t + i) % 4 {
+    ///                 0 => ring.clear(),
+    ///                 1 => ring.dump().expect("dump ok"),
+    ///                 2 => drop(ring.dump_nowait().expect("dump ok")),
+    ///                 _ => assert!(ring.dump_size().unwrap() <= 4096),
+    ///             }
+    ///         }
+    ///         done_tx.send(()).unwrap();
+    ///     });
+    /// }
+    /// // Every reply reaches its own caller, none is left blocking the backend
+    /// for _ in 0..4 {
+    ///     done_rx.recv_timeout(Duration::from_secs(30)).expect("no deadlock");
+    /// }
+    /// ring.clear();
+    /// ring.write("last\n".to_string());
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read("/tmp/ringfile_clear.store").unwrap(), b"last\n");
+    /// ```
+    pub fn clear(&self) {
+        let _ = self.send(Msg::Clear);
+    }
+
+    /// Return the number of lines (messages) currently kept, in line mode.
+    ///
+    /// The oldest line partially overwritten is counted. Returns 0 if not in line mode.
+    pub fn line_count(&self) -> usize {
+        if self.is_noop() {
+            return 0;
+        }
+        self.call(Msg::LineCount).unwrap_or(0)
+    }
+
+    /// Keep at most `n` newest lines (messages) in line mode, independent of buf_size.
+    /// (0 for unlimited)
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFileBuilder;
+    /// use std::path::Path;
+    /// let path = Path::new("/tmp/ringfile_max_lines.store");
+    /// let ring = RingFileBuilder::new(1024, path.into()).line_mode(true).build().expect("build ok");
+    /// ring.set_max_lines(3);
+    /// for i in 0..10 {
+    ///     ring.write(format!("line {}\n", i));
+    /// }
+    /// assert_eq!(ring.line_count(), 3);
+    /// ring.dump().expect("dump ok");
+    /// assert_eq!(std::fs::read(path).unwrap(), b"line 7\nline 8\nline 9\n");
+    /// ```
+    pub fn set_max_lines(&self, n: usize) {
+        let _ = self.send(Msg::SetMaxLines(n));
+    }
+
+    /// Block until all the messages sent before this call are processed by the backend.
+    #[inline]
+    pub fn flush(&self) {
+        let _ = self.try_flush();
+    }
+
+    /// Same as [RingFile::flush()], return the error when the backend is shut down or dead.
+    fn try_flush(&self) -> std::io::Result<()> {
+        if self.is_noop() {
+            return Ok(());
+        }
+        self.call(Msg::Flush)
+    }
+
+    /// Stop the backend thread after processing the messages already sent, and wait for it to exit.
+    ///
+    /// Messages sent afterwards are rejected, see [RingFile] for the behavior of each method.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::io::Write;
+    /// use std::path::Path;
+    /// let ring = RingFile::new(1024, Path::new("/tmp/ringfile_shutdown.store").into());
+    /// ring.write("before shutdown\n".to_string());
+    /// ring.shutdown();
+    /// assert!(ring.is_shutdown());
+    /// ring.write("after shutdown\n".to_string());
+    /// assert!(Write::write(&mut &ring, b"after shutdown\n").is_err());
+    /// assert_eq!(Write::flush(&mut &ring).unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+    /// assert!(ring.dump().is_err());
+    /// ```
+    pub fn shutdown(&self) {
+        // Reject the later writes before the backend exits, instead of dropping them silently
+        self.shutdown.store(true, Ordering::Release);
+        if !self.is_noop() {
+            let _ = self.dispatch(Msg::Exit);
+        }
+        if let Some(th) = self.th.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = th.join();
+        }
+    }
+
+    /// Return false once the backend has exited, after [RingFile::shutdown()], or when it died
+    /// e.g. of a panic in the predicate of [RingFile::dump_filtered()]. Also false for the noop
+    /// handle. The flag is cleared when the backend is dropped, so shortly after the requests
+    /// in flight have failed.
+    ///
+    /// Requests to a dead backend return error of `ErrorKind::BrokenPipe` with the message
+    /// "RingFile backend is dead", instead of panicking. There is no retry, as the backend
+    /// is not restarted.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// let ring = RingFile::new(1024, Path::new("/tmp/ringfile_dead.store").into());
+    /// ring.write("log message\n".to_string());
+    /// assert!(ring.backend_alive());
+    /// // Kill the backend
+    /// let res = ring.dump_filtered("/tmp/ringfile_dead.filtered", |_| panic!("bad predicate"));
+    /// assert_eq!(res.unwrap_err().to_string(), "RingFile backend is dead");
+    /// let e = ring.dump().unwrap_err();
+    /// assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe);
+    /// assert_eq!(e.to_string(), "RingFile backend is dead");
+    /// // Cleared once the backend is dropped, just after the failed requests
+    /// for _ in 0..1000 {
+    ///     if !ring.backend_alive() {
+    ///         break;
+    ///     }
+    ///     std::thread::sleep(std::time::Duration::from_millis(1));
+    /// }
+    /// assert!(!ring.backend_alive());
+    /// ```
+    #[inline]
+    pub fn backend_alive(&self) -> bool {
+        self.alive.as_ref().is_some_and(|alive| alive.load(Ordering::Acquire))
+    }
+
+    /// Dump on any crash path: on panic of any thread, and when one of `signals` is received,
+    /// e.g. `[libc::SIGTERM, libc::SIGUSR1]`.
+    ///
+    /// The panic hook dumps synchronously before calling the previous hook, so the dump is
+    /// done before the thread unwinds or the process aborts. It holds a weak reference, and
+    /// does nothing once the RingFile is dropped. It skips the dump for a panic on the thread
+    /// running the backend, which can not serve it.
+    ///
+    /// The signal handler only counts the signal, which is async-signal-safe, and the backend
+    /// thread polls the counts every 100ms to dump. The handler replaces the default action,
+    /// so e.g. SIGTERM no longer terminates the process, the application decides what follows.
+    /// The faults like SIGSEGV can not be handled this way and return error of
+    /// `ErrorKind::InvalidInput`, as the handler would return to the faulting instruction.
+    ///
+    /// Platform differences:
+    /// - On unix, the signals are installed with `sigaction` and SA_RESTART.
+    /// - Elsewhere, a non-empty `signals` returns error of `ErrorKind::Unsupported`
+    ///   before installing anything, and the panic hook works as on unix.
+    /// - The signals also return `ErrorKind::Unsupported` with [RingFileBuilder::inline()]
+    ///   or [RingFileBuilder::build_task()], which have no backend thread to poll.
+    ///
+    /// Arming again is idempotent: the panic hook is installed once per RingFile, a signal
+    /// handler once per process, and the signals already watched are not added. The newly
+    /// given signals are added. No-op for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// let path = Path::new("/tmp/ringfile_crash.store");
+    /// let _ = std::fs::remove_file(path);
+    /// let ring = Arc::new(RingFile::new(1024, path.into()));
+    /// ring.clone().arm_crash_capture(&[]).expect("arm ok");
+    /// ring.clone().arm_crash_capture(&[]).expect("arm twice ok");
+    /// ring.write("before panic\n".to_string());
+    /// assert!(std::thread::spawn(|| panic!("boom")).join().is_err());
+    /// assert_eq!(std::fs::read(path).unwrap(), b"before panic\n");
+    ///
+    /// #[cfg(unix)]
+    /// {
+    ///     let path = Path::new("/tmp/ringfile_crash_signal.store");
+    ///     let _ = std::fs::remove_file(path);
+    ///     let ring = Arc::new(RingFile::new(1024, path.into()));
+    ///     ring.clone().arm_crash_capture(&[libc::SIGUSR1]).expect("arm ok");
+    ///     ring.clone().arm_crash_capture(&[libc::SIGUSR1]).expect("arm twice ok");
+    ///     let e = ring.clone().arm_crash_capture(&[libc::SIGSEGV]).unwrap_err();
+    ///     assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    ///     ring.write("before signal\n".to_string());
+    ///     ring.flush();
+    ///     // SAFETY: the handler is installed
+    ///     unsafe { libc::raise(libc::SIGUSR1) };
+    ///     for _ in 0..100 {
+    ///         if path.exists() {
+    ///             break;
+    ///         }
+    ///         std::thread::sleep(std::time::Duration::from_millis(20));
+    ///     }
+    ///     assert_eq!(std::fs::read(path).unwrap(), b"before signal\n");
+    /// }
+    /// ```
+    pub fn arm_crash_capture(self: Arc<Self>, signals: &[i32]) -> std::io::Result<()>
+    where
+        X: 'static,
+    {
+        if self.is_noop() {
+            return Ok(());
+        }
+        if !signals.is_empty() {
+            crash::install(signals)?;
+            self.call(|res| Msg::WatchSignals(signals.to_vec(), res))??;
+        }
+        if !self.crash_hook.swap(true, Ordering::AcqRel) {
+            let ring = Arc::downgrade(&self);
+            let prev = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                if let Some(ring) = ring.upgrade() {
+                    if !crash::in_backend() {
+                        let _ = ring.trigger_dump();
+                    }
+                }
+                prev(info);
+            }));
+        }
+        Ok(())
+    }
+
+    /// Tee the stderr of the process into the ring, so that what is printed to the console
+    /// (e.g. panic messages, or the output of a C library) shows inline with the messages.
+    ///
+    /// Each line is written as a message prefixed with `[stderr] `, taking the other prefixes
+    /// of the builder. The output still goes to the previous stderr as well.
+    /// A line longer than 4KB without newline is cut into several messages.
+    ///
+    /// **NOTE**: This is invasive and affects the whole process. File descriptor 2 is replaced by
+    /// a pipe, which a thread `ring-file-stderr` reads, so every write to stderr by any code goes
+    /// through the pipe, and is delayed until that thread forwards it. A process writing faster than
+    /// the thread forwards blocks on the full pipe. The redirection ends at the first output
+    /// after the RingFile is dropped, then stderr can be captured again.
+    ///
+    /// Only one ring can capture at a time, otherwise return error of `ErrorKind::AlreadyExists`.
+    /// Returns error of `ErrorKind::Unsupported` except on unix. No-op for the noop handle.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; }
+    /// use ring_file::RingFile;
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// let path = Path::new("/tmp/ringfile_stderr.store");
+    /// let ring = Arc::new(RingFile::new(4096, path.into()));
+    /// #[cfg(unix)]
+    /// {
+    ///     ring.clone().capture_stderr().expect("capture ok");
+    ///     let e = ring.clone().capture_stderr().unwrap_err();
+    ///     assert_eq!(e.kind(), std::io::ErrorKind::AlreadyExists);
+    ///     ring.write("logged\n".to_string());
+    ///     eprintln!("printed to stderr");
+    ///     let expected = b"logged\n[stderr] printed to stderr\n";
+    ///     for _ in 0..100 {
+    ///         ring.dump().expect("dump ok");
+    ///         if std::fs::read(path).unwrap() == expected {
+    ///             break;
+    ///         }
+    ///         std::thread::sleep(std::time::Duration::from_millis(20));
+    ///     }
+    ///     assert_eq!(std::fs::read(path).unwrap(), expected);
+    /// }
+    /// ```
+    pub fn capture_stderr(self: Arc<Self>) -> std::io::Result<()>
+    where
+        X: 'static,
+    {
+        if self.is_noop() {
+            return Ok(());
+        }
+        let (mut pipe, mut saved) = stderr::redirect()?;
+        // To restore stderr if the thread can not start
+        let fallback = match saved.try_clone() {
+            Ok(file) => file,
+            Err(e) => {
+                stderr::restore(&saved);
+                return Err(e);
+            }
+        };
+        let ring = Arc::downgrade(&self);
+        let forward = move || {
+            let mut chunk = [0u8; STDERR_CHUNK];
+            let mut pending: Vec<u8> = Vec::new();
+            loop {
+                let n = match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                };
+                let _ = saved.write_all(&chunk[0..n]);
+                let Some(ring) = ring.upgrade() else {
+                    break;
+                };
+                pending.extend_from_slice(&chunk[0..n]);
+                while let Some(i) = pending.iter().position(|b| *b == b'\n') {
+                    let rest = pending.split_off(i + 1);
+                    let line = std::mem::replace(&mut pending, rest);
+                    let _ = ring.send_line(None, [STDERR_TAG, &line].concat());
+                }
+                if pending.len() >= STDERR_CHUNK {
+                    let line = std::mem::take(&mut pending);
+                    let _ = ring.send_line(None, [STDERR_TAG, &line].concat());
+                }
+            }
+            stderr::restore(&saved);
+        };
+        if let Err(e) = thread::Builder::new().name("ring-file-stderr".to_string()).spawn(forward) {
+            stderr::restore(&fallback);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Return true after [RingFile::shutdown()] is called.
+    #[inline]
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire)
+    }
+}
+
+/// Append the backtrace of the caller after the message, see [RingFileBuilder::backtrace()].
+#[cfg(feature = "backtrace")]
+fn append_backtrace(line: &mut Vec<u8>) {
+    if !line.ends_with(b"\n") {
+        line.push(b'\n');
+    }
+    let bt = std::backtrace::Backtrace::force_capture();
+    let _ = write!(line, "backtrace:\n{}\n", bt);
+}
+
+/// The backend loop of a [RingFile], returned by [RingFile::new_detached()] to be run
+/// on a thread of the caller's choice.
+pub struct RingFileDriver<X: MsgTransport = DefaultTransport> {
+    /// None for the noop handle
+    backend: Option<(RingFileBackend, X::Receiver<Msg>)>,
+}
+
+impl<X: MsgTransport> RingFileDriver<X> {
+    /// Process the messages until the [RingFile] is dropped or [RingFile::shutdown()],
+    /// blocks the current thread meanwhile.
+    pub fn run(self) {
+        if let Some((mut backend, rx)) = self.backend {
+            backend.run(rx);
+        }
+    }
+}
+
+/// The backend loop of a [RingFile] as a future, returned by [RingFile::spawn_on()] and
+/// [RingFileBuilder::build_task()] to be spawned on an async runtime.
+///
+/// It completes when the [RingFile] is dropped or [RingFile::shutdown()].
+pub struct RingFileTask {
+    /// None for the noop handle, and once completed
+    backend: Option<(RingFileBackend, TaskReceiver<Msg>)>,
+}
+
+/// Messages processed in one poll before yielding to the other tasks
+const TASK_BUDGET: usize = 1024;
+
+impl Future for RingFileTask {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let Some((backend, rx)) = this.backend.as_mut() else {
+            return Poll::Ready(());
+        };
+        let _guard = BackendGuard::enter();
+        let mut processed = 0;
+        loop {
+            match rx.poll_recv(cx) {
+                Poll::Ready(Some(msg)) => {
+                    if !backend.process(msg) {
+                        this.backend = None;
+                        return Poll::Ready(());
+                    }
+                    processed += 1;
+                    if processed >= TASK_BUDGET {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                }
+                Poll::Ready(None) => {
+                    this.backend = None;
+                    return Poll::Ready(());
+                }
+                Poll::Pending => {
+                    if processed > 0 {
+                        backend.flush_live_batch(Instant::now());
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+/// Result of [RingFile::dump_nowait()] to be waited.
+pub struct PendingDump {
+    /// None for the noop handle
+    rx: Option<Receiver<std::io::Result<()>>>,
+}
+
+impl PendingDump {
+    /// Block until the dump is done and return its result.
+    pub fn wait(self) -> std::io::Result<()> {
+        match self.rx {
+            Some(rx) => rx.recv().unwrap_or_else(|_| Err(backend_dead())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Connect to the first address of `addr` accepting within `timeout`, with the write timeout set.
+fn connect_tcp<A: ToSocketAddrs>(addr: A, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut last = None;
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(e) => {
+                last = Some(std::io::Error::new(e.kind(), format!("connect to {}: {}", addr, e)))
+            }
+        }
+    }
+    Err(last.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "no address to connect to")
+    }))
+}
+
+fn closed() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "RingFile is shut down")
+}
+
+/// The backend exited without shutdown(), e.g. a panic in a closure run on the backend.
+fn backend_dead() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "RingFile backend is dead")
+}
+
+/// Answer a request, see [Msg]. The channel only holds this reply, so it never blocks the
+/// backend. The caller may be gone, e.g. a dropped [PendingDump], then the reply is dropped.
+#[inline]
+fn reply<T>(res: SyncSender<T>, value: T) {
+    let r = res.try_send(value);
+    debug_assert!(
+        !matches!(r, Err(TrySendError::Full(_))),
+        "a reply was sent without a pending request"
+    );
+}
+
+impl Options {
+    /// See [RingFileBuilder::build()].
+    pub(crate) fn build<X: MsgTransport + 'static>(
+        self, transport: X,
+    ) -> std::io::Result<RingFile<X>> {
+        if cfg!(feature = "disabled") {
+            return Ok(RingFile::empty());
+        }
+        let (ring, driver) = self.assemble(&transport)?;
+        if let Some(driver) = driver {
+            *ring.th.lock().unwrap() = Some(thread::spawn(move || driver.run()));
+        }
+        Ok(ring)
+    }
+
+    /// See [RingFileBuilder::build_detached()].
+    pub(crate) fn build_detached<X: MsgTransport>(
+        self, transport: X,
+    ) -> std::io::Result<(RingFile<X>, RingFileDriver<X>)> {
+        if cfg!(feature = "disabled") {
+            return Ok((RingFile::empty(), RingFileDriver { backend: None }));
+        }
+        if self.inline {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "inline has no backend to detach",
+            ));
+        }
+        let (ring, driver) = self.assemble(&transport)?;
+        Ok((ring, driver.expect("backend")))
+    }
+
+    /// See [RingFileBuilder::build_task()].
+    pub(crate) fn build_task(self) -> std::io::Result<(RingFile, RingFileTask)> {
+        if cfg!(feature = "disabled") {
+            return Ok((RingFile::noop(), RingFileTask { backend: None }));
+        }
+        if self.inline
+            || self.dump_on_idle.is_some()
+            || self.live_flush_interval.is_some()
+            || self.mirror.is_some()
+            || self.strict_order.is_some()
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the task backend can not be combined with inline, dump_on_idle, live_flush_interval, mirror or strict_order",
+            ));
+        }
+        let (tx, rx) = task::channel();
+        let (ring, backend) = self.assemble_with(Some(Dispatch::Task(tx)))?;
+        Ok((ring, RingFileTask { backend: backend.map(|backend| (backend, rx)) }))
+    }
+
+    /// Create the handle, and the driver of the backend unless inline.
+    fn assemble<X: MsgTransport>(
+        self, transport: &X,
+    ) -> std::io::Result<(RingFile<X>, Option<RingFileDriver<X>>)> {
+        if self.inline {
+            let (ring, _) = self.assemble_with(None)?;
+            return Ok((ring, None));
+        }
+        let (tx, rx) = transport.unbounded();
+        let (ring, backend) = self.assemble_with(Some(Dispatch::Channel(tx)))?;
+        Ok((ring, backend.map(|backend| RingFileDriver { backend: Some((backend, rx)) })))
+    }
+
+    /// Create the handle sending to `tx`, and return the backend to receive.
+    /// Without `tx`, the backend is inline in the handle.
+    fn assemble_with<X: MsgTransport>(
+        self, tx: Option<Dispatch<X>>,
+    ) -> std::io::Result<(RingFile<X>, Option<RingFileBackend>)> {
+        let mut buffer = RingBuffer::alloc(self.buf_size)?;
+        if self.align > 1
+            && (!self.align.is_power_of_two()
+                || self.align > 4096
+                || buffer.capacity() % self.align != 0)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "align should be a power of two up to 4096, dividing buf_size",
+            ));
+        }
+        if self.framed && (self.align > 1 || self.spill.is_some()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "framed can not be combined with align or spill",
+            ));
+        }
+        if self.framed && self.generations > 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "framed can not be combined with generations",
+            ));
+        }
+        if self.binary_uptime && !self.framed {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "binary_uptime requires framed",
+            ));
+        }
+        buffer.set_no_split(self.no_split);
+        buffer.set_framed(self.framed);
+        buffer.set_binary_uptime(self.binary_uptime);
+        let start = if self.uptime || self.binary_uptime { Some(Instant::now()) } else { None };
+        let mut file_path = self.file_path;
+        if let Some(id) = self.run_id.as_ref() {
+            if id.is_empty()
+                || !id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "run_id should only contain ASCII letters, digits, '-' or '_'",
+                ));
+            }
+            file_path = with_run_id(&file_path, id).into();
+        }
+        for (key, value) in self.labels.iter() {
+            if key.is_empty()
+                || !key.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b))
+                || value.chars().any(|c| c.is_control() || c == ',')
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid label {:?}={:?}", key, value),
+                ));
+            }
+        }
+        if let Some((window, _)) = self.strict_order {
+            if window == 0 || self.per_thread_seq || tx.is_none() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "strict_order requires a positive window, without per_thread_seq or inline",
+                ));
+            }
+        }
+        if self.max_dumps_per.is_some_and(|(max, _)| max == 0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "max_dumps_per requires a positive max",
+            ));
+        }
+        if self.categories.0 == 0 || self.categories.1 <= 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "categories requires positive max and buf_size",
+            ));
+        }
+        if let Some((high, low)) = self.sample {
+            if high == 0 || low > high {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "adaptive_sample requires 0 < high and low <= high",
+                ));
+            }
+        }
+        let labels =
+            self.labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>();
+        if let Some(mut banner) = self.banner.clone().map(String::into_bytes) {
+            if self.binary_uptime {
+                // Stamped at the start
+                banner.splice(0..0, [0u8; STAMP_LEN].iter().copied());
+            }
+            if self.align > 1 {
+                pad_to_align(&mut banner, self.align);
+            }
+            // A framed record also takes its length prefix
+            let room = if self.framed { max_payload(buffer.capacity()) } else { buffer.capacity() };
+            if banner.len() > room {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "banner is larger than buf_size",
+                ));
+            }
+            buffer.write_all(&banner)?;
+        }
+        buffer.set_partial_dump(self.partial_dump);
+        buffer.set_dump_mode(self.dump_mode);
+        if self.prefault {
+            buffer.prefault();
+        }
+        // All the options are checked above, before any file is opened or truncated
+        let spill = match self.spill.as_ref() {
+            Some(config) => Some(config.open()?),
+            None => None,
+        };
+        let live = match self.live_file {
+            Some((path, max_size)) => Some(LiveFile::open(path, max_size)?),
+            None => None,
+        };
+        let manifest = match self.manifest {
+            Some(path) => Some(Manifest::open(
+                path.into(),
+                self.dump_mode,
+                self.run_id.as_deref(),
+                &labels.join(","),
+            )?),
+            None => None,
+        };
+        let sampler = self.sample.map(|(high, low)| Arc::new(Sampler::new(high, low)));
+        let timestamps = if self.timestamps { Some(self.timestamp_precision) } else { None };
+        let binary_start = if self.binary_uptime { start } else { None };
+        let latency = if self.timestamps || start.is_some() {
+            Some(Arc::new(Latency::default()))
+        } else {
+            None
+        };
+        let backlog =
+            if self.inline { None } else { Some(Arc::new(Backlog::new(self.max_queued_bytes))) };
+        let drops = Arc::new(Drops::new(self.on_drop));
+        let dumps_throttled = Arc::new(AtomicU64::new(0));
+        let alive = Arc::new(AtomicBool::new(true));
+        let backend = RingFileBackend {
+            file_path,
+            path_fn: self.path_fn,
+            buffer,
+            run_header: run_header(self.run_id.as_deref(), &labels),
+            stream_tags: format!(
+                "run={} labels={}",
+                self.run_id.as_deref().unwrap_or("-"),
+                if labels.is_empty() { "-".to_string() } else { labels.join(",") }
+            ),
+            sticky_banner: self.sticky_banner,
+            sticky_footer: None,
+            last_dumped: 0,
+            align: self.align,
+            binary_start,
+            uptime_start: start,
+            latency: latency.clone().map(LatencyWindows::new),
+            utf8_replace: self.utf8 == Utf8Mode::Replace,
+            max_line_len: self.max_line_len,
+            skip_empty: self.skip_empty,
+            strip_ansi: self.strip_ansi,
+            histogram: self.histogram,
+            end_marker: self.end_marker,
+            dedup: if self.dedup { Some(Dedup::default()) } else { None },
+            max_total_bytes: self.max_total_bytes,
+            frozen: false,
+            on_frozen: self.on_frozen,
+            reuse_dump_file: self.reuse_dump_file,
+            dump_handle: None,
+            manifest,
+            categories: Categories::new(self.categories.0, self.categories.1),
+            on_message: self.on_message,
+            large_writes: self.large_writes.map(|(threshold, f)| LargeWrites {
+                threshold,
+                f,
+                last: None,
+                suppressed: 0,
+            }),
+            flush_every: self.flush_every,
+            processed: 0,
+            spill,
+            live,
+            live_flush_interval: self.live_flush_interval,
+            live_flush_deadline: None,
+            mirror: self.mirror,
+            mirror_deadline: None,
+            dump_on_idle: self.dump_on_idle,
+            idle_armed: false,
+            idle_deadline: None,
+            timers: false,
+            signals: Vec::new(),
+            signal_deadline: None,
+            boost: None,
+            generations: match self.generations {
+                0 => None,
+                max => Some(Generations { max, sealed: VecDeque::with_capacity(max) }),
+            },
+            dump_limit: self.max_dumps_per.map(|(max, interval)| DumpLimit {
+                max,
+                interval,
+                recent: VecDeque::with_capacity(max),
+                throttled: dumps_throttled.clone(),
+            }),
+            reorder: self.strict_order.map(|(window, timeout)| Reorder {
+                window,
+                timeout,
+                next: 1,
+                pending: BTreeMap::new(),
+                deadline: None,
+            }),
+            lines: if self.line_mode { Some(VecDeque::new()) } else { None },
+            max_lines: 0,
+            sampler: sampler.clone(),
+            backlog: backlog.clone(),
+            drops: drops.clone(),
+            _alive: Alive(alive.clone()),
+        };
+        let (dispatch, backend) = match tx {
+            Some(tx) => (tx, Some(backend)),
+            None => (Dispatch::Inline(Box::new(Mutex::new(backend))), None),
+        };
+        let ring = RingFile {
+            tx: Some(dispatch),
+            shutdown: AtomicBool::new(false),
+            armed: AtomicBool::new(self.armed),
+            alive: Some(alive),
+            crash_hook: AtomicBool::new(false),
+            th: Mutex::new(None),
+            sampler,
+            backlog,
+            drops: Some(drops),
+            latency,
+            tcp_timeout: self.tcp_timeout,
+            order: self.strict_order.map(|_| Arc::new(AtomicU64::new(1))),
+            dumps_throttled: Some(dumps_throttled),
+            writers: Some(Writers::new()),
+            rate: Some(Rate::new()),
+            timestamps,
+            start,
+            binary_uptime: self.binary_uptime,
+            thread_names: self.thread_names,
+            per_thread_seq: self.per_thread_seq,
+            indent: self.indent,
+            utf8: self.utf8,
+            utf8_rejected: AtomicU64::new(0),
+            #[cfg(feature = "backtrace")]
+            backtrace_levels: self.backtrace_levels,
+        };
+        Ok((ring, backend))
+    }
+}
+
+impl<X: MsgTransport> Write for &RingFile<X> {
+    /// Enqueue a copy of `buf` to the backend, always accept the whole buffer
+    /// (even when sampled out), unless rejected by [Utf8Mode::Reject].
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.send_line(None, buf.to_vec())?;
+        Ok(buf.len())
+    }
+
+    /// Act as a barrier, see [RingFile::flush()].
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.try_flush()
     }
 }
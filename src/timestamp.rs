@@ -0,0 +1,316 @@
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Upper bound of the prefix length, to reserve the capacity.
+pub(crate) const MAX_PREFIX_LEN: usize = 32;
+
+/// Unit of the timestamp prefix, see
+/// [RingFileBuilder::timestamp_precision()](crate::RingFileBuilder::timestamp_precision()).
+///
+/// The prefix is the integer count of the unit since UNIX epoch followed by the unit suffix,
+/// e.g. `[1700000000123456us] `, which is much cheaper to format than a calendar date.
+/// Use [decode_timestamps()] to convert a dump to human-readable time offline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    Secs,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimestampPrecision {
+    #[inline]
+    fn suffix(self) -> &'static [u8] {
+        match self {
+            TimestampPrecision::Secs => b"s",
+            TimestampPrecision::Millis => b"ms",
+            TimestampPrecision::Micros => b"us",
+            TimestampPrecision::Nanos => b"ns",
+        }
+    }
+
+    #[inline]
+    fn nanos_per_unit(self) -> u64 {
+        match self {
+            TimestampPrecision::Secs => 1_000_000_000,
+            TimestampPrecision::Millis => 1_000_000,
+            TimestampPrecision::Micros => 1_000,
+            TimestampPrecision::Nanos => 1,
+        }
+    }
+
+    /// Number of fraction digits in the decoded form
+    #[inline]
+    fn digits(self) -> usize {
+        match self {
+            TimestampPrecision::Secs => 0,
+            TimestampPrecision::Millis => 3,
+            TimestampPrecision::Micros => 6,
+            TimestampPrecision::Nanos => 9,
+        }
+    }
+
+    fn from_suffix(suffix: &[u8]) -> Option<Self> {
+        match suffix {
+            b"s" => Some(TimestampPrecision::Secs),
+            b"ms" => Some(TimestampPrecision::Millis),
+            b"us" => Some(TimestampPrecision::Micros),
+            b"ns" => Some(TimestampPrecision::Nanos),
+            _ => None,
+        }
+    }
+}
+
+/// Append the decimal digits of `n`, without going through fmt.
+#[inline]
+fn write_u64(buf: &mut Vec<u8>, mut n: u64) {
+    let mut digits = [0u8; 20];
+    let mut i = digits.len();
+    loop {
+        i -= 1;
+        digits[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    buf.extend_from_slice(&digits[i..]);
+}
+
+/// Append the prefix `[{count}{unit}] ` of `time` since UNIX epoch.
+#[inline]
+pub(crate) fn write_timestamp(buf: &mut Vec<u8>, time: SystemTime, precision: TimestampPrecision) {
+    let nanos = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+    buf.push(b'[');
+    write_u64(buf, nanos / precision.nanos_per_unit());
+    buf.extend_from_slice(precision.suffix());
+    buf.extend_from_slice(b"] ");
+}
+
+/// Parse the prefix written by [write_timestamp()], return the time, the precision and
+/// the length of the prefix.
+fn parse_prefix(line: &[u8]) -> Option<(SystemTime, TimestampPrecision, usize)> {
+    if line.first() != Some(&b'[') {
+        return None;
+    }
+    let close = line.iter().take(MAX_PREFIX_LEN).position(|b| *b == b']')?;
+    if line.get(close + 1) != Some(&b' ') {
+        return None;
+    }
+    let inner = &line[1..close];
+    let digits = inner.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let precision = TimestampPrecision::from_suffix(&inner[digits..])?;
+    let count: u64 = std::str::from_utf8(&inner[0..digits]).ok()?.parse().ok()?;
+    let nanos = count.checked_mul(precision.nanos_per_unit())?;
+    Some((UNIX_EPOCH + Duration::from_nanos(nanos), precision, close + 2))
+}
+
+/// Parse the prefix written by [write_timestamp()], return the time and the length of the prefix.
+#[inline]
+pub(crate) fn parse_timestamp(line: &[u8]) -> Option<(SystemTime, usize)> {
+    parse_prefix(line).map(|(time, _, l)| (time, l))
+}
+
+/// Format `time` as RFC 3339 in UTC, with the fraction digits of `precision`.
+fn format_rfc3339(time: SystemTime, precision: TimestampPrecision) -> String {
+    let d = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = d.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let rem = secs % 86400;
+    let mut s = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    );
+    let digits = precision.digits();
+    if digits > 0 {
+        let frac = format!("{:09}", d.subsec_nanos());
+        s.push('.');
+        s.push_str(&frac[0..digits]);
+    }
+    s.push('Z');
+    s
+}
+
+/// Convert days since UNIX epoch to (year, month, day), by Howard Hinnant's algorithm.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Convert the compact timestamp prefix of every line in the dump `input` to RFC 3339,
+/// e.g. `[1700000000123456us] ` to `[2023-11-14T22:13:20.123456Z] `, and write to `output`.
+///
+/// Lines without the prefix are copied as is.
+///
+/// # Example:
+///
+/// ```rust
+/// # if cfg!(feature = "disabled") { return; }
+/// use ring_file::{decode_timestamps, RingFileBuilder, TimestampPrecision};
+/// use std::path::Path;
+/// let path = Path::new("/tmp/ringfile_decode.store");
+/// let ring = RingFileBuilder::new(1024, path.into())
+///     .timestamps(true)
+///     .timestamp_precision(TimestampPrecision::Millis)
+///     .build()
+///     .expect("build ok");
+/// ring.write("log message\n".to_string());
+/// ring.dump().expect("dump ok");
+/// let raw = std::fs::read_to_string(path).unwrap();
+/// assert!(raw.starts_with('[') && raw.ends_with("ms] log message\n"));
+/// std::fs::write(path, format!("{}[1700000000123ms] known\nplain\n", raw)).unwrap();
+/// let output = Path::new("/tmp/ringfile_decode.txt");
+/// decode_timestamps(path, output).expect("decode ok");
+/// let decoded = std::fs::read_to_string(output).unwrap();
+/// let lines: Vec<&str> = decoded.lines().collect();
+/// assert!(lines[0].len() == "[2023-11-14T22:13:20.123Z] log message".len());
+/// assert_eq!(lines[1], "[2023-11-14T22:13:20.123Z] known");
+/// assert_eq!(lines[2], "plain");
+/// ```
+pub fn decode_timestamps(input: &Path, output: &Path) -> std::io::Result<()> {
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer =
+        BufWriter::new(crate::buffer::create_file(output, crate::buffer::DEFAULT_MODE)?);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        match parse_prefix(&line) {
+            Some((time, precision, l)) => {
+                write!(writer, "[{}] ", format_rfc3339(time, precision))?;
+                writer.write_all(&line[l..])?;
+            }
+            None => writer.write_all(&line)?,
+        }
+    }
+    writer.flush()
+}
+
+/// Append the prefix `[+{nanos}] ` of the time elapsed since `start`.
+#[inline]
+pub(crate) fn write_uptime(buf: &mut Vec<u8>, start: Instant) {
+    write_uptime_nanos(buf, start.elapsed().as_nanos() as u64);
+}
+
+#[inline]
+fn write_uptime_nanos(buf: &mut Vec<u8>, nanos: u64) {
+    buf.extend_from_slice(b"[+");
+    write_u64(buf, nanos);
+    buf.extend_from_slice(b"] ");
+}
+
+/// Length of the stamp of [RingFileBuilder::binary_uptime()](crate::RingFileBuilder::binary_uptime())
+pub(crate) const STAMP_LEN: usize = 8;
+
+/// Append the stamp of the time elapsed since `start`: the nanoseconds as 8 bytes little endian.
+#[inline]
+pub(crate) fn write_stamp(buf: &mut Vec<u8>, start: Instant) {
+    buf.extend_from_slice(&(start.elapsed().as_nanos() as u64).to_le_bytes());
+}
+
+/// Render a record starting with the stamp of [write_stamp()] as text, the stamp becomes the
+/// prefix of [write_uptime()], after the timestamp prefix if any.
+pub(crate) fn render_stamp(record: &[u8]) -> Vec<u8> {
+    if record.len() < STAMP_LEN {
+        return record.to_vec();
+    }
+    let (stamp, rest) = record.split_at(STAMP_LEN);
+    let nanos = u64::from_le_bytes(stamp.try_into().unwrap());
+    let at = parse_timestamp(rest).map(|(_, l)| l).unwrap_or(0);
+    let mut line = Vec::with_capacity(rest.len() + MAX_PREFIX_LEN);
+    line.extend_from_slice(&rest[0..at]);
+    write_uptime_nanos(&mut line, nanos);
+    line.extend_from_slice(&rest[at..]);
+    line
+}
+
+/// Parse the prefix written by [write_uptime()], return the elapsed time and the length of the prefix.
+pub(crate) fn parse_uptime(line: &[u8]) -> Option<(Duration, usize)> {
+    if !line.starts_with(b"[+") {
+        return None;
+    }
+    let close = line.iter().position(|b| *b == b']')?;
+    if close == 2 || line.get(close + 1) != Some(&b' ') {
+        return None;
+    }
+    let digits = &line[2..close];
+    if !digits.iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos: u64 = std::str::from_utf8(digits).ok()?.parse().ok()?;
+    Some((Duration::from_nanos(nanos), close + 2))
+}
+
+/// How long ago the writer stamped a line: from the binary stamp or the uptime prefix since
+/// `start` (monotonic) when given, otherwise from the timestamp prefix.
+pub(crate) fn line_age(line: &[u8], start: Option<Instant>, binary: bool) -> Option<Duration> {
+    if let (Some(start), true) = (start, binary) {
+        let stamp = line.get(0..STAMP_LEN)?;
+        let nanos = u64::from_le_bytes(stamp.try_into().unwrap());
+        return Some(start.elapsed().saturating_sub(Duration::from_nanos(nanos)));
+    }
+    let (wall, rest) = match parse_timestamp(line) {
+        Some((time, l)) => (Some(time), &line[l..]),
+        None => (None, line),
+    };
+    if let (Some(start), Some((elapsed, _))) = (start, parse_uptime(rest)) {
+        return Some(start.elapsed().saturating_sub(elapsed));
+    }
+    wall.map(|time| SystemTime::now().duration_since(time).unwrap_or_default())
+}
+
+/// Return the time of a line in nanoseconds, from the uptime prefix if any (monotonic),
+/// otherwise from the timestamp prefix.
+pub(crate) fn line_nanos(line: &[u8]) -> Option<i128> {
+    let (wall, rest) = match parse_timestamp(line) {
+        Some((time, l)) => (Some(time), &line[l..]),
+        None => (None, line),
+    };
+    if let Some((elapsed, _)) = parse_uptime(rest) {
+        return Some(elapsed.as_nanos() as i128);
+    }
+    wall.map(|time| time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as i128)
+}
+
+/// Append the delta `+{secs}.{micros}s ` (or with `-` when the clock went back).
+pub(crate) fn write_delta(buf: &mut Vec<u8>, nanos: i128) {
+    buf.push(if nanos < 0 { b'-' } else { b'+' });
+    let nanos = nanos.unsigned_abs();
+    write_u64(buf, (nanos / 1_000_000_000) as u64);
+    buf.push(b'.');
+    let micros = format!("{:06}", nanos % 1_000_000_000 / 1_000);
+    buf.extend_from_slice(micros.as_bytes());
+    buf.extend_from_slice(b"s ");
+}
+
+/// Skip the time prefixes of a line, return the rest.
+pub(crate) fn strip_time_prefixes(mut line: &[u8]) -> &[u8] {
+    if let Some((_, l)) = parse_timestamp(line) {
+        line = &line[l..];
+    }
+    if let Some((_, l)) = parse_uptime(line) {
+        line = &line[l..];
+    }
+    line
+}
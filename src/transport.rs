@@ -0,0 +1,174 @@
+use std::sync::mpsc;
+pub use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+/// The sending side of the channel to the backend, shared by all the writers.
+pub trait MsgSender<T>: Send + Sync {
+    /// Return the message back when the receiver is gone.
+    fn send(&self, msg: T) -> Result<(), T>;
+}
+
+/// The receiving side of the channel, owned by the backend.
+pub trait MsgReceiver<T>: Send {
+    /// Block until a message arrives, return None when all the senders are gone.
+    fn recv(&self) -> Option<T>;
+
+    /// Return None when there's no message queued.
+    fn try_recv(&self) -> Option<T>;
+
+    /// Block until a message arrives or `timeout` elapsed.
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError>;
+}
+
+/// The sender and receiver pair created by [MsgTransport] `X`.
+pub type Channel<X, T> = (<X as MsgTransport>::Sender<T>, <X as MsgTransport>::Receiver<T>);
+
+/// Abstraction of the unbounded channel between the writers and the backend,
+/// so that you can use the same channel crate as the rest of your program.
+///
+/// The default is [CrossbeamTransport] (with feature `crossbeam`, enabled by default),
+/// otherwise [StdTransport]. The handles are generic over the transport, e.g.
+/// `RingFile<StdTransport>`, so the channel is called without dynamic dispatch.
+///
+/// # Example:
+///
+/// To plug another channel crate, wrap its sender and receiver:
+///
+/// ```rust
+/// use ring_file::*;
+/// use std::path::Path;
+/// use std::sync::mpsc::{self, RecvTimeoutError};
+/// use std::time::Duration;
+///
+/// struct MyTransport;
+/// struct MySender<T>(mpsc::Sender<T>);
+/// struct MyReceiver<T>(mpsc::Receiver<T>);
+///
+/// impl<T: Send> MsgSender<T> for MySender<T> {
+///     fn send(&self, msg: T) -> Result<(), T> {
+///         self.0.send(msg).map_err(|e| e.0)
+///     }
+/// }
+///
+/// impl<T: Send> MsgReceiver<T> for MyReceiver<T> {
+///     fn recv(&self) -> Option<T> {
+///         self.0.recv().ok()
+///     }
+///
+///     fn try_recv(&self) -> Option<T> {
+///         self.0.try_recv().ok()
+///     }
+///
+///     fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+///         self.0.recv_timeout(timeout)
+///     }
+/// }
+///
+/// impl MsgTransport for MyTransport {
+///     type Sender<T: Send + 'static> = MySender<T>;
+///     type Receiver<T: Send + 'static> = MyReceiver<T>;
+///
+///     fn unbounded<T: Send + 'static>(&self) -> Channel<Self, T> {
+///         let (tx, rx) = mpsc::channel();
+///         (MySender(tx), MyReceiver(rx))
+///     }
+/// }
+///
+/// let path = Path::new("/tmp/ringfile_transport.store");
+/// let ring: RingFile<MyTransport> =
+///     RingFileBuilder::new(1024, path.into()).transport(MyTransport).build().unwrap();
+/// ring.write("log message\n".to_string());
+/// ring.dump().expect("dump ok");
+/// assert_eq!(std::fs::read(path).unwrap(), b"log message\n");
+/// ```
+pub trait MsgTransport {
+    type Sender<T: Send + 'static>: MsgSender<T>;
+    type Receiver<T: Send + 'static>: MsgReceiver<T>;
+
+    fn unbounded<T: Send + 'static>(&self) -> Channel<Self, T>;
+}
+
+/// Transport with `std::sync::mpsc`, requires no extra dependency.
+pub struct StdTransport;
+
+impl<T: Send> MsgSender<T> for mpsc::Sender<T> {
+    #[inline(always)]
+    fn send(&self, msg: T) -> Result<(), T> {
+        mpsc::Sender::send(self, msg).map_err(|e| e.0)
+    }
+}
+
+impl<T: Send> MsgReceiver<T> for mpsc::Receiver<T> {
+    #[inline(always)]
+    fn recv(&self) -> Option<T> {
+        mpsc::Receiver::recv(self).ok()
+    }
+
+    #[inline(always)]
+    fn try_recv(&self) -> Option<T> {
+        mpsc::Receiver::try_recv(self).ok()
+    }
+
+    #[inline(always)]
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        mpsc::Receiver::recv_timeout(self, timeout)
+    }
+}
+
+impl MsgTransport for StdTransport {
+    type Sender<T: Send + 'static> = mpsc::Sender<T>;
+    type Receiver<T: Send + 'static> = mpsc::Receiver<T>;
+
+    fn unbounded<T: Send + 'static>(&self) -> Channel<Self, T> {
+        mpsc::channel()
+    }
+}
+
+/// Transport with `crossbeam_channel::unbounded()`, enabled with feature `crossbeam`.
+#[cfg(feature = "crossbeam")]
+pub struct CrossbeamTransport;
+
+#[cfg(feature = "crossbeam")]
+impl<T: Send> MsgSender<T> for crossbeam_channel::Sender<T> {
+    #[inline(always)]
+    fn send(&self, msg: T) -> Result<(), T> {
+        crossbeam_channel::Sender::send(self, msg).map_err(|e| e.0)
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T: Send> MsgReceiver<T> for crossbeam_channel::Receiver<T> {
+    #[inline(always)]
+    fn recv(&self) -> Option<T> {
+        crossbeam_channel::Receiver::recv(self).ok()
+    }
+
+    #[inline(always)]
+    fn try_recv(&self) -> Option<T> {
+        crossbeam_channel::Receiver::try_recv(self).ok()
+    }
+
+    #[inline(always)]
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        crossbeam_channel::Receiver::recv_timeout(self, timeout).map_err(|e| match e {
+            crossbeam_channel::RecvTimeoutError::Timeout => RecvTimeoutError::Timeout,
+            crossbeam_channel::RecvTimeoutError::Disconnected => RecvTimeoutError::Disconnected,
+        })
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl MsgTransport for CrossbeamTransport {
+    type Sender<T: Send + 'static> = crossbeam_channel::Sender<T>;
+    type Receiver<T: Send + 'static> = crossbeam_channel::Receiver<T>;
+
+    fn unbounded<T: Send + 'static>(&self) -> Channel<Self, T> {
+        crossbeam_channel::unbounded()
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+pub(crate) use CrossbeamTransport as DefaultTransport;
+
+#[cfg(not(feature = "crossbeam"))]
+pub(crate) use StdTransport as DefaultTransport;
@@ -0,0 +1,120 @@
+use crate::RingBuffer;
+use std::cell::RefCell;
+use std::io::{Result, Write};
+use std::sync::RwLock;
+
+/// Share a [RingBuffer] between one writer and many readers, e.g. for a live debug endpoint.
+///
+/// Writes take the write lock, reads take the read lock, so readers always see
+/// a consistent content but will block the writer while reading.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::{RingBuffer, RingBufferView};
+/// use std::sync::Arc;
+/// let view = Arc::new(RingBufferView::new(RingBuffer::new(64)));
+/// let mut readers = Vec::new();
+/// for _ in 0..2 {
+///     let view = view.clone();
+///     readers.push(std::thread::spawn(move || {
+///         for _ in 0..100 {
+///             let content = view.snapshot();
+///             // No torn line is observed
+///             assert!(content.len() % 8 == 0);
+///         }
+///     }));
+/// }
+/// for i in 0..1000 {
+///     view.write(format!("line {:02}\n", i % 100).as_bytes()).expect("write ok");
+/// }
+/// for th in readers {
+///     th.join().unwrap();
+/// }
+/// assert_eq!(view.snapshot().len(), 64);
+/// ```
+pub struct RingBufferView {
+    inner: RwLock<RingBuffer>,
+}
+
+impl RingBufferView {
+    pub fn new(buffer: RingBuffer) -> Self {
+        Self { inner: RwLock::new(buffer) }
+    }
+
+    /// Write the whole `buf` under the write lock.
+    pub fn write(&self, buf: &[u8]) -> Result<()> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner()).write_all(buf)
+    }
+
+    /// Copy the content in order, see [RingBuffer::read_to_vec()].
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.read(|buffer| buffer.read_to_vec())
+    }
+
+    /// Run `f` with the buffer under the read lock.
+    pub fn read<R, F: FnOnce(&RingBuffer) -> R>(&self, f: F) -> R {
+        f(&self.inner.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn into_inner(self) -> RingBuffer {
+        self.inner.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Share a [RingBuffer] by `&` or [Rc](std::rc::Rc) within one thread, without locking,
+/// e.g. embedded in a structure of a single-threaded event loop.
+///
+/// The buffer is kept in a [RefCell], so the type is not [Sync] and the compiler enforces the
+/// single-thread use. It can still be sent to another thread as a whole.
+/// Calling write() or clear() from inside the closure of read() panics, like a nested borrow.
+/// For sharing across threads, use [RingBufferView] instead.
+///
+/// # Example:
+///
+/// ```rust
+/// use ring_file::{LocalRingBuffer, RingBuffer};
+/// use std::rc::Rc;
+/// let ring = Rc::new(LocalRingBuffer::new(RingBuffer::new(16)));
+/// let (writer, reader) = (ring.clone(), ring.clone());
+/// writer.write(b"first line\n").expect("write ok");
+/// writer.write(b"second\n").expect("write ok");
+/// assert_eq!(reader.snapshot(), b"rst line\nsecond\n");
+/// reader.clear();
+/// assert!(writer.read(|buffer| buffer.is_empty()));
+/// writer.write(b"after clear\n").expect("write ok");
+/// assert_eq!(reader.snapshot(), b"after clear\n");
+/// ```
+pub struct LocalRingBuffer {
+    inner: RefCell<RingBuffer>,
+}
+
+impl LocalRingBuffer {
+    pub fn new(buffer: RingBuffer) -> Self {
+        Self { inner: RefCell::new(buffer) }
+    }
+
+    /// Write the whole `buf`.
+    pub fn write(&self, buf: &[u8]) -> Result<()> {
+        self.inner.borrow_mut().write_all(buf)
+    }
+
+    /// See [RingBuffer::clear()].
+    pub fn clear(&self) {
+        self.inner.borrow_mut().clear()
+    }
+
+    /// Copy the content in order, see [RingBuffer::read_to_vec()].
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.read(|buffer| buffer.read_to_vec())
+    }
+
+    /// Run `f` with the buffer, e.g. to dump.
+    pub fn read<R, F: FnOnce(&RingBuffer) -> R>(&self, f: F) -> R {
+        f(&self.inner.borrow())
+    }
+
+    pub fn into_inner(self) -> RingBuffer {
+        self.inner.into_inner()
+    }
+}
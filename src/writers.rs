@@ -0,0 +1,77 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Names kept by [Writers], the count goes on beyond.
+const MAX_NAMES: usize = 1024;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Id of every [Writers] the current thread is registered with, and the count of writes
+    static SEEN: RefCell<Vec<(u64, u64)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The threads that have written to a RingFile, see
+/// [RingFile::writer_thread_count()](crate::RingFile::writer_thread_count()).
+///
+/// Each thread registers once, after that a write only checks a thread-local list,
+/// which also numbers the writes of each thread for
+/// [RingFileBuilder::per_thread_seq()](crate::RingFileBuilder::per_thread_seq()).
+pub(crate) struct Writers {
+    id: u64,
+    count: AtomicUsize,
+    names: Mutex<Vec<String>>,
+}
+
+impl Writers {
+    pub(crate) fn new() -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            count: AtomicUsize::new(0),
+            names: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Called by the writer, register the current thread on its first write.
+    /// Return the sequence of this write in the current thread, from 1.
+    #[inline]
+    pub(crate) fn see(&self) -> u64 {
+        let seq = SEEN.with(|seen| {
+            let mut seen = seen.borrow_mut();
+            if let Some((_, seq)) = seen.iter_mut().find(|(id, _)| *id == self.id) {
+                *seq += 1;
+                return *seq;
+            }
+            seen.push((self.id, 1));
+            1
+        });
+        if seq == 1 {
+            self.register();
+        }
+        seq
+    }
+
+    #[cold]
+    fn register(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let mut names = self.names.lock().unwrap_or_else(|e| e.into_inner());
+        if names.len() < MAX_NAMES {
+            let current = thread::current();
+            names.push(match current.name() {
+                Some(name) => name.to_string(),
+                None => format!("{:?}", current.id()),
+            });
+        }
+    }
+
+    #[inline]
+    pub(crate) fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.names.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
@@ -0,0 +1,169 @@
+//! Minimal zip support for [RingBuffer::dump_into_zip()](crate::RingBuffer::dump_into_zip()),
+//! without extra dependency.
+//!
+//! The entries are stored without compression, and zip64 is not supported.
+
+use crate::gzip::crc32;
+use crate::timestamp::civil_from_days;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version 1.0, enough for the stored entries
+const VERSION_NEEDED: u16 = 10;
+const VERSION_MADE_BY: u16 = (3 << 8) | 20;
+const FLAG_UTF8: u16 = 0x0800;
+const MODE: u32 = 0o100644;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// The CRC32 and the size of the content of an entry.
+#[derive(Default)]
+pub(crate) struct Checksum {
+    crc: u32,
+    size: u64,
+}
+
+impl Write for Checksum {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.crc = crc32(self.crc, buf);
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Build a zip archive into `W`, with entries added by [ZipWriter::add_file()] or
+/// [RingBuffer::dump_into_zip()](crate::RingBuffer::dump_into_zip()).
+///
+/// **NOTE**: The archive is only complete after [ZipWriter::finish()].
+pub struct ZipWriter<W: Write> {
+    inner: W,
+    /// Bytes written to inner
+    offset: u64,
+    entries: Vec<Entry>,
+    time: u16,
+    date: u16,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(inner: W) -> Self {
+        let (time, date) = dos_time(SystemTime::now());
+        Self { inner, offset: 0, entries: Vec::new(), time, date }
+    }
+
+    /// Add an entry `name` holding `data`.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let mut sum = Checksum::default();
+        sum.write_all(data)?;
+        self.write_entry(name, sum, |inner| inner.write_all(data))
+    }
+
+    /// Write the local header of `name` with `sum`, then the content by `write`,
+    /// which should write the same bytes as summed.
+    pub(crate) fn write_entry<F>(&mut self, name: &str, sum: Checksum, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut W) -> Result<()>,
+    {
+        if name.is_empty() || name.len() > u16::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "zip entry name should be 1 to 65535 bytes",
+            ));
+        }
+        if self.entries.len() >= u16::MAX as usize {
+            return Err(Error::new(ErrorKind::InvalidInput, "zip archive has too many entries"));
+        }
+        let header_len = 30 + name.len() as u64;
+        if sum.size > u32::MAX as u64 || self.offset + header_len + sum.size > u32::MAX as u64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "zip archive is larger than 4GiB"));
+        }
+        let entry = Entry {
+            name: name.to_string(),
+            crc: sum.crc,
+            size: sum.size as u32,
+            offset: self.offset as u32,
+        };
+        let mut header = Vec::with_capacity(header_len as usize);
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+        self.put_common(&mut header, &entry);
+        // No extra field
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.inner.write_all(&header)?;
+        write(&mut self.inner)?;
+        self.offset += header_len + sum.size;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// The fields shared by the local header and the central directory, from the flags to the
+    /// name length.
+    fn put_common(&self, out: &mut Vec<u8>, entry: &Entry) {
+        out.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        // Stored
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&self.time.to_le_bytes());
+        out.extend_from_slice(&self.date.to_le_bytes());
+        out.extend_from_slice(&entry.crc.to_le_bytes());
+        out.extend_from_slice(&entry.size.to_le_bytes());
+        out.extend_from_slice(&entry.size.to_le_bytes());
+        out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+    }
+
+    /// Write the central directory, and return the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        let mut dir = Vec::new();
+        for entry in self.entries.iter() {
+            dir.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            dir.extend_from_slice(&VERSION_MADE_BY.to_le_bytes());
+            dir.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+            self.put_common(&mut dir, entry);
+            // No extra field, comment, disk number and internal attributes
+            dir.extend_from_slice(&[0u8; 8]);
+            dir.extend_from_slice(&(MODE << 16).to_le_bytes());
+            dir.extend_from_slice(&entry.offset.to_le_bytes());
+            dir.extend_from_slice(entry.name.as_bytes());
+        }
+        if self.offset + dir.len() as u64 > u32::MAX as u64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "zip archive is larger than 4GiB"));
+        }
+        let dir_len = dir.len() as u32;
+        let count = (self.entries.len() as u16).to_le_bytes();
+        dir.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        // Disk numbers
+        dir.extend_from_slice(&[0u8; 4]);
+        dir.extend_from_slice(&count);
+        dir.extend_from_slice(&count);
+        dir.extend_from_slice(&dir_len.to_le_bytes());
+        dir.extend_from_slice(&(self.offset as u32).to_le_bytes());
+        // No comment
+        dir.extend_from_slice(&0u16.to_le_bytes());
+        self.inner.write_all(&dir)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Convert `t` to the MS-DOS (time, date) in UTC, clamped to 1980.
+fn dos_time(t: SystemTime) -> (u16, u16) {
+    let secs = t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    if year < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let rem = secs % 86400;
+    let time = (rem / 3600) << 11 | (rem / 60 % 60) << 5 | (rem % 60 / 2);
+    let date = ((year - 1980).min(127) as u32) << 9 | month << 5 | day;
+    (time as u16, date as u16)
+}